@@ -0,0 +1,103 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use protodec_rs::parser::parser::{FullParser, Parser, PartialParser, SimpleParser};
+use protodec_rs::proto::codec::read_varint;
+use protodec_rs::proto::field::{FieldTrait, StringField};
+use protodec_rs::proto::message::{Message, MessageBuilder};
+
+/// About 1KB message: scalars, strings and two levels of nested messages
+fn sample_message() -> Message {
+    let mut builder = MessageBuilder::new("Sample");
+    for i in 0..13 {
+        let inner = MessageBuilder::new("Inner")
+            .add_int64(1, 1_600_000_000 + i)
+            .add_string(2, "nested string value")
+            .add_double(3, 0.5 * i as f64)
+            .build()
+            .unwrap();
+        let item = MessageBuilder::new("Item")
+            .add_uint32(1, i as u32)
+            .add_string(2, "item name with some text")
+            .add_embedded(3, inner)
+            .add_bytes(4, &[0x01, 0xff, 0x7f, 0x80])
+            .build()
+            .unwrap();
+        builder = builder.add_embedded(1, item);
+    }
+    builder
+        .add_string(2, "message level description text")
+        .add_fixed64(3, 0x0123_4567_89ab_cdef)
+        .build()
+        .unwrap()
+}
+
+fn bench_varint(c: &mut Criterion) {
+    let one_byte = [0x7f];
+    let ten_bytes = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+    c.bench_function("read_varint/1 byte", |b| {
+        b.iter(|| read_varint(black_box(&one_byte)))
+    });
+    c.bench_function("read_varint/10 bytes", |b| {
+        b.iter(|| read_varint(black_box(&ten_bytes)))
+    });
+}
+
+fn bench_field(c: &mut Criterion) {
+    let parser = FullParser::new();
+    let varint = [0x08, 0x96, 0x01];
+    let string = StringField::new("".to_string(), 2, "some text".to_string()).serialize();
+    c.bench_function("deserialize_field/varint", |b| {
+        b.iter(|| parser.deserialize_field(black_box(&varint)))
+    });
+    c.bench_function("deserialize_field/string", |b| {
+        b.iter(|| parser.deserialize_field(black_box(&string)))
+    });
+}
+
+fn bench_message(c: &mut Criterion) {
+    let buffer = sample_message().serialize();
+    let parser = FullParser::new();
+    c.bench_function("FullParser::deserialize/1KB", |b| {
+        b.iter(|| parser.deserialize(black_box(&buffer)))
+    });
+}
+
+/// Varint fields only, every type before the varint ones in the order is
+/// skipped by wire type
+fn bench_varint_message(c: &mut Criterion) {
+    let mut builder = MessageBuilder::new("Varints");
+    for i in 1..=64 {
+        builder = builder.add_int64(i, 1_600_000_000 + i as i64);
+    }
+    let buffer = builder.build().unwrap().serialize();
+    let full = FullParser::new();
+    let simple = SimpleParser::new();
+    c.bench_function("FullParser::deserialize/64 varints", |b| {
+        b.iter(|| full.deserialize(black_box(&buffer)))
+    });
+    c.bench_function("SimpleParser::deserialize/64 varints", |b| {
+        b.iter(|| simple.deserialize(black_box(&buffer)))
+    });
+}
+
+fn bench_map(c: &mut Criterion) {
+    let buffer = sample_message().serialize();
+    let parser = PartialParser::new();
+    let mut group = c.benchmark_group("PartialParser::deserialize_map");
+    group.sample_size(10);
+    for size in [128, 256, 512].iter() {
+        group.bench_function(format!("{} bytes", size), |b| {
+            b.iter(|| parser.deserialize_map(black_box(&buffer[..*size])))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_varint,
+    bench_field,
+    bench_message,
+    bench_varint_message,
+    bench_map
+);
+criterion_main!(benches);
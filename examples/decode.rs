@@ -0,0 +1,27 @@
+//! Decode a message given as hex arguments and print it as JSON
+//!
+//! ```text
+//! cargo run --example decode --features serde -- 08 96 01 12 02 61 62
+//! ```
+
+use protodec_rs::parser::parser::{FullParser, Parser};
+use protodec_rs::proto::utils::parse_hex;
+
+fn main() {
+    let hex = std::env::args().skip(1).collect::<Vec<String>>().join(" ");
+    let data = match parse_hex(&hex) {
+        Ok(data) => data,
+        Err(e) => {
+            println!("Unable to parse hex input: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match FullParser::new().deserialize(&data) {
+        Ok(message) => println!("{}", serde_json::to_string(&message).unwrap()),
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,15 +1,124 @@
-use clap::{crate_version, App, AppSettings, Arg};
+use clap::{crate_version, App, AppSettings, Arg, SubCommand};
 use core::str::FromStr;
 use log::LevelFilter;
+use protodec_rs::parser::analysis::Framing;
+use protodec_rs::parser::parser::DEFAULT_SCAN_LIMIT;
+use protodec_rs::proto::field::{FieldType, Radix};
+use protodec_rs::proto::message::Syntax;
+use protodec_rs::proto::utils::{parse_hex, MAX_VARINT_BYTES};
+use std::ffi::OsString;
+
+/// How decoded fields are printed
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OutputFormat {
+    /// Debug representation of every field (default)
+    Text,
+    /// Byte ranges and raw bytes next to every field
+    Annotated,
+    /// Protoscope text syntax
+    Protoscope,
+}
+
+/// Parser decoding the input
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ParserKind {
+    /// Whole input as one message, see `FullParser` (default)
+    Full,
+    /// Longest decodable prefix of the input, see `PartialParser`
+    Partial,
+    /// First suitable type of every field, see `SimpleParser`
+    Simple,
+}
+
+/// When decoded fields are colored
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ColorMode {
+    /// Color a terminal unless `NO_COLOR` is set (default)
+    Auto,
+    Always,
+    Never,
+}
+
+/// Arguments of the `encode` subcommand
+pub struct EncodeConfig {
+    /// File with protoscope text
+    pub file: String,
+    /// File to write encoded bytes to, hex is printed when not set
+    pub output: Option<String>,
+}
 
 pub struct Config {
     pub file: String,
+    /// Bytes given as hex by `--data`, decoded instead of `file`
+    pub data: Option<Vec<u8>>,
+    pub encode: Option<EncodeConfig>,
     pub verbose_level: LevelFilter,
+    pub require_full: bool,
+    pub utf8: bool,
+    pub compare_encoders: bool,
+    pub verify: bool,
+    pub format: OutputFormat,
+    pub parser: ParserKind,
+    pub schema: Option<String>,
+    pub lenient: bool,
+    pub big_endian: bool,
+    pub explain: bool,
+    pub hints: bool,
+    pub stats: bool,
+    /// Show source bytes of every top-level field
+    pub raw: bool,
+    /// Split bare input into concatenated messages
+    pub multi: bool,
+    /// Only list messages found at any offset of the input
+    pub scan: bool,
+    /// Render bytes made of varints as packed repeated fields
+    pub packed: bool,
+    /// Declare fields of `.proto` output by field number instead of parse order
+    pub sort_fields: bool,
+    /// Radix of integer values in both debug and `.proto` output
+    pub radix: Option<Radix>,
+    /// Only list strings at least this long instead of decoding output
+    pub strings: Option<usize>,
+    /// Render at most this many fields, counting embedded ones
+    pub max_fields: Option<usize>,
+    pub order: Option<Vec<FieldType>>,
+    pub syntax: Syntax,
+    pub color: ColorMode,
+    pub min_fields: usize,
+    /// Maximum count of start offsets tried by the partial scan
+    pub scan_limit: usize,
+    /// Longest accepted varint in bytes
+    pub max_varint_bytes: usize,
+    /// Largest accepted length of a length-delimited field
+    pub max_length: u64,
+    /// Distance between start offsets tried by the partial scan
+    pub stride: usize,
+    /// Indentation of one nesting level in `.proto` output
+    pub indent: String,
+    /// Name fields of `.proto` output by their inferred kind
+    pub semantic_names: bool,
+    /// Framing of the input, detected when not set
+    pub framing: Option<Framing>,
 }
 
+/// Log level of `--quiet`, per-field parse traces are logged at info
+pub const QUIET_LEVEL: LevelFilter = LevelFilter::Warn;
+
 pub fn get_config() -> Config {
+    get_config_from(std::env::args_os())
+}
+
+/// Build `Config` from command line arguments, the first one is the binary name
+fn get_config_from<I, T>(arguments: I) -> Config
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let default_scan_limit = DEFAULT_SCAN_LIMIT.to_string();
+    let default_max_varint_bytes = MAX_VARINT_BYTES.to_string();
     let app = App::new("protodec")
         .setting(AppSettings::ArgRequiredElseHelp)
+        .setting(AppSettings::SubcommandsNegateReqs)
         .version(crate_version!())
         .author("kusok <ovsyanka@protonmail.com>")
         .about("Protobuf reverse tool")
@@ -19,7 +128,14 @@ pub fn get_config() -> Config {
                 .long("file")
                 .help("File to decode")
                 .takes_value(true)
-                .required(true),
+                .required_unless("data"),
+        )
+        .arg(
+            Arg::with_name("data")
+                .long("data")
+                .help("Hex bytes to decode instead of a file, e.g. `08 96 01` or `0x08,0x96,0x01`")
+                .takes_value(true)
+                .conflicts_with("file"),
         )
         .arg(
             Arg::with_name("verbose_level")
@@ -27,15 +143,216 @@ pub fn get_config() -> Config {
                 .long("verobose_level")
                 .help("Verbose level")
                 .default_value("INFO"),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .help("Only log warnings and errors, hiding per-field parse traces"),
+        )
+        .arg(
+            Arg::with_name("require_full")
+                .long("require-full")
+                .help("Fail unless the entire buffer is parsed as one message"),
+        )
+        .arg(
+            Arg::with_name("utf8")
+                .long("utf8")
+                .help("Accept any valid UTF-8 in string fields, not only ASCII"),
+        )
+        .arg(
+            Arg::with_name("compare_encoders")
+                .long("compare-encoders")
+                .help("Print a fingerprint of encoder specific choices instead of fields"),
+        )
+        .arg(
+            Arg::with_name("verify")
+                .long("verify")
+                .help("Warn when the decoded message does not re-encode to the input"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .help("Output format of decoded fields")
+                .possible_values(&["text", "annotated", "protoscope"])
+                .default_value("text"),
+        )
+        .arg(
+            Arg::with_name("parser")
+                .long("parser")
+                .help("Parser decoding the input, partial keeps the decodable prefix")
+                .possible_values(&["full", "partial", "simple"])
+                .default_value("full"),
+        )
+        .arg(
+            Arg::with_name("schema")
+                .long("schema")
+                .help("Name and type fields using a .proto schema")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("lenient")
+                .long("lenient")
+                .help("Keep bytes that do not decode as unknown fields instead of failing"),
+        )
+        .arg(
+            Arg::with_name("big_endian")
+                .long("big-endian")
+                .help("Decode fixed32/fixed64 fields as big-endian"),
+        )
+        .arg(
+            Arg::with_name("explain")
+                .long("explain")
+                .help("Show which field types decode the first field"),
+        )
+        .arg(Arg::with_name("multi").long("multi").help(
+            "Decode bare input as several concatenated messages, split where field numbers restart",
+        ))
+        .arg(
+            Arg::with_name("packed")
+                .long("packed")
+                .help("Declare bytes fields holding two or more varints as packed repeated fields"),
+        )
+        .arg(Arg::with_name("scan").long("scan").help(
+            "Only list byte ranges of messages found at any offset, covering the most bytes first",
+        ))
+        .arg(Arg::with_name("sort_fields").long("sort-fields").help(
+            "Declare fields of .proto output by field number instead of the order they were decoded",
+        ))
+        .arg(
+            Arg::with_name("raw")
+                .long("raw")
+                .help("Show the source bytes of every field next to its decoded form"),
+        )
+        .arg(
+            Arg::with_name("stats")
+                .long("stats")
+                .help("Show counts of fields per wire type, embedded messages and raw bytes"),
+        )
+        .arg(
+            Arg::with_name("hints")
+                .long("hints")
+                .help("Annotate fields that look like timestamps or durations in text output"),
+        )
+        .arg(
+            Arg::with_name("max_fields")
+                .long("max-fields")
+                .value_name("N")
+                .help("Render at most N decoded fields, counting nested ones, omitting the .proto past them"),
+        )
+        .arg(
+            Arg::with_name("strings")
+                .long("strings")
+                .value_name("MIN_LEN")
+                .help(
+                    "List readable strings with their tag numbers instead of the decoded message",
+                ),
+        )
+        .arg(
+            Arg::with_name("order")
+                .long("order")
+                .help("Comma separated field types to try, e.g. bytes,string,message")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("syntax")
+                .long("syntax")
+                .help("Syntax of the generated .proto")
+                .possible_values(&["proto2", "proto3"])
+                .default_value("proto3"),
+        )
+        .arg(
+            Arg::with_name("radix")
+                .long("radix")
+                .help("Write integer values in hex or decimal in all output")
+                .possible_values(&["hex", "dec"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("color")
+                .long("color")
+                .help("Color decoded fields, auto respects NO_COLOR and colors only a terminal")
+                .possible_values(&["auto", "always", "never"])
+                .default_value("auto"),
+        )
+        .arg(
+            Arg::with_name("min_fields")
+                .long("min-fields")
+                .help("Only list partial parses with at least this many fields")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("max_length")
+                .long("max-length")
+                .value_name("N")
+                .help("Reject length-delimited fields declaring more than N bytes"),
+        )
+        .arg(
+            Arg::with_name("max_varint_bytes")
+                .long("max-varint-bytes")
+                .value_name("N")
+                .help("Reject keys, values and lengths encoded in more than N varint bytes, N from 1 to 10")
+                .default_value(&default_max_varint_bytes),
+        )
+        .arg(
+            Arg::with_name("scan_limit")
+                .long("scan-limit")
+                .value_name("N")
+                .help("Try at most N start offsets when listing partial parses")
+                .default_value(&default_scan_limit),
+        )
+        .arg(
+            Arg::with_name("stride")
+                .long("stride")
+                .value_name("N")
+                .help("Try only every N-th start offset when listing partial parses")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("naming")
+                .long("naming")
+                .help("Field names of .proto output, e.g. param2 or str_field_2")
+                .possible_values(&["param", "semantic"])
+                .default_value("param"),
+        )
+        .arg(
+            Arg::with_name("indent")
+                .long("indent")
+                .help("Indentation of .proto output, `tab` or a number of spaces")
+                .default_value("tab"),
+        )
+        .arg(
+            Arg::with_name("framing")
+                .long("framing")
+                .help("Whether the input is one bare message or varint length-prefixed messages")
+                .possible_values(&["bare", "varint", "auto"])
+                .default_value("bare"),
+        )
+        .subcommand(
+            SubCommand::with_name("encode")
+                .about("Encode protoscope text, e.g. `1: 150 2: {\"abc\"}`, to protobuf")
+                .arg(
+                    Arg::with_name("file")
+                        .short("f")
+                        .long("file")
+                        .help("File with protoscope text")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("File to write encoded bytes to instead of printing hex")
+                        .takes_value(true),
+                ),
         );
-    let args = app.clone().get_matches();
+    let args = app.get_matches_from(arguments);
 
-    let file = match args.value_of("file") {
-        Some(val) => val,
-        None => "",
-    };
+    let file = args.value_of("file").unwrap_or_default();
 
     let verbose = match args.value_of("verbose_level") {
+        _ if args.is_present("quiet") => QUIET_LEVEL,
         Some(val) => match LevelFilter::from_str(val) {
             Ok(v) => v,
             Err(_) => {
@@ -47,8 +364,199 @@ pub fn get_config() -> Config {
         None => LevelFilter::Info,
     };
 
+    let order = args.value_of("order").map(|val| {
+        val.split(',')
+            .map(|x| match x.trim().parse::<FieldType>() {
+                Ok(v) => v,
+                Err(e) => {
+                    println!("Unable to parse 'order' value: {}", e);
+                    std::process::exit(1);
+                }
+            })
+            .collect()
+    });
+
+    let indent = match args.value_of("indent").unwrap_or("tab") {
+        "tab" => "\t".to_string(),
+        x => match x.parse::<usize>() {
+            Ok(v) => " ".repeat(v),
+            Err(e) => {
+                println!("Unable to parse 'indent' value: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let strings = args.value_of("strings").map(|x| match x.parse::<usize>() {
+        Ok(v) => v,
+        Err(e) => {
+            println!("Unable to parse 'strings' value: {}", e);
+            std::process::exit(1);
+        }
+    });
+
+    let max_fields = args
+        .value_of("max_fields")
+        .map(|x| match x.parse::<usize>() {
+            Ok(v) => v,
+            Err(e) => {
+                println!("Unable to parse 'max-fields' value: {}", e);
+                std::process::exit(1);
+            }
+        });
+
+    let min_fields = match args.value_of("min_fields").unwrap_or("1").parse::<usize>() {
+        Ok(v) => v,
+        Err(e) => {
+            println!("Unable to parse 'min-fields' value: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let scan_limit = match args
+        .value_of("scan_limit")
+        .unwrap_or(&default_scan_limit)
+        .parse::<usize>()
+    {
+        Ok(v) => v,
+        Err(e) => {
+            println!("Unable to parse 'scan-limit' value: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let max_varint_bytes = match args
+        .value_of("max_varint_bytes")
+        .unwrap_or(&default_max_varint_bytes)
+        .parse::<usize>()
+    {
+        Ok(v) if (1..=MAX_VARINT_BYTES).contains(&v) => v,
+        Ok(v) => {
+            println!(
+                "Unable to parse 'max-varint-bytes' value: {} is not from 1 to {}",
+                v, MAX_VARINT_BYTES
+            );
+            std::process::exit(1);
+        }
+        Err(e) => {
+            println!("Unable to parse 'max-varint-bytes' value: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let max_length = match args.value_of("max_length").map(|x| x.parse::<u64>()) {
+        None => u64::MAX,
+        Some(Ok(v)) => v,
+        Some(Err(e)) => {
+            println!("Unable to parse 'max-length' value: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let stride = match args.value_of("stride").unwrap_or("1").parse::<usize>() {
+        Ok(v) if v > 0 => v,
+        Ok(_) => {
+            println!("Unable to parse 'stride' value: must be positive");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            println!("Unable to parse 'stride' value: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let data = match args.value_of("data").map(parse_hex) {
+        Some(Ok(data)) => Some(data),
+        Some(Err(e)) => {
+            println!("Unable to parse 'data' value: {}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+
+    let encode = args.subcommand_matches("encode").map(|x| EncodeConfig {
+        file: x.value_of("file").unwrap_or_default().to_string(),
+        output: x.value_of("output").map(|x| x.to_string()),
+    });
+
     Config {
         file: file.to_string(),
+        data,
+        encode,
         verbose_level: verbose,
+        require_full: args.is_present("require_full"),
+        utf8: args.is_present("utf8"),
+        compare_encoders: args.is_present("compare_encoders"),
+        verify: args.is_present("verify"),
+        format: match args.value_of("format") {
+            Some("annotated") => OutputFormat::Annotated,
+            Some("protoscope") => OutputFormat::Protoscope,
+            _ => OutputFormat::Text,
+        },
+        parser: match args.value_of("parser") {
+            Some("partial") => ParserKind::Partial,
+            Some("simple") => ParserKind::Simple,
+            _ => ParserKind::Full,
+        },
+        schema: args.value_of("schema").map(|x| x.to_string()),
+        lenient: args.is_present("lenient"),
+        big_endian: args.is_present("big_endian"),
+        explain: args.is_present("explain"),
+        hints: args.is_present("hints"),
+        stats: args.is_present("stats"),
+        raw: args.is_present("raw"),
+        multi: args.is_present("multi"),
+        scan: args.is_present("scan"),
+        packed: args.is_present("packed"),
+        sort_fields: args.is_present("sort_fields"),
+        radix: match args.value_of("radix") {
+            Some("hex") => Some(Radix::Hex),
+            Some("dec") => Some(Radix::Dec),
+            _ => None,
+        },
+        strings,
+        max_fields,
+        order,
+        syntax: match args.value_of("syntax") {
+            Some("proto2") => Syntax::Proto2,
+            _ => Syntax::Proto3,
+        },
+        min_fields,
+        scan_limit,
+        max_varint_bytes,
+        max_length,
+        stride,
+        indent,
+        semantic_names: args.value_of("naming") == Some("semantic"),
+        framing: match args.value_of("framing") {
+            Some("varint") => Some(Framing::LengthPrefixed),
+            Some("auto") => None,
+            _ => Some(Framing::Bare),
+        },
+        color: match args.value_of("color") {
+            Some("always") => ColorMode::Always,
+            Some("never") => ColorMode::Never,
+            _ => ColorMode::Auto,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_quiet_level() {
+        let config = get_config_from(["protodec", "--data", "089601"]);
+        assert_eq!(config.verbose_level, LevelFilter::Info);
+
+        let config = get_config_from(["protodec", "-q", "--data", "089601"]);
+        assert_eq!(config.verbose_level, QUIET_LEVEL);
+
+        // Quiet wins over an explicit verbose level
+        let config = get_config_from(["protodec", "-V", "debug", "-q", "--data", "089601"]);
+        assert_eq!(config.verbose_level, QUIET_LEVEL);
+        let config = get_config_from(["protodec", "-V", "debug", "--data", "089601"]);
+        assert_eq!(config.verbose_level, LevelFilter::Debug);
     }
 }
@@ -1,16 +1,24 @@
-pub mod args;
-pub mod parser;
-pub mod proto;
+mod args;
+mod render;
 
 use log::LevelFilter;
 use log4rs::append::console::ConsoleAppender;
 use log4rs::config::{Appender, Config, Root};
 
-use args::get_config;
-use parser::parser::{FullParser, Parser, PartialParser};
+use args::{get_config, EncodeConfig, OutputFormat, ParserKind};
+use protodec_rs::parser::analysis::{
+    annotate, detect_framing, encoder_fingerprint, scan, scan_summary, split_frames,
+    split_messages, suggest_boundaries, FrameReader, Framing,
+};
+use protodec_rs::parser::parser::{FullParser, ParseStats, Parser, PartialParser, SimpleParser};
+use protodec_rs::proto::field::{param_name, semantic_name, Endianness, RenderOptions, StringMode};
+use protodec_rs::proto::protoscope;
+use protodec_rs::proto::schema::Schema;
+use render::{color_enabled, is_truncated, limit_lines, render_fields, render_protoscope, Notes};
 
 use std::fs::File;
 use std::io::Read;
+use std::sync::Arc;
 
 fn init_log(
     // logfile: &str,
@@ -38,18 +46,28 @@ fn init_log(
     Ok(())
 }
 
-fn test() {
-    let buffer = [
-        0x76, 0x2e, 0x30, 0x3a, 0xae, 0xcc, 0x04, 0x2b, 0x5a, 0xc5, 0x0d, 0x80, 0xea, 0x36, 0x4e,
-        0xd8, 0x41, 0xf8, 0xfc, 0x7a, 0xb3, 0x59, 0x91, 0x9f, 0xea, 0xd9, 0xc9, 0xfa, 0x31, 0xd8,
-        0x94, 0xa9, 0xdd, 0xd8, 0xd9, 0xd8, 0x9d, 0x7d, 0xb5, 0x7d, 0x55, 0x40, 0x1b, 0x31, 0xe5,
-        0x1a, 0x16, 0x9f, 0xa1, 0x1c, 0x33, 0x34, 0x58, 0x58, 0xec, 0x97, 0x31, 0x18, 0x70, 0x71,
-        0xf6, 0x4d, 0xd7, 0x11, 0xda, 0x48, 0x3c, 0xb2, 0x04, 0x0f,
-    ];
-    let deserializer = FullParser::new();
-    let message = deserializer.deserialize(&buffer).unwrap();
-
-    println!("{:?}", message);
+/// Encode protoscope text and write or print the bytes
+fn encode(config: &EncodeConfig) {
+    let text = std::fs::read_to_string(&config.file).expect("Failed to read protoscope text");
+    let message = match protoscope::parse(&text) {
+        Ok(message) => message,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let data = message.serialize();
+
+    match &config.output {
+        Some(path) => std::fs::write(path, &data).expect("Failed to write data"),
+        None => println!(
+            "{}",
+            data.iter()
+                .map(|x| format!("{:02x}", x))
+                .collect::<Vec<String>>()
+                .join("")
+        ),
+    }
 }
 
 fn main() {
@@ -58,25 +76,274 @@ fn main() {
 
     init_log(config.verbose_level).unwrap();
 
-    // test();
+    if let Some(encode_config) = &config.encode {
+        encode(encode_config);
+        return;
+    }
+
+    let string_mode = match config.utf8 {
+        true => StringMode::Utf8,
+        false => StringMode::Ascii,
+    };
+    let endianness = match config.big_endian {
+        true => Endianness::Big,
+        false => Endianness::Little,
+    };
+    let mut parser = FullParser::new()
+        .with_require_full(config.require_full)
+        .with_lenient(config.lenient)
+        .with_string_mode(string_mode)
+        .with_endianness(endianness)
+        .with_max_varint_bytes(config.max_varint_bytes)
+        .with_max_length(config.max_length)
+        .with_syntax(config.syntax);
+    let mut deserializer = PartialParser::new()
+        .with_min_fields(config.min_fields)
+        .with_scan_limit(config.scan_limit)
+        .with_stride(config.stride)
+        .with_max_varint_bytes(config.max_varint_bytes)
+        .with_max_length(config.max_length)
+        .with_string_mode(string_mode)
+        .with_endianness(endianness)
+        .with_syntax(config.syntax);
+    let mut simple = SimpleParser::new()
+        .with_lenient(config.lenient)
+        .with_string_mode(string_mode)
+        .with_endianness(endianness)
+        .with_max_varint_bytes(config.max_varint_bytes)
+        .with_max_length(config.max_length)
+        .with_syntax(config.syntax);
+    if let Some(order) = &config.order {
+        parser = parser.with_order(order);
+        deserializer = deserializer.with_order(order);
+        simple = simple.with_order(order);
+    }
+    let selected: &dyn Parser = match config.parser {
+        ParserKind::Full => &parser,
+        ParserKind::Partial => &deserializer,
+        ParserKind::Simple => &simple,
+    };
+    let mut f: Box<dyn Read> = match &config.data {
+        Some(data) => Box::new(std::io::Cursor::new(data.clone())),
+        None => Box::new(File::open(&config.file).expect("Something went wrong reading the file")),
+    };
 
-    let mut f = File::open(config.file).expect("Something went wrong reading the file");
+    // Explicitly framed input is decoded while reading, one frame at a time
+    if config.framing == Some(Framing::LengthPrefixed) && !config.compare_encoders {
+        for frame in FrameReader::new(std::io::BufReader::new(f)) {
+            match frame {
+                Ok((start, payload)) => {
+                    println!("// frame [{:#x}..{:#x}]", start, start + payload.len());
+                    decode(&config, &parser, &deserializer, selected, &payload);
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        return;
+    }
     f.read_to_end(&mut data).expect("Failed to read data");
 
-    let parser = FullParser::new();
-    let message = parser.deserialize(&data).unwrap();
-    for field in message.fields.iter() {
-        // let b: &StringField = match field.as_any().downcast_ref::<StringField>() {
-        //     Some(b) => b,
-        //     None => panic!("&a isn't a B!"),
-        // };
-        println!("{}", field.repr());
+    if config.scan {
+        if deserializer.scan_truncated(&data) {
+            println!(
+                "// note: only the first {} start offsets scanned, see --scan-limit",
+                config.scan_limit
+            );
+        }
+        for (start, end, message) in scan(&deserializer, &data) {
+            println!("[{:#x}..{:#x}] {}", start, end, scan_summary(&message));
+        }
+        return;
+    }
+
+    if config.compare_encoders {
+        match encoder_fingerprint(&parser, &data) {
+            Ok(fingerprint) => println!("{}", fingerprint),
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let framing = match config.framing {
+        Some(framing) => framing,
+        None => detect_framing(&data),
+    };
+    match framing {
+        Framing::Bare if config.multi => {
+            let messages = match split_messages(&parser, &data) {
+                Ok(messages) => messages,
+                Err(e) => {
+                    println!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            for (start, end) in messages {
+                println!("// message [{:#x}..{:#x}]", start, end);
+                decode(&config, &parser, &deserializer, selected, &data[start..end]);
+            }
+        }
+        Framing::Bare => decode(&config, &parser, &deserializer, selected, &data),
+        Framing::LengthPrefixed => {
+            let frames = match split_frames(&data) {
+                Ok(frames) => frames,
+                Err(e) => {
+                    println!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            for (start, end) in frames {
+                println!("// frame [{:#x}..{:#x}]", start, end);
+                decode(&config, &parser, &deserializer, selected, &data[start..end]);
+            }
+        }
+    }
+}
+
+/// Decode one message and print it in the configured format
+///
+/// `selected` decodes the message, `parser` and `deserializer` back the
+/// analyses printed along with it.
+fn decode(
+    config: &args::Config,
+    parser: &FullParser,
+    deserializer: &PartialParser,
+    selected: &dyn Parser,
+    data: &[u8],
+) {
+    if data.is_empty() {
+        println!("// empty message");
+        return;
+    }
+
+    if config.explain {
+        for (field_type, result) in parser.classify(data) {
+            match result {
+                Ok(readed) => println!(
+                    "// explain: {} consumes {} bytes",
+                    field_type.proto_type(),
+                    readed
+                ),
+                Err(e) => println!("// explain: {} fails: {}", field_type.proto_type(), e),
+            }
+        }
     }
 
-    let deserializer = PartialParser::new();
-    let map = deserializer.deserialize_map(&data);
+    let (mut message, spans) = match selected.deserialize_with_spans(data) {
+        Ok(x) => x,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if config.stats {
+        println!("// stats: {}", ParseStats::of(&message));
+    }
+    let boundaries = suggest_boundaries(&message.fields, &spans);
+
+    if let Some(path) = &config.schema {
+        let text = std::fs::read_to_string(path).expect("Failed to read schema");
+        let schema = match Schema::parse(&text) {
+            Ok(schema) => schema,
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        for warning in schema.apply(&mut message) {
+            println!("// warning: {}", warning);
+        }
+    }
+    if let Some(min_len) = config.strings {
+        for (number, text) in message.find_strings(min_len) {
+            println!("{}: {:?}", number, text);
+        }
+        return;
+    }
+
+    message.collapse_repeated();
+    let options = RenderOptions {
+        indent: config.indent.clone(),
+        naming: match config.semantic_names {
+            true => Arc::new(semantic_name),
+            false => Arc::new(param_name),
+        },
+        detect_packed: config.packed,
+        sorted: config.sort_fields,
+        radix: config.radix,
+        ..RenderOptions::default()
+    };
+    let color = color_enabled(config.color);
+    colored::control::set_override(color);
+    let notes = Notes {
+        hints: config.hints,
+        raw: match config.raw {
+            true => Some((data, &spans)),
+            false => None,
+        },
+    };
+    match config.format {
+        OutputFormat::Text => {
+            for line in render_fields(&message.fields, &options, color, &notes, config.max_fields) {
+                println!("{}", line);
+            }
+        }
+        OutputFormat::Annotated => match annotate(parser, data) {
+            Ok(lines) => limit_lines(lines, config.max_fields)
+                .iter()
+                .for_each(|x| println!("{}", x)),
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        OutputFormat::Protoscope => {
+            for line in render_protoscope(&message.fields, config.max_fields) {
+                println!("{}", line);
+            }
+        }
+    }
+
+    match is_truncated(&message.fields, config.max_fields) {
+        true => println!("\n// ... (.proto definition omitted, see --max-fields)\n"),
+        false => println!("\n{}\n", message.to_proto_file_with(&options)),
+    }
+
+    if config.verify && !message.reencode_matches(data) {
+        println!("// warning: decoded message does not re-encode to the input bytes");
+    }
+
+    for offset in boundaries {
+        println!(
+            "// note: field numbers restart at {:#x}, possibly a concatenated message",
+            offset
+        );
+    }
+
+    if deserializer.scan_truncated(data) {
+        println!(
+            "// note: partial parses listed only for the first {} start offsets, see --scan-limit",
+            config.scan_limit
+        );
+    }
+    let map = deserializer.deserialize_map(data);
 
     for (bounds, value) in map.iter() {
-        println!("data[{:x}:{:x}] - {:?}", bounds.0, bounds.1, value);
+        println!("data[{:x}:{:x}] - Message", bounds.0, bounds.1);
+        for line in render_fields(
+            &value.fields,
+            &options,
+            color,
+            &Notes::default(),
+            config.max_fields,
+        ) {
+            println!("{}", line);
+        }
+        println!();
     }
 }
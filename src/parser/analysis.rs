@@ -0,0 +1,855 @@
+use crate::parser::parser::{FullParser, Parser, PartialParser, Span};
+use crate::proto::codec::{is_overlong_varint, read_varint};
+use crate::proto::error::{Error, Result};
+use crate::proto::field::{
+    EmbeddedField, FieldTrait, FieldType, Fixed32Field, Fixed64Field, Int32Field, Int64Field,
+    SFixed32Field, SFixed64Field, SInt32Field, SInt64Field, UInt32Field, UInt64Field,
+    VariantTypeRaw,
+};
+use crate::proto::message::Message;
+use crate::proto::utils::*;
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::io::Read;
+
+/// Suggest offsets where a new top-level message likely starts
+///
+/// Two messages of the same schema concatenated without framing decode as one
+/// message whose field numbers "restart" (e.g. `1, 2, 5, 1, 2`). A boundary is
+/// suggested when a field number drops below the previous one and is not greater
+/// than the first field number of the current message.
+///
+/// `fields` and `spans` are the result of `Parser::deserialize_with_spans`, so
+/// the input is not decoded again.
+pub fn suggest_boundaries(fields: &[Box<dyn FieldTrait>], spans: &[Span]) -> Vec<usize> {
+    // Entries of a detected map are separate fields on the wire, a group
+    // counts as one field up to its end
+    let mut starts: Vec<(usize, u64)> = Vec::new();
+    let mut groups: usize = 0;
+    for (field, span) in fields.iter().zip(spans) {
+        match field.field_type() {
+            FieldType::EndGroup => groups = groups.saturating_sub(1),
+            _ if groups > 0 => {}
+            FieldType::Map => starts.extend(span.fields.iter().map(|x| (x.start, field.number()))),
+            _ => starts.push((span.start, field.number())),
+        }
+        if field.field_type() == FieldType::StartGroup {
+            groups += 1;
+        }
+    }
+    starts.sort_by_key(|&(start, _)| start);
+
+    let mut boundaries = Vec::new();
+    let mut first: Option<u64> = None;
+    let mut previous: Option<u64> = None;
+    for (index, number) in starts {
+        if let (Some(first_number), Some(previous_number)) = (first, previous) {
+            if number < previous_number && number <= first_number {
+                log::debug!(
+                    "Analysis: field numbers restart at {:#x} ({} -> {})",
+                    index,
+                    previous_number,
+                    number
+                );
+                boundaries.push(index);
+                first = Some(number);
+            }
+        } else {
+            first = Some(number);
+        }
+        previous = Some(number);
+    }
+
+    boundaries
+}
+
+/// Split bare messages concatenated without framing at `suggest_boundaries`
+///
+/// Returns `start..end` ranges covering `into`, a single range when no boundary
+/// is found.
+pub fn split_messages(parser: &FullParser, into: &[u8]) -> Result<Vec<(usize, usize)>> {
+    let (message, spans) = parser.deserialize_with_spans(into)?;
+    let mut starts = vec![0];
+    starts.extend(suggest_boundaries(&message.fields, &spans));
+    let ends = starts
+        .iter()
+        .skip(1)
+        .copied()
+        .chain(std::iter::once(into.len()));
+    Ok(starts.iter().copied().zip(ends).collect())
+}
+
+/// Deserialize every message of a stream of bare concatenated messages
+pub fn deserialize_multi(parser: &FullParser, into: &[u8]) -> Result<Vec<Message>> {
+    split_messages(parser, into)?
+        .into_iter()
+        .map(|(start, end)| parser.deserialize(&into[start..end]))
+        .collect()
+}
+
+/// Encoder specific choices collected by `encoder_fingerprint`
+#[derive(Default)]
+struct EncoderChoices {
+    unsorted: bool,
+    non_minimal_varints: usize,
+    present_defaults: usize,
+}
+
+/// Collect choices of one message, returns its field order
+fn collect_encoder_choices(
+    parser: &FullParser,
+    into: &[u8],
+    choices: &mut EncoderChoices,
+) -> Result<String> {
+    let mut order = Vec::new();
+    let mut previous = 0;
+    let mut index: usize = 0;
+
+    while index != into.len() {
+        let data = &into[index..];
+        let (field, readed) = parser.deserialize_field(data)?;
+        let (key, key_len) = read_varint(data)?;
+        let (number, wire_type) = parse_key(key);
+        let payload = &data[key_len as usize..readed as usize];
+
+        if is_overlong_varint(key, key_len) {
+            choices.non_minimal_varints += 1;
+        }
+        if number < previous {
+            choices.unsorted = true;
+        }
+        previous = number;
+
+        let mut entry = number.to_string();
+        match VariantTypeRaw::from(wire_type) {
+            VariantTypeRaw::Varint => {
+                let (value, value_len) = read_varint(payload)?;
+                if is_overlong_varint(value, value_len) {
+                    choices.non_minimal_varints += 1;
+                }
+                if value == 0 {
+                    choices.present_defaults += 1;
+                }
+            }
+            VariantTypeRaw::Buffer => {
+                let (size, size_len) = read_varint(payload)?;
+                if is_overlong_varint(size, size_len) {
+                    choices.non_minimal_varints += 1;
+                }
+                if size == 0 {
+                    choices.present_defaults += 1;
+                } else if field.field_type() == FieldType::Embedded {
+                    let nested =
+                        collect_encoder_choices(parser, &payload[size_len as usize..], choices)?;
+                    entry = format!("{}{{{}}}", entry, nested);
+                }
+            }
+            VariantTypeRaw::Double | VariantTypeRaw::Float if payload.iter().all(|&x| x == 0) => {
+                choices.present_defaults += 1;
+            }
+            _ => {}
+        }
+        order.push(entry);
+        index += readed as usize;
+    }
+
+    Ok(order.join(","))
+}
+
+/// Describe encoder specific choices of a buffer as a fingerprint string
+///
+/// Two encoders producing the same logical message may differ in field order,
+/// varint minimality and whether fields with default values are written. The
+/// fingerprint lists the field order (nested messages in braces), whether every
+/// message is sorted by field number, the count of non-minimal varints and the
+/// count of fields holding a default value.
+pub fn encoder_fingerprint(parser: &FullParser, into: &[u8]) -> Result<String> {
+    let mut choices = EncoderChoices::default();
+    let order = collect_encoder_choices(parser, into, &mut choices)?;
+
+    Ok(format!(
+        "order={} sorted={} non_minimal_varints={} present_defaults={}",
+        order, !choices.unsorted, choices.non_minimal_varints, choices.present_defaults
+    ))
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|x| format!("{:02X}", x))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Annotate fields of one message, `offset` is the absolute offset of `into`
+fn annotate_at(
+    parser: &FullParser,
+    into: &[u8],
+    offset: usize,
+    depth: usize,
+    lines: &mut Vec<String>,
+) -> Result<()> {
+    let mut index: usize = 0;
+
+    while index != into.len() {
+        let data = &into[index..];
+        let (field, readed) = parser.deserialize_field(data)?;
+        let (key, key_len) = read_varint(data)?;
+        let (number, wire_type) = parse_key(key);
+        let start = offset + index;
+
+        let (value, header_len) = match field.field_type() {
+            FieldType::Embedded => {
+                let (_, size_len) = read_varint(&data[key_len as usize..])?;
+                ("message".to_string(), (key_len + size_len) as usize)
+            }
+            _ => (field.example(), readed as usize),
+        };
+        lines.push(format!(
+            "{}[{:#04x}..{:#04x}] field {} ({}) = {}  // {}",
+            "\t".repeat(depth),
+            start,
+            start + readed as usize,
+            number,
+            VariantTypeRaw::from(wire_type).wire_name(),
+            value,
+            hex(&data[..header_len])
+        ));
+
+        if field.field_type() == FieldType::Embedded {
+            annotate_at(
+                parser,
+                &data[header_len..readed as usize],
+                start + header_len,
+                depth + 1,
+                lines,
+            )?;
+        }
+        index += readed as usize;
+    }
+
+    Ok(())
+}
+
+/// Describe every field with its absolute byte range and raw bytes
+///
+/// Each line looks like `[0x00..0x03] field 1 (varint) = 150  // 08 96 01`.
+/// Fields of embedded messages follow their parent indented by one tab, the raw
+/// bytes of an embedded field are only its key and length.
+pub fn annotate(parser: &FullParser, into: &[u8]) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    annotate_at(parser, into, 0, 0, &mut lines)?;
+    Ok(lines)
+}
+
+/// How messages are delimited in a buffer
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Framing {
+    /// A single message without prefix
+    Bare,
+    /// Messages each prefixed with its length as a varint
+    LengthPrefixed,
+}
+
+/// Split a buffer of varint length-prefixed messages
+///
+/// Returns (start, end) of every message payload
+pub fn split_frames(into: &[u8]) -> Result<Vec<(usize, usize)>> {
+    let mut frames = Vec::new();
+    let mut index: usize = 0;
+
+    while index != into.len() {
+        let (size, size_len) = read_varint(&into[index..])?;
+        let start = index + size_len as usize;
+        let remaining = (into.len() - start) as u64;
+        if size > remaining {
+            return Err(Error::truncated(size, remaining));
+        }
+        index = start + size as usize;
+        frames.push((start, index));
+    }
+
+    Ok(frames)
+}
+
+/// Reads varint length-prefixed messages from a stream one frame at a time
+///
+/// Unlike `split_frames` the input is never buffered whole, only the current
+/// frame is held in memory. Yields (offset, payload) where offset is the
+/// position of the payload in the stream. Nothing is yielded after an error,
+/// the stream position of the next frame is unknown.
+pub struct FrameReader<R> {
+    reader: R,
+    offset: usize,
+    /// Set once the stream ended or failed
+    done: bool,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(reader: R) -> Self {
+        FrameReader {
+            reader,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Read one byte, `None` at the end of the stream
+    fn read_byte(&mut self) -> Result<Option<u8>> {
+        let mut byte = [0u8];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => {
+                    self.offset += 1;
+                    return Ok(Some(byte[0]));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(Error::with_source("failed to read frame", None, e)),
+            }
+        }
+    }
+
+    /// Read the length prefix, `None` when the stream ends before a frame
+    fn read_size(&mut self) -> Result<Option<u64>> {
+        let mut prefix = Vec::new();
+        while prefix.len() < MAX_VARINT_BYTES {
+            match self.read_byte()? {
+                Some(byte) => {
+                    prefix.push(byte);
+                    if byte >> 7 == 0 {
+                        break;
+                    }
+                }
+                None if prefix.is_empty() => return Ok(None),
+                None => break,
+            }
+        }
+        read_varint(&prefix).map(|(size, _)| Some(size))
+    }
+
+    fn read_frame(&mut self) -> Result<Option<(usize, Vec<u8>)>> {
+        let size = match self.read_size()? {
+            Some(size) => size,
+            None => return Ok(None),
+        };
+        let start = self.offset;
+        // Read through `take`, an absurd size must not allocate upfront
+        let mut payload = Vec::new();
+        (&mut self.reader)
+            .take(size)
+            .read_to_end(&mut payload)
+            .map_err(|e| Error::with_source("failed to read frame", None, e))?;
+        self.offset += payload.len();
+        if (payload.len() as u64) < size {
+            return Err(Error::truncated(size, payload.len() as u64));
+        }
+        Ok(Some((start, payload)))
+    }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = Result<(usize, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let frame = self.read_frame().transpose();
+        self.done = !matches!(frame, Some(Ok(_)));
+        frame
+    }
+}
+
+impl<R: Read> std::iter::FusedIterator for FrameReader<R> {}
+
+/// Guess whether a buffer is a bare message or length-prefixed messages
+///
+/// The buffer is length-prefixed when reading varint lengths one after another
+/// ends exactly at the end of the buffer and every frame decodes as a whole
+/// message. A buffer decoding entirely as one message is bare even then, the
+/// prefix of a frame rarely doubles as a key.
+pub fn detect_framing(into: &[u8]) -> Framing {
+    let parser = FullParser::new().with_require_full(true);
+    let frames = match split_frames(into) {
+        Ok(frames) if !frames.is_empty() => frames,
+        _ => return Framing::Bare,
+    };
+    if parser.deserialize(into).is_ok() {
+        return Framing::Bare;
+    }
+
+    match frames
+        .iter()
+        .all(|&(start, end)| parser.deserialize(&into[start..end]).is_ok())
+    {
+        true => Framing::LengthPrefixed,
+        false => Framing::Bare,
+    }
+}
+
+/// Unix seconds of 2000-01-01T00:00:00Z
+const TIMESTAMP_MIN: i64 = 946_684_800;
+/// Unix seconds of 2100-01-01T00:00:00Z
+const TIMESTAMP_MAX: i64 = 4_102_444_800;
+
+/// Format Unix time as RFC 3339 UTC, `fraction` digits of `nanos` are shown
+fn format_timestamp(seconds: i64, nanos: i64, fraction: usize) -> String {
+    // Days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = seconds.div_euclid(86_400);
+    let time = seconds.rem_euclid(86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    let fraction = match fraction {
+        0 => String::new(),
+        _ => format!(".{:09}", nanos)[..fraction + 1].to_string(),
+    };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        fraction
+    )
+}
+
+/// Describe `value` as a timestamp when it falls within 2000..2100 read as
+/// Unix seconds, milliseconds or microseconds
+pub fn timestamp_hint(value: i64) -> Option<String> {
+    [(1, 0), (1_000, 3), (1_000_000, 6)]
+        .iter()
+        .find(|(scale, _)| (TIMESTAMP_MIN..TIMESTAMP_MAX).contains(&(value / scale)))
+        .map(|&(scale, fraction)| {
+            let nanos = value % scale * (1_000_000_000 / scale);
+            format!(
+                "maybe timestamp: {}",
+                format_timestamp(value / scale, nanos, fraction)
+            )
+        })
+}
+
+/// Longest `google.protobuf.Duration`, 10000 years in seconds
+const DURATION_MAX: i64 = 315_576_000_000;
+
+/// Integer value of an integer field, read from its typed data
+fn integer_value(field: &dyn FieldTrait) -> Option<i64> {
+    let any = field.as_any_ref();
+    any.downcast_ref::<Int32Field>()
+        .map(|x| i64::from(x.0.data))
+        .or_else(|| any.downcast_ref::<Int64Field>().map(|x| x.0.data))
+        .or_else(|| {
+            any.downcast_ref::<UInt32Field>()
+                .map(|x| i64::from(x.0.data))
+        })
+        .or_else(|| {
+            any.downcast_ref::<UInt64Field>()
+                .and_then(|x| i64::try_from(x.0.data).ok())
+        })
+        .or_else(|| {
+            any.downcast_ref::<SInt32Field>()
+                .map(|x| i64::from(x.0.data))
+        })
+        .or_else(|| any.downcast_ref::<SInt64Field>().map(|x| x.0.data))
+        .or_else(|| {
+            any.downcast_ref::<Fixed32Field>()
+                .map(|x| i64::from(x.0.data))
+        })
+        .or_else(|| {
+            any.downcast_ref::<SFixed32Field>()
+                .map(|x| i64::from(x.0.data))
+        })
+        .or_else(|| any.downcast_ref::<Fixed64Field>().map(|x| x.0.data))
+        .or_else(|| any.downcast_ref::<SFixed64Field>().map(|x| x.0.data))
+}
+
+/// Integer value of a varint field
+fn varint_value(field: &dyn FieldTrait) -> Option<i64> {
+    match field.field_type() {
+        FieldType::Int32 | FieldType::Int64 | FieldType::UInt32 | FieldType::UInt64 => {
+            integer_value(field)
+        }
+        _ => None,
+    }
+}
+
+/// Describe `fields` shaped like `google.protobuf.Timestamp` or `Duration`:
+/// field 1 = seconds and optionally field 2 = nanos
+///
+/// Timestamps must fall within 2000..2100. Durations need both fields with the
+/// same sign, a lone small integer is too common to call a duration.
+pub fn well_known_type(fields: &[Box<dyn FieldTrait>]) -> Option<String> {
+    let (seconds, nanos) = match fields {
+        [seconds] if seconds.number() == 1 => (varint_value(seconds.as_ref())?, None),
+        [seconds, nanos] if seconds.number() == 1 && nanos.number() == 2 => (
+            varint_value(seconds.as_ref())?,
+            Some(varint_value(nanos.as_ref())?),
+        ),
+        _ => return None,
+    };
+
+    if (TIMESTAMP_MIN..TIMESTAMP_MAX).contains(&seconds)
+        && (0..1_000_000_000).contains(&nanos.unwrap_or(0))
+    {
+        let nanos = nanos.unwrap_or(0);
+        return Some(format!(
+            "Timestamp {{ seconds: {}, nanos: {} }} // {}",
+            seconds,
+            nanos,
+            format_timestamp(seconds, nanos, if nanos == 0 { 0 } else { 9 })
+        ));
+    }
+
+    let nanos = nanos?;
+    if nanos != 0
+        && seconds.abs() <= DURATION_MAX
+        && nanos.abs() < 1_000_000_000
+        && (seconds == 0 || seconds.signum() == nanos.signum())
+    {
+        let sign = if seconds < 0 || nanos < 0 { "-" } else { "" };
+        let fraction = format!("{:09}", nanos.abs());
+        return Some(format!(
+            "Duration {{ seconds: {}, nanos: {} }} // {}PT{}.{}S",
+            seconds,
+            nanos,
+            sign,
+            seconds.abs(),
+            fraction.trim_end_matches('0')
+        ));
+    }
+    None
+}
+
+/// Hint about what `field` may mean, e.g. a timestamp or an embedded
+/// `google.protobuf.Timestamp`/`Duration` message
+pub fn field_hint(field: &dyn FieldTrait) -> Option<String> {
+    match field.as_any_ref().downcast_ref::<EmbeddedField>() {
+        Some(embedded) => well_known_type(&embedded.field.data.fields),
+        None => integer_value(field).and_then(timestamp_hint),
+    }
+}
+
+/// Messages found by `PartialParser::deserialize_map` in `into`, the ones
+/// covering the most bytes first
+///
+/// Ranges of equal length are ordered by start offset. Useful to locate
+/// protobuf payloads embedded in a larger blob.
+///
+/// Returns (start, end, message)
+pub fn scan(deserializer: &PartialParser, into: &[u8]) -> Vec<(usize, usize, Message)> {
+    let mut found: Vec<(usize, usize, Message)> = deserializer
+        .deserialize_map(into)
+        .into_iter()
+        .map(|((start, end), message)| (start, end, message))
+        .collect();
+    found.sort_by_key(|(start, end, _)| (std::cmp::Reverse(end - start), *start));
+    found
+}
+
+/// One line summary of a scanned message, e.g. `3 fields: int32, string, message`
+pub fn scan_summary(message: &Message) -> String {
+    let types: Vec<&str> = message
+        .fields
+        .iter()
+        .map(|x| x.field_type().proto_type())
+        .collect();
+    format!("{} fields: {}", types.len(), types.join(", "))
+}
+
+/// Group top-level tags never present together in `messages` into candidate
+/// `oneof` sets
+///
+/// Oneofs look like optional fields on the wire, only many samples show that
+/// their tags exclude each other. Tags are grouped greedily in ascending
+/// order, sets of a single tag are dropped.
+pub fn analyze_oneofs(messages: &[Message]) -> Vec<Vec<u64>> {
+    let samples: Vec<BTreeSet<u64>> = messages
+        .iter()
+        .map(|x| x.fields.iter().map(|field| field.number()).collect())
+        .collect();
+    let tags: BTreeSet<u64> = samples.iter().flatten().copied().collect();
+    let exclusive = |a: u64, b: u64| {
+        !samples
+            .iter()
+            .any(|sample| sample.contains(&a) && sample.contains(&b))
+    };
+
+    let mut grouped = BTreeSet::new();
+    let mut groups = Vec::new();
+    for &tag in tags.iter() {
+        if grouped.contains(&tag) {
+            continue;
+        }
+        let mut group = vec![tag];
+        for &other in tags.range(tag + 1..) {
+            if !grouped.contains(&other) && group.iter().all(|&x| exclusive(x, other)) {
+                group.push(other);
+            }
+        }
+        if group.len() > 1 {
+            grouped.extend(group.iter().copied());
+            groups.push(group);
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::proto::error::ErrorType;
+    use crate::proto::field::FieldExt;
+
+    #[test]
+    fn test_suggest_boundaries() {
+        // field 1 = 150, field 2 = "abc", field 5 = 1
+        let message = [0x08, 0x96, 0x01, 0x12, 0x03, 0x61, 0x62, 0x63, 0x28, 0x01];
+        let mut buffer = message.to_vec();
+        buffer.extend_from_slice(&message);
+
+        let parser = FullParser::new();
+        let (decoded, spans) = parser.deserialize_with_spans(&message).unwrap();
+        assert!(suggest_boundaries(&decoded.fields, &spans).is_empty());
+        let (decoded, spans) = parser.deserialize_with_spans(&buffer).unwrap();
+        assert_eq!(suggest_boundaries(&decoded.fields, &spans), [message.len()]);
+
+        // field 3 = group {1: 1}, field 4 = 1, fields of the group and its end
+        // don't restart the numbers
+        let buffer = [0x1b, 0x08, 0x01, 0x1c, 0x20, 0x01];
+        let (decoded, spans) = parser.deserialize_with_spans(&buffer).unwrap();
+        assert!(suggest_boundaries(&decoded.fields, &spans).is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_multi() {
+        // field 1 = 150, field 2 = "abc"
+        let first = [0x08, 0x96, 0x01, 0x12, 0x03, 0x61, 0x62, 0x63];
+        // field 1 = 1, field 3 = 2
+        let second = [0x08, 0x01, 0x18, 0x02];
+        let mut buffer = first.to_vec();
+        buffer.extend_from_slice(&second);
+
+        let parser = FullParser::new();
+        assert_eq!(
+            split_messages(&parser, &buffer).unwrap(),
+            [(0, first.len()), (first.len(), buffer.len())]
+        );
+        let messages = deserialize_multi(&parser, &buffer).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].serialize(), first);
+        assert_eq!(messages[1].serialize(), second);
+
+        assert_eq!(deserialize_multi(&parser, &first).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_encoder_fingerprint() {
+        // field 1 = 150, field 2 = "abc"
+        let canonical = [0x08, 0x96, 0x01, 0x12, 0x03, 0x61, 0x62, 0x63];
+        // field 2 = "abc", field 1 = 150 (overlong varint), field 3 = 0
+        let other = [
+            0x12, 0x03, 0x61, 0x62, 0x63, 0x08, 0x96, 0x81, 0x00, 0x18, 0x00,
+        ];
+
+        let parser = FullParser::new();
+        let canonical = encoder_fingerprint(&parser, &canonical).unwrap();
+        let other = encoder_fingerprint(&parser, &other).unwrap();
+
+        assert_eq!(
+            canonical,
+            "order=1,2 sorted=true non_minimal_varints=0 present_defaults=0"
+        );
+        assert_eq!(
+            other,
+            "order=2,1,3 sorted=false non_minimal_varints=1 present_defaults=1"
+        );
+    }
+
+    #[test]
+    fn test_annotate() {
+        // field 1 = 150, field 2 = {1: 1}
+        let buffer = [0x08, 0x96, 0x01, 0x12, 0x02, 0x08, 0x01];
+
+        let parser = FullParser::new();
+        assert_eq!(
+            annotate(&parser, &buffer).unwrap(),
+            [
+                "[0x00..0x03] field 1 (varint) = 150  // 08 96 01",
+                "[0x03..0x07] field 2 (len) = message  // 12 02",
+                "\t[0x05..0x07] field 1 (varint) = 1  // 08 01",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_frame_reader() {
+        // frame {1: 150}, frame {2: "ab"}
+        let stream = [0x03, 0x08, 0x96, 0x01, 0x04, 0x12, 0x02, 0x61, 0x62];
+        let parser = FullParser::new();
+
+        let frames: Vec<(usize, Vec<u8>)> = FrameReader::new(std::io::Cursor::new(&stream))
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], (1, stream[1..4].to_vec()));
+        assert_eq!(frames[1], (5, stream[5..].to_vec()));
+        assert_eq!(
+            frames
+                .iter()
+                .map(|x| (x.0, x.0 + x.1.len()))
+                .collect::<Vec<_>>(),
+            split_frames(&stream).unwrap()
+        );
+        for (_, payload) in frames.iter() {
+            assert_eq!(parser.deserialize(payload).unwrap().serialize(), *payload);
+        }
+
+        // Last frame cut off, then a prefix cut off
+        let mut reader = FrameReader::new(std::io::Cursor::new(&stream[..7]));
+        assert!(reader.next().unwrap().is_ok());
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::Truncated);
+        assert!(reader.next().is_none());
+        let mut reader = FrameReader::new(std::io::Cursor::new([0x80]));
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+        assert!(FrameReader::new(std::io::Cursor::new([])).next().is_none());
+    }
+
+    #[test]
+    fn test_detect_framing() {
+        // field 1 = 150, field 2 = "abc"
+        let message = [0x08, 0x96, 0x01, 0x12, 0x03, 0x61, 0x62, 0x63];
+        assert_eq!(detect_framing(&message), Framing::Bare);
+        assert_eq!(detect_framing(&[]), Framing::Bare);
+
+        let mut framed = vec![message.len() as u8];
+        framed.extend_from_slice(&message);
+        assert_eq!(detect_framing(&framed), Framing::LengthPrefixed);
+        assert_eq!(split_frames(&framed).unwrap(), [(1, 9)]);
+
+        framed.push(0x02);
+        framed.extend_from_slice(&[0x08, 0x01]);
+        assert_eq!(detect_framing(&framed), Framing::LengthPrefixed);
+        assert_eq!(split_frames(&framed).unwrap(), [(1, 9), (10, 12)]);
+
+        // field 1 = 9 bytes, its key reads as the length of the rest
+        let ambiguous = [
+            0x0a, 0x09, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+        ];
+        assert_eq!(detect_framing(&ambiguous), Framing::Bare);
+    }
+
+    #[test]
+    fn test_timestamp_hints() {
+        assert_eq!(
+            timestamp_hint(1622505600).unwrap(),
+            "maybe timestamp: 2021-06-01T00:00:00Z"
+        );
+        assert_eq!(
+            timestamp_hint(1622505600123).unwrap(),
+            "maybe timestamp: 2021-06-01T00:00:00.123Z"
+        );
+        assert!(timestamp_hint(150).is_none());
+
+        // field 1 = 1622505600, field 2 = {1: 1, 2: 1622505600000000}
+        let mut buffer = vec![0x08];
+        buffer.extend(serialize_varint(1622505600));
+        let mut nested = vec![0x08, 0x01, 0x10];
+        nested.extend(serialize_varint(1622505600000000));
+        buffer.push(0x12);
+        buffer.push(nested.len() as u8);
+        buffer.extend(nested);
+        let mut message = FullParser::new().deserialize(&buffer).unwrap();
+
+        assert!(field_hint(message.fields[0].as_ref())
+            .unwrap()
+            .starts_with("maybe timestamp: 2021-06-01"));
+        assert!(field_hint(message.fields[1].as_ref()).is_none());
+        let nested = &message.fields[1].as_embedded().unwrap().field.data.fields;
+        assert!(field_hint(nested[0].as_ref()).is_none());
+        assert_eq!(
+            field_hint(nested[1].as_ref()).unwrap(),
+            "maybe timestamp: 2021-06-01T00:00:00.000000Z"
+        );
+    }
+
+    #[test]
+    fn test_well_known_types() {
+        // field 1 = Timestamp {1: 1622505600, 2: 500000000}
+        let mut timestamp = vec![0x08];
+        timestamp.extend(serialize_varint(1622505600));
+        timestamp.push(0x10);
+        timestamp.extend(serialize_varint(500000000));
+        let mut buffer = vec![0x0a, timestamp.len() as u8];
+        buffer.extend(timestamp);
+        // field 2 = Duration {1: 90, 2: 250000000}
+        let mut duration = vec![0x08, 90, 0x10];
+        duration.extend(serialize_varint(250000000));
+        buffer.push(0x12);
+        buffer.push(duration.len() as u8);
+        buffer.extend(duration);
+        // field 3 = {1: 5}
+        buffer.extend([0x1a, 0x02, 0x08, 0x05]);
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+
+        let hints: Vec<Option<String>> = message
+            .fields
+            .iter()
+            .map(|x| field_hint(x.as_ref()))
+            .collect();
+        assert_eq!(
+            hints[0].as_deref(),
+            Some("Timestamp { seconds: 1622505600, nanos: 500000000 } // 2021-06-01T00:00:00.500000000Z")
+        );
+        assert_eq!(
+            hints[1].as_deref(),
+            Some("Duration { seconds: 90, nanos: 250000000 } // PT90.25S")
+        );
+        assert!(hints[2].is_none());
+    }
+
+    #[test]
+    fn test_analyze_oneofs() {
+        // Field 1 is always set, field 2 or field 3 but never both, field 4
+        // sometimes along with either of them
+        let samples: Vec<&[u8]> = vec![
+            &[0x08, 0x01, 0x10, 0x02],
+            &[0x08, 0x01, 0x18, 0x03, 0x20, 0x04],
+            &[0x08, 0x01, 0x10, 0x02, 0x20, 0x04],
+            &[0x08, 0x01, 0x18, 0x03],
+        ];
+        let parser = FullParser::new();
+        let messages: Vec<Message> = samples
+            .iter()
+            .map(|x| parser.deserialize(x).unwrap())
+            .collect();
+
+        assert_eq!(analyze_oneofs(&messages), [vec![2, 3]]);
+        assert!(analyze_oneofs(&messages[..1]).is_empty());
+    }
+
+    #[test]
+    fn test_scan() {
+        // Garbage, then field 1 = 150 and field 2 = {1: "ab"}, then garbage
+        let blob = [
+            0xff, 0xff, 0xff, 0x08, 0x96, 0x01, 0x12, 0x04, 0x0a, 0x02, 0x61, 0x62, 0xff,
+        ];
+        let deserializer = PartialParser::new().with_min_fields(2);
+        let found = scan(&deserializer, &blob);
+
+        let (start, end, message) = &found[0];
+        assert_eq!((*start, *end), (3, 12));
+        assert_eq!(scan_summary(message), "2 fields: int32, message");
+        assert!(found.windows(2).all(|x| x[0].1 - x[0].0 >= x[1].1 - x[1].0));
+    }
+}
@@ -1 +1,3 @@
+pub mod analysis;
+#[allow(clippy::module_inception)]
 pub mod parser;
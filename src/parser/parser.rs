@@ -1,14 +1,25 @@
 use std::collections::BTreeMap;
 
+use crate::proto::codec::read_varint;
 use crate::proto::error::*;
 use crate::proto::field::*;
 use crate::proto::message::*;
+use crate::proto::utils::parse_key;
 
 pub trait Parser {
+    /// Decode `into` as a message
+    ///
+    /// Empty input is a valid message without fields, not an error.
     fn deserialize(&self, into: &[u8]) -> Result<Message>;
+
+    /// Decode `into` along with absolute byte spans of the fields
+    ///
+    /// Spans are parallel to `Message::fields`, spans of fields of embedded
+    /// messages are nested in the span of their parent.
+    fn deserialize_with_spans(&self, into: &[u8]) -> Result<(Message, Vec<Span>)>;
 }
 
-const SimpleFieldsOrder: &'static [FieldType] = &[
+const SIMPLE_FIELDS_ORDER: &[FieldType] = &[
     FieldType::Int32,
     FieldType::Int64,
     FieldType::UInt32,
@@ -28,59 +39,514 @@ const SimpleFieldsOrder: &'static [FieldType] = &[
     // FieldType::Enum,
     // FieldType::Repeated,
     FieldType::StartGroup,
-    // FieldType::EndGroup,
+    FieldType::EndGroup,
+];
+
+/// Types missing from the default order, still reported by `FullParser::classify`
+const UNORDERED_TYPES: &[FieldType] = &[FieldType::Enum, FieldType::Repeated, FieldType::Map];
+
+/// Default limit of nested embedded messages
+pub const DEFAULT_MAX_DEPTH: usize = 100;
+
+/// Default limit of start offsets tried by `PartialParser::deserialize_map`,
+/// inputs up to 64 KiB are scanned completely
+pub const DEFAULT_SCAN_LIMIT: usize = 0x10000;
+
+/// Default magnitude bound of plausible float/double values
+pub const DEFAULT_FLOAT_THRESHOLD: f64 = 1e9;
+
+/// Whether `error` is rejected the same way by every field type, e.g. the data
+/// ends inside the field or its tag is invalid, so it's worth reporting
+fn is_reportable(error: &Error) -> bool {
+    matches!(
+        error.error_type(),
+        ErrorType::Truncated
+            | ErrorType::UnexpectedEof
+            | ErrorType::InvalidFieldNumber
+            | ErrorType::LengthLimit
+    )
+}
+
+/// Whether a fixed-width payload reads better as floating point than as integer
+///
+/// NaN, infinities, subnormals and magnitudes outside `1/threshold..=threshold`
+/// are implausible. Zero is left to the integer interpretation.
+pub fn is_plausible_float(value: f64, threshold: f64) -> bool {
+    value.is_normal() && value.abs() <= threshold && value.abs() >= 1.0 / threshold
+}
+
+/// Absolute byte range `start..end` of a decoded field, key included
+///
+/// `fields` holds the spans of the fields of an embedded message. A map field
+/// detected from several consecutive entries spans from its first to its last
+/// entry and holds one span per entry.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub fields: Vec<Span>,
+}
+
+/// Exact source bytes of a decoded field, key included
+///
+/// Built from a `Span`, `fields` holds the raw bytes of the fields of an
+/// embedded message.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RawField {
+    pub bytes: Vec<u8>,
+    pub fields: Vec<RawField>,
+}
+
+impl RawField {
+    /// Copy bytes covered by `span` out of `into`
+    pub fn from_span(span: &Span, into: &[u8]) -> Self {
+        RawField {
+            bytes: into[span.start..span.end].to_vec(),
+            fields: span
+                .fields
+                .iter()
+                .map(|x| RawField::from_span(x, into))
+                .collect(),
+        }
+    }
+}
+
+/// Shape of a decoded message
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ParseStats {
+    /// Number of fields per wire type, indexed by `VariantTypeRaw` value
+    pub wire_types: [usize; 6],
+    /// Number of embedded messages
+    pub embedded: usize,
+    /// Deepest nesting of embedded messages, 0 when there are none
+    pub max_depth: usize,
+    /// Payload bytes left as raw `bytes` fields
+    pub raw_bytes: usize,
+}
+
+impl ParseStats {
+    /// Stats of a message decoded by any parser
+    pub fn of(message: &Message) -> Self {
+        let mut stats = ParseStats {
+            max_depth: message.depth() - 1,
+            ..ParseStats::default()
+        };
+        stats.collect(&message.fields);
+        stats
+    }
+
+    /// Collect stats of `fields`, their embedded messages and map values
+    fn collect(&mut self, fields: &[Box<dyn FieldTrait>]) {
+        for field in fields.iter() {
+            let wire_type = field.field_type().wire_type() as i32;
+            if let Some(count) = self.wire_types.get_mut(wire_type as usize) {
+                *count += 1;
+            }
+            let any = field.as_any_ref();
+            if let Some(embedded) = any.downcast_ref::<EmbeddedField>() {
+                self.embedded += 1;
+                self.collect(&embedded.field.data.fields);
+            } else if let Some(map) = any.downcast_ref::<MapField>() {
+                for (_, value) in map.field.data.iter() {
+                    self.collect(std::slice::from_ref(value));
+                }
+            } else if let Some(bytes) = any.downcast_ref::<BytesField>() {
+                self.raw_bytes += bytes.0.data.len();
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ParseStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "varint {}, fixed64 {}, length-delimited {}, start group {}, end group {}, fixed32 {}; \
+             embedded {}, max depth {}, raw bytes {}",
+            self.wire_types[0],
+            self.wire_types[1],
+            self.wire_types[2],
+            self.wire_types[3],
+            self.wire_types[4],
+            self.wire_types[5],
+            self.embedded,
+            self.max_depth,
+            self.raw_bytes
+        )
+    }
+}
+
+/// Fields along with their spans
+type SpannedFields = (Vec<Box<dyn FieldTrait>>, Vec<Span>);
+
+/// Types allowed as keys of protobuf maps
+const MAP_KEY_TYPES: &[FieldType] = &[
+    FieldType::Int32,
+    FieldType::Int64,
+    FieldType::UInt32,
+    FieldType::UInt64,
+    FieldType::SInt32,
+    FieldType::SInt64,
+    FieldType::Bool,
+    FieldType::Fixed64,
+    FieldType::SFixed64,
+    FieldType::Fixed32,
+    FieldType::SFixed32,
+    FieldType::String,
 ];
 
+/// Key and value types of an embedded field shaped as a map entry
+fn map_entry_types(field: &mut Box<dyn FieldTrait>) -> Option<(FieldType, FieldType)> {
+    let embedded = field.as_embedded()?;
+    match embedded.field.data.fields.as_slice() {
+        [key, value]
+            if key.number() == 1
+                && value.number() == 2
+                && MAP_KEY_TYPES.contains(&key.field_type()) =>
+        {
+            Some((key.field_type(), value.field_type()))
+        }
+        _ => None,
+    }
+}
+
+/// Replace consecutive embedded fields of one number whose entries consistently
+/// hold only a key (field 1) and a value (field 2) with a single `MapField`
+///
+/// A lone entry is indistinguishable from a small message, so at least two
+/// entries are required. `spans` are kept parallel to `fields`.
+fn detect_maps(mut fields: Vec<Box<dyn FieldTrait>>, mut spans: Vec<Span>) -> SpannedFields {
+    let mut i = 0;
+    while i < fields.len() {
+        if fields[i].field_type() != FieldType::Embedded {
+            i += 1;
+            continue;
+        }
+        let number = fields[i].number();
+        // Only consecutive entries are merged, so the field order is kept
+        let group: Vec<usize> = (i..fields.len())
+            .take_while(|&j| {
+                fields[j].number() == number && fields[j].field_type() == FieldType::Embedded
+            })
+            .collect();
+
+        let types: Vec<Option<(FieldType, FieldType)>> = group
+            .iter()
+            .map(|&j| map_entry_types(&mut fields[j]))
+            .collect();
+        let entry_types = match types.first() {
+            Some(first) if group.len() >= 2 && types.iter().all(|x| x == first) => *first,
+            _ => None,
+        };
+
+        if let Some((key_type, value_type)) = entry_types {
+            log::info!(
+                "Deserialization: {} entries of field {} decoded as map",
+                group.len(),
+                number
+            );
+            let mut entries = Vec::new();
+            let mut entry_spans = Vec::new();
+            for &j in group.iter().rev() {
+                entry_spans.push(spans.remove(j));
+                let mut entry = fields.remove(j);
+                if let Some(embedded) = entry.as_embedded() {
+                    let mut pair = std::mem::take(&mut embedded.field.data.fields);
+                    let value = pair.pop();
+                    let key = pair.pop();
+                    if let (Some(key), Some(value)) = (key, value) {
+                        entries.push((key, value));
+                    }
+                }
+            }
+            entries.reverse();
+            entry_spans.reverse();
+            fields.insert(
+                i,
+                Box::new(MapField::new(number, key_type, value_type, entries)),
+            );
+            spans.insert(
+                i,
+                Span {
+                    start: entry_spans.first().map_or(0, |x| x.start),
+                    end: entry_spans.last().map_or(0, |x| x.end),
+                    fields: entry_spans,
+                },
+            );
+        }
+        i += 1;
+    }
+    (fields, spans)
+}
+
 pub struct SimpleParser<'a> {
     syntax: Syntax,
     fields_order: &'a [FieldType],
+    options: DecodeOptions,
+    /// Keep unclassified bytes as unknown fields instead of failing
+    lenient: bool,
+}
+
+/// Offset of the next plausible key after a failure at `index`, or end of buffer
+fn next_plausible_key<F: Fn(&[u8]) -> bool>(into: &[u8], index: usize, parses: F) -> usize {
+    (index + 1..into.len())
+        .find(|&x| parses(&into[x..]))
+        .unwrap_or(into.len())
+}
+
+/// Reject a length-delimited field declaring more than `options.max_length`
+/// bytes before any type tries to decode it
+fn check_length(into: &[u8], options: &DecodeOptions) -> Result<()> {
+    let (key, readed) = read_varint(into)?;
+    if !VariantTypeRaw::from(parse_key(key).1).is_length_delimited() {
+        return Ok(());
+    }
+    let (size, _) = read_varint(&into[readed as usize..])?;
+    if size > options.max_length {
+        return Err(Error::new(
+            &format!(
+                "declared size {} exceeds maximum length {}",
+                size, options.max_length
+            ),
+            Some(ErrorType::LengthLimit),
+        ));
+    }
+    Ok(())
+}
+
+/// Warning about bytes of `len` left after `consumed` bytes form a message
+fn trailing_warning(consumed: usize, len: usize) -> Option<String> {
+    match consumed < len {
+        true => Some(format!(
+            "consumed {} of {} bytes, {} trailing bytes at {:#x}",
+            consumed,
+            len,
+            len - consumed,
+            consumed
+        )),
+        false => None,
+    }
+}
+
+/// Numbers of groups opened and not yet closed while decoding a message, along
+/// with the position of their StartGroup among the decoded fields
+#[derive(Debug, Default)]
+struct OpenGroups(Vec<(u64, usize)>);
+
+impl OpenGroups {
+    /// Open a group on StartGroup, close the innermost one on EndGroup,
+    /// `position` is the index `field` gets among the decoded fields
+    ///
+    /// An EndGroup with another number than the innermost StartGroup, or
+    /// without any open group, is rejected.
+    fn track(&mut self, field: &dyn FieldTrait, position: usize) -> Result<()> {
+        match field.field_type() {
+            FieldType::StartGroup => self.0.push((field.number(), position)),
+            FieldType::EndGroup => match self.0.pop() {
+                Some((number, _)) if number == field.number() => {}
+                Some((number, _)) => {
+                    return Err(Error::new(
+                        &format!(
+                            "end of group {} does not match start of group {}",
+                            field.number(),
+                            number
+                        ),
+                        Some(ErrorType::IncorrectData),
+                    ))
+                }
+                None => {
+                    return Err(Error::new(
+                        &format!("unexpected end of group {}", field.number()),
+                        Some(ErrorType::IncorrectData),
+                    ))
+                }
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Reject a message ending while a group is still open
+    fn finish(&self) -> Result<()> {
+        match self.0.last() {
+            Some((number, _)) => Err(Error::new(
+                &format!("group {} is not closed", number),
+                Some(ErrorType::IncorrectData),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Drop the fields from the outermost group still open on, so a decoded
+    /// prefix only holds closed groups
+    ///
+    /// `index` is the length of the prefix and `offset` the absolute offset of
+    /// the decoded buffer, returns the length of the remaining prefix.
+    fn cut(
+        &self,
+        fields: &mut Vec<Box<dyn FieldTrait>>,
+        spans: &mut Vec<Span>,
+        offset: usize,
+        index: usize,
+    ) -> usize {
+        match self.0.first() {
+            Some(&(number, position)) => {
+                log::info!("Deserialization: group {} is not closed", number);
+                let end = spans[position].start - offset;
+                fields.truncate(position);
+                spans.truncate(position);
+                end
+            }
+            None => index,
+        }
+    }
+}
+
+/// Types of `fields_order` sharing the wire type of the key at the start of
+/// `into`, all of them when the key is malformed
+///
+/// A varint is never tried as a length-delimited type and the other way round.
+/// Only the wire type is read here, each field tried reads the key again.
+pub(crate) fn matching_types<'b>(
+    fields_order: &'b [FieldType],
+    into: &[u8],
+) -> impl Iterator<Item = &'b FieldType> + 'b {
+    let wire_type = read_varint(into).ok().map(|(key, _)| parse_key(key).1);
+    fields_order.iter().filter(move |x| match wire_type {
+        Some(wire_type) => x.wire_type() as u8 == wire_type,
+        None => true,
+    })
 }
 
 /// Trying to decode/deserialize bytes into field
 pub(crate) fn try_deserialize_specific_field(
     into: &[u8],
     field_type: FieldType,
+    options: &DecodeOptions,
 ) -> Result<(Box<dyn FieldTrait>, u64)> {
     log::debug!("Deserialization: try deserialize as {:}", field_type);
+    if field_type.wire_type().is_length_delimited() {
+        check_length(into, options)?;
+    }
     let mut field: Box<dyn FieldTrait> = (field_type).into();
-    (*field).deserialize(into).and_then(|x| Ok((field, x)))
+    (*field).deserialize_with(into, options).map(|x| (field, x))
 }
 
 pub(crate) fn try_deserialize_field<'a, I: Iterator<Item = &'a FieldType>>(
     into: &[u8],
     fields_type: I,
+    options: &DecodeOptions,
 ) -> Result<(Box<dyn FieldTrait>, u64)> {
     for type_i in fields_type {
         let mut field: Box<dyn FieldTrait> = (*type_i).into();
         log::debug!("Deserialization: try deserialize as {:}", type_i);
-        match (*field).deserialize(into) {
+        match (*field).deserialize_with(into, options) {
             Ok(i) => {
-                log::debug!("Deserialization: deserialize as {:} successed", i);
+                log::debug!(
+                    "Deserialization: deserialize as {:} successed, {} bytes",
+                    type_i,
+                    i
+                );
                 return Ok((field, i));
             }
-            Err(_) => continue,
+            Err(e) => {
+                log::debug!("Deserialization: deserialize as {:} failed: {}", type_i, e);
+                continue;
+            }
         };
     }
 
     Err(Error::new("Failed to parse bytes", None))
 }
 
+/// Decode the first field of `into` like `SimpleParser` does
+///
+/// Returns (field, bytes following the field)
+pub fn decode_one(into: &[u8]) -> Result<(Box<dyn FieldTrait>, &[u8])> {
+    let (field, readed) = try_deserialize_field(
+        into,
+        matching_types(SIMPLE_FIELDS_ORDER, into),
+        &DecodeOptions::default(),
+    )?;
+    Ok((field, &into[readed as usize..]))
+}
+
+impl<'a> Default for SimpleParser<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> SimpleParser<'a> {
     pub fn new() -> SimpleParser<'a> {
         SimpleParser {
             syntax: Syntax::Proto3,
-            fields_order: SimpleFieldsOrder,
+            fields_order: SIMPLE_FIELDS_ORDER,
+            options: DecodeOptions::default(),
+            lenient: false,
         }
     }
 
+    /// Keep bytes no field type matches as `BytesField::unknown` and continue
+    /// from the next plausible key instead of failing
+    pub fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Set validation of string fields, see `StringMode`
+    pub fn with_string_mode(mut self, string_mode: StringMode) -> Self {
+        self.options.string_mode = string_mode;
+        self
+    }
+
+    /// Set syntax of decoded messages, see `Message::to_proto`
+    pub fn with_syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Set field types to try, in order, for each field
+    pub fn with_order(mut self, fields_order: &'a [FieldType]) -> Self {
+        self.fields_order = fields_order;
+        self
+    }
+
+    /// Set byte order of fixed-width fields, see `Endianness`
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.options.endianness = endianness;
+        self
+    }
+
+    /// Set largest accepted payload of length-delimited fields, longer ones
+    /// are rejected without being decoded
+    pub fn with_max_length(mut self, max_length: u64) -> Self {
+        self.options.max_length = max_length;
+        self
+    }
+
+    /// Set longest accepted varint, `MAX_VARINT_BYTES` (10) by default
+    pub fn with_max_varint_bytes(mut self, max_varint_bytes: usize) -> Self {
+        self.options.max_varint_bytes = max_varint_bytes;
+        self
+    }
+
     pub fn try_deserialize_field(&self, into: &[u8]) -> Result<(Box<dyn FieldTrait>, u64)> {
-        try_deserialize_field(into, self.fields_order.iter())
+        try_deserialize_field(into, matching_types(self.fields_order, into), &self.options)
     }
 }
 
 impl<'a> Parser for SimpleParser<'a> {
     fn deserialize(&self, into: &[u8]) -> Result<Message> {
+        self.deserialize_with_spans(into)
+            .map(|(message, _)| message)
+    }
+
+    /// Embedded payloads are not decoded, so spans have no nested spans
+    fn deserialize_with_spans(&self, into: &[u8]) -> Result<(Message, Vec<Span>)> {
         let mut fields = Vec::new();
+        let mut spans = Vec::new();
+        let mut groups = OpenGroups::default();
         let mut index: u64 = 0;
         while index != into.len() as u64 {
             log::debug!(
@@ -90,33 +556,368 @@ impl<'a> Parser for SimpleParser<'a> {
             );
             match self.try_deserialize_field(&into[index as usize..]) {
                 Ok((s, i)) => {
+                    groups.track(s.as_ref(), fields.len())?;
                     fields.push(s);
+                    spans.push(Span {
+                        start: index as usize,
+                        end: (index + i) as usize,
+                        fields: Vec::new(),
+                    });
                     index += i;
                 }
+                Err(_) if self.lenient => {
+                    let next = next_plausible_key(into, index as usize, |x| {
+                        self.try_deserialize_field(x).is_ok()
+                    });
+                    log::info!(
+                        "Deserialization: skip unknown bytes {:#x}..{:#x}",
+                        index,
+                        next
+                    );
+                    fields.push(Box::new(BytesField::unknown(&into[index as usize..next])));
+                    spans.push(Span {
+                        start: index as usize,
+                        end: next,
+                        fields: Vec::new(),
+                    });
+                    index = next as u64;
+                }
                 Err(e) => {
                     return Err(e);
                 }
             };
         }
-        Ok(Message::new("Generated".to_string(), Some(fields)))
+        groups.finish()?;
+        let message = Message::new("Generated".to_string(), Some(fields)).with_syntax(self.syntax);
+        Ok((message, spans))
     }
 }
 
 pub struct FullParser<'a> {
     syntax: Syntax,
     fields_order: &'a [FieldType],
+    options: DecodeOptions,
+    /// Maximum nesting of embedded messages, deeper payloads are kept as bytes
+    max_depth: usize,
+    /// Fail unless the whole buffer is consumed by the message
+    require_full: bool,
+    /// Keep unclassified top-level bytes as unknown fields instead of failing
+    lenient: bool,
+    /// Magnitude bound of plausible float/double values
+    float_threshold: f64,
+}
+
+impl<'a> Default for FullParser<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> FullParser<'a> {
     pub fn new() -> FullParser<'a> {
         FullParser {
             syntax: Syntax::Proto3,
-            fields_order: SimpleFieldsOrder,
+            fields_order: SIMPLE_FIELDS_ORDER,
+            options: DecodeOptions::default(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            require_full: false,
+            lenient: false,
+            float_threshold: DEFAULT_FLOAT_THRESHOLD,
         }
     }
 
+    /// Set magnitude bound of float/double values preferred over fixed32/fixed64,
+    /// see `is_plausible_float`
+    ///
+    /// Thresholds below 1 would reject every float and are clamped to 1.
+    pub fn with_float_threshold(mut self, float_threshold: f64) -> Self {
+        self.float_threshold = float_threshold.max(1.0);
+        self
+    }
+
+    /// Keep top-level bytes no field type matches as `BytesField::unknown` and
+    /// continue from the next plausible key instead of failing
+    ///
+    /// Embedded payloads are still decoded strictly, otherwise any payload would
+    /// pass as a message.
+    pub fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Fail unless the whole buffer parses as one message, reporting the leftover
+    pub fn with_require_full(mut self, require_full: bool) -> Self {
+        self.require_full = require_full;
+        self
+    }
+
+    /// Set validation of string fields, see `StringMode`
+    pub fn with_string_mode(mut self, string_mode: StringMode) -> Self {
+        self.options.string_mode = string_mode;
+        self
+    }
+
+    /// Set syntax of decoded messages, see `Message::to_proto`
+    pub fn with_syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Set field types to try, in order, for each field
+    pub fn with_order(mut self, fields_order: &'a [FieldType]) -> Self {
+        self.fields_order = fields_order;
+        self
+    }
+
+    /// Set byte order of fixed-width fields, see `Endianness`
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.options.endianness = endianness;
+        self
+    }
+
+    /// Set largest accepted payload of length-delimited fields, longer ones
+    /// are rejected without being decoded
+    pub fn with_max_length(mut self, max_length: u64) -> Self {
+        self.options.max_length = max_length;
+        self
+    }
+
+    /// Set longest accepted varint, `MAX_VARINT_BYTES` (10) by default
+    pub fn with_max_varint_bytes(mut self, max_varint_bytes: usize) -> Self {
+        self.options.max_varint_bytes = max_varint_bytes;
+        self
+    }
+
+    /// Set maximum nesting of embedded messages, deeper payloads are kept as bytes
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Trying to decode/deserialize bytes into the first suitable field
+    ///
+    /// Returns (field, bytes readed)
+    pub fn deserialize_field(&self, into: &[u8]) -> Result<(Box<dyn FieldTrait>, u64)> {
+        self.deserialize_field_at(into, 0, 0)
+            .map(|(field, i, _)| (field, i))
+    }
+
+    /// Deserialize a field of a message nested `depth` times, `offset` is the
+    /// absolute offset of `into`
+    fn deserialize_field_at(
+        &self,
+        into: &[u8],
+        depth: usize,
+        offset: usize,
+    ) -> Result<(Box<dyn FieldTrait>, u64, Span)> {
+        let mut span = Span {
+            start: offset,
+            ..Span::default()
+        };
+        // A field running past the buffer or with an invalid tag is rejected by
+        // every type, the reason is reported rather than a generic failure
+        let mut reported: Option<Error> = None;
+        let mut limited: Option<Error> = None;
+        for field_type in matching_types(self.fields_order, into) {
+            match *field_type {
+                FieldType::Embedded if depth >= self.max_depth => {
+                    log::info!(
+                        "Deserialization: max depth {} reached, skip {:}",
+                        self.max_depth,
+                        field_type
+                    );
+                    limited.get_or_insert_with(|| {
+                        Error::new(
+                            &format!("max depth {} of embedded messages reached", self.max_depth),
+                            Some(ErrorType::RecursionLimit),
+                        )
+                    });
+                    continue;
+                }
+                FieldType::Embedded => {
+                    match try_deserialize_specific_field(into, *field_type, &self.options) {
+                        Ok((mut s_em, i)) => {
+                            log::info!(
+                                "Deserialization: deserialize as {:} (size: {:}) successed {:}\n\n",
+                                field_type,
+                                i,
+                                s_em.repr()
+                            );
+                            match s_em.as_embedded() {
+                                Some(b) => match &b.raw {
+                                    Some(data) => {
+                                        let payload = offset + i as usize - data.len();
+                                        let embedded = match self.deserialize_fields_at(
+                                            data,
+                                            depth + 1,
+                                            payload,
+                                        ) {
+                                            Ok(((s, spans), _)) => {
+                                                span.fields = spans;
+                                                s
+                                            }
+                                            // The length prefix is valid, so the
+                                            // payload is kept as bytes right away
+                                            // instead of trying other types
+                                            Err(e) => {
+                                                log::info!(
+                                                    "Deserialization: payload at {:#x} is not a message ({}), kept as bytes",
+                                                    payload,
+                                                    e
+                                                );
+                                                return self.deserialize_bytes_at(into, offset);
+                                            }
+                                        };
+                                        b.field.data.fields = embedded;
+                                    }
+                                    None => {
+                                        log::info!("{:}", "Failed to create Embedded 1");
+                                        continue;
+                                    }
+                                },
+                                None => {
+                                    log::info!(
+                                        "{:}  {:?}",
+                                        "Failed to create Embedded",
+                                        s_em.repr()
+                                    );
+                                    continue;
+                                }
+                            };
+                            span.end = offset + i as usize;
+                            return Ok((s_em, i, span));
+                        }
+                        Err(e) => {
+                            log::info!("{:}", e);
+                            if is_reportable(&e) {
+                                reported.get_or_insert(e);
+                            }
+                            continue;
+                        }
+                    };
+                }
+                _ => {
+                    match try_deserialize_specific_field(into, *field_type, &self.options) {
+                        Ok((s, i)) => {
+                            log::info!(
+                                "deserialization: deserialize as {:}(size: {:}) successed{:}\n\n",
+                                field_type,
+                                i,
+                                s.repr()
+                            );
+                            let (s, i) = self.rescore_fixed(into, s, i);
+                            span.end = offset + i as usize;
+                            return Ok((s, i, span));
+                        }
+                        Err(e) => {
+                            log::info!("{:}", e);
+                            if is_reportable(&e) {
+                                reported.get_or_insert(e);
+                            }
+                            continue;
+                        }
+                    };
+                }
+            }
+        }
+        match reported.or(limited) {
+            Some(e) => Err(e),
+            None => Err(Error::new("Failed to find suitable field", None)),
+        }
+    }
+
+    /// Deserialize a length-delimited field as bytes, `offset` is the absolute
+    /// offset of `into`
+    fn deserialize_bytes_at(
+        &self,
+        into: &[u8],
+        offset: usize,
+    ) -> Result<(Box<dyn FieldTrait>, u64, Span)> {
+        let (field, i) = try_deserialize_specific_field(into, FieldType::Bytes, &self.options)?;
+        let span = Span {
+            start: offset,
+            end: offset + i as usize,
+            fields: Vec::new(),
+        };
+        Ok((field, i, span))
+    }
+
+    /// Attempt every field type of the parser order against the start of `into`
+    ///
+    /// Reports for each type whether it decodes with the parser options and how
+    /// many bytes it consumes, i.e. the raw data behind the choice made by
+    /// `deserialize_field`. Enum, repeated and map types follow the order.
+    /// Types of another wire type than the key are skipped, see
+    /// `matching_types`.
+    pub fn classify(&self, into: &[u8]) -> Vec<(FieldType, Result<u64>)> {
+        let unordered = UNORDERED_TYPES
+            .iter()
+            .filter(|x| !self.fields_order.contains(x));
+        let types: Vec<FieldType> = self.fields_order.iter().chain(unordered).copied().collect();
+        let matching: Vec<FieldType> = matching_types(&types, into).copied().collect();
+        types
+            .iter()
+            .map(|x| {
+                let result = match matching.contains(x) {
+                    true => try_deserialize_specific_field(into, *x, &self.options),
+                    false => Err(Error::new(
+                        "field type skipped, wire type of the key differs",
+                        Some(ErrorType::IncorrectType),
+                    )),
+                };
+                (*x, result.map(|(_, i)| i))
+            })
+            .collect()
+    }
+
+    /// Prefer float/double over a fixed32/fixed64 field holding a plausible value
+    fn rescore_fixed(
+        &self,
+        into: &[u8],
+        field: Box<dyn FieldTrait>,
+        readed: u64,
+    ) -> (Box<dyn FieldTrait>, u64) {
+        let float_type = match field.field_type() {
+            FieldType::Fixed32 => FieldType::Float,
+            FieldType::Fixed64 => FieldType::Double,
+            _ => return (field, readed),
+        };
+        if !self.fields_order.contains(&float_type) {
+            return (field, readed);
+        }
+
+        if let Ok((mut float, i)) = try_deserialize_specific_field(into, float_type, &self.options)
+        {
+            let value = match float.as_float() {
+                Some(x) => x.0.data as f64,
+                None => float.as_double().map_or(f64::NAN, |x| x.0.data),
+            };
+            if is_plausible_float(value, self.float_threshold) {
+                log::info!("Deserialization: {} is plausible as {:}", value, float_type);
+                return (float, i);
+            }
+        }
+        (field, readed)
+    }
+
     pub fn deserialize_fields(&self, into: &[u8]) -> Result<(Vec<Box<dyn FieldTrait>>, u64)> {
+        self.deserialize_fields_at(into, 0, 0)
+            .map(|((fields, _), index)| (fields, index))
+    }
+
+    /// Deserialize fields of a message nested `depth` times, `offset` is the
+    /// absolute offset of `into`
+    ///
+    /// Returns ((fields, spans of fields), bytes readed)
+    fn deserialize_fields_at(
+        &self,
+        into: &[u8],
+        depth: usize,
+        offset: usize,
+    ) -> Result<(SpannedFields, u64)> {
         let mut fields = Vec::new();
+        let mut spans = Vec::new();
+        let mut groups = OpenGroups::default();
         let mut index: u64 = 0;
         while index != into.len() as u64 {
             log::debug!(
@@ -124,97 +925,243 @@ impl<'a> FullParser<'a> {
                 index,
                 into.len()
             );
-            let mut found = false;
-            for field_type in self.fields_order.iter() {
-                match *field_type {
-                    FieldType::Embedded => {
-                        match try_deserialize_specific_field(&into[index as usize..], *field_type) {
-                            Ok((mut s_em, i)) => {
-                                log::info!("Deserialization: deserialize as {:} (size: {:}) successed {:}\n\n", field_type, i, s_em.repr());
-                                match s_em.as_any().downcast_mut::<EmbeddedField>() {
-                                    Some(b) => match &b.raw {
-                                        Some(data) => {
-                                            let embedded = match self.deserialize_fields(&data) {
-                                                Ok((s, _)) => s,
-                                                Err(e) => {
-                                                    log::info!("{:}", e);
-                                                    continue;
-                                                }
-                                            };
-                                            b.field.data.fields = embedded;
-                                        }
-                                        None => {
-                                            log::info!("{:}", "Failed to create Embedded 1");
-                                            continue;
-                                        }
-                                    },
-                                    None => {
-                                        log::info!(
-                                            "{:}  {:?}",
-                                            "Failed to create Embedded",
-                                            s_em.repr()
-                                        );
-                                        continue;
-                                    }
-                                };
-                                fields.push(s_em);
-                                index += i;
-                                found = true;
-                                break;
-                            }
-                            Err(e) => {
-                                log::info!("{:}", e);
-                                continue;
-                            }
-                        };
-                    }
-                    _ => {
-                        match try_deserialize_specific_field(&into[index as usize..], *field_type) {
-                            Ok((s, i)) => {
-                                log::info!("deserialization: deserialize as {:}(size: {:}) successed{:}\n\n", field_type, i, s.repr());
-                                fields.push(s);
-                                index += i;
-                                found = true;
-                                break;
-                            }
-                            Err(e) => {
-                                log::info!("{:}", e);
-                                continue;
-                            }
-                        };
-                    }
+            match self.deserialize_field_at(&into[index as usize..], depth, offset + index as usize)
+            {
+                Ok((field, i, span)) => {
+                    groups.track(field.as_ref(), fields.len())?;
+                    fields.push(field);
+                    spans.push(span);
+                    index += i;
                 }
+                Err(_) if self.lenient && depth == 0 => {
+                    let next = next_plausible_key(into, index as usize, |x| {
+                        self.deserialize_field_at(x, depth, 0).is_ok()
+                    });
+                    log::info!(
+                        "Deserialization: skip unknown bytes {:#x}..{:#x}",
+                        index,
+                        next
+                    );
+                    fields.push(Box::new(BytesField::unknown(&into[index as usize..next])));
+                    spans.push(Span {
+                        start: offset + index as usize,
+                        end: offset + next,
+                        fields: Vec::new(),
+                    });
+                    index = next as u64;
+                }
+                Err(e) => return Err(e),
             }
-            if found == false {
-                return Err(Error::new("Failed to find suitable field", None));
+        }
+        groups.finish()?;
+        Ok((detect_maps(fields, spans), index))
+    }
+
+    /// Deserialize fields while possible
+    ///
+    /// Returns (fields and their spans, bytes readed)
+    fn deserialize_prefix(&self, into: &[u8]) -> (SpannedFields, usize) {
+        let mut fields = Vec::new();
+        let mut spans = Vec::new();
+        let mut groups = OpenGroups::default();
+        let mut index: usize = 0;
+        while index < into.len() {
+            match self.deserialize_field_at(&into[index..], 0, index) {
+                Ok((field, i, span)) => {
+                    if let Err(e) = groups.track(field.as_ref(), fields.len()) {
+                        log::info!("{:}", e);
+                        break;
+                    }
+                    fields.push(field);
+                    spans.push(span);
+                    index += i as usize;
+                }
+                Err(e) => {
+                    log::info!("{:}", e);
+                    break;
+                }
             }
         }
-        Ok((fields, index))
+        let index = groups.cut(&mut fields, &mut spans, 0, index);
+        (detect_maps(fields, spans), index)
+    }
+
+    /// Deserialize fields while possible and check that the whole buffer is consumed
+    fn deserialize_full(&self, into: &[u8]) -> Result<SpannedFields> {
+        let (fields, index) = self.deserialize_prefix(into);
+        if index != into.len() {
+            return Err(Error::new(
+                &format!(
+                    "consumed {} of {} bytes, {} bytes left over at {:#x}",
+                    index,
+                    into.len(),
+                    into.len().saturating_sub(index),
+                    index
+                ),
+                Some(ErrorType::IncorrectData),
+            ));
+        }
+        Ok(fields)
+    }
+
+    /// Deserialize the longest decodable prefix of `into` as a message
+    ///
+    /// Unlike `deserialize` trailing bytes are not an error, a warning is
+    /// logged and the caller gets the count of bytes forming the message.
+    ///
+    /// Returns (message, bytes consumed)
+    pub fn deserialize_with_consumed(&self, into: &[u8]) -> Result<(Message, usize)> {
+        let ((fields, _), consumed) = self.deserialize_prefix(into);
+        if let Some(warning) = trailing_warning(consumed, into.len()) {
+            log::warn!("{}", warning);
+        }
+        let message = Message::new("Generated".to_string(), Some(fields)).with_syntax(self.syntax);
+        Ok((message, consumed))
+    }
+
+    /// Deserialize message along with the raw bytes of every field
+    ///
+    /// Raw fields are parallel to `Message::fields`, like the spans of
+    /// `deserialize_with_spans`.
+    pub fn deserialize_with_raw(&self, into: &[u8]) -> Result<(Message, Vec<RawField>)> {
+        let (message, spans) = self.deserialize_with_spans(into)?;
+        let raw = spans.iter().map(|x| RawField::from_span(x, into)).collect();
+        Ok((message, raw))
+    }
+
+    /// Deserialize message along with `ParseStats` describing its shape
+    pub fn deserialize_with_stats(&self, into: &[u8]) -> Result<(Message, ParseStats)> {
+        let message = self.deserialize(into)?;
+        let stats = ParseStats::of(&message);
+        Ok((message, stats))
     }
 }
 
 impl<'a> Parser for FullParser<'a> {
     fn deserialize(&self, into: &[u8]) -> Result<Message> {
-        let (x, s) = self.deserialize_fields(into)?;
-        Ok(Message::new("Generated".to_string(), Some(x)))
+        self.deserialize_with_spans(into)
+            .map(|(message, _)| message)
+    }
+
+    fn deserialize_with_spans(&self, into: &[u8]) -> Result<(Message, Vec<Span>)> {
+        let (x, spans) = match self.require_full {
+            true => self.deserialize_full(into)?,
+            false => self.deserialize_fields_at(into, 0, 0)?.0,
+        };
+        let message = Message::new("Generated".to_string(), Some(x)).with_syntax(self.syntax);
+        Ok((message, spans))
+    }
+}
+
+/// Decodes fields while possible, the message holds the longest decodable
+/// prefix of the input instead of failing on trailing garbage
+impl<'a> Parser for PartialParser<'a> {
+    fn deserialize(&self, into: &[u8]) -> Result<Message> {
+        self.deserialize_with_spans(into)
+            .map(|(message, _)| message)
+    }
+
+    fn deserialize_with_spans(&self, into: &[u8]) -> Result<(Message, Vec<Span>)> {
+        let ((fields, spans), _) = self.deserialize_fields_at(into, 0, 0)?;
+        let message = Message::new("Generated".to_string(), Some(fields)).with_syntax(self.syntax);
+        Ok((message, spans))
     }
 }
 
 pub struct PartialParser<'a> {
     syntax: Syntax,
     fields_order: &'a [FieldType],
+    options: DecodeOptions,
+    /// Maximum nesting of embedded messages, deeper payloads are kept as bytes
+    max_depth: usize,
+    /// Minimum count of top-level fields of a parse kept by `deserialize_map`
+    min_fields: usize,
+    /// Maximum count of start offsets tried by `deserialize_map`
+    scan_limit: usize,
+    /// Distance between start offsets tried by `deserialize_map`
+    stride: usize,
+}
+
+impl<'a> Default for PartialParser<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> PartialParser<'a> {
     pub fn new() -> PartialParser<'a> {
         Self {
             syntax: Syntax::Proto3,
-            fields_order: SimpleFieldsOrder,
+            fields_order: SIMPLE_FIELDS_ORDER,
+            options: DecodeOptions::default(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            min_fields: 1,
+            scan_limit: DEFAULT_SCAN_LIMIT,
+            stride: 1,
         }
     }
 
+    /// Set validation of string fields, see `StringMode`
+    pub fn with_string_mode(mut self, string_mode: StringMode) -> Self {
+        self.options.string_mode = string_mode;
+        self
+    }
+
+    /// Set syntax of decoded messages, see `Message::to_proto`
+    pub fn with_syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Set field types to try, in order, for each field
+    pub fn with_order(mut self, fields_order: &'a [FieldType]) -> Self {
+        self.fields_order = fields_order;
+        self
+    }
+
+    /// Set byte order of fixed-width fields, see `Endianness`
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.options.endianness = endianness;
+        self
+    }
+
+    /// Set largest accepted payload of length-delimited fields, longer ones
+    /// are rejected without being decoded
+    pub fn with_max_length(mut self, max_length: u64) -> Self {
+        self.options.max_length = max_length;
+        self
+    }
+
+    /// Set longest accepted varint, `MAX_VARINT_BYTES` (10) by default
+    pub fn with_max_varint_bytes(mut self, max_varint_bytes: usize) -> Self {
+        self.options.max_varint_bytes = max_varint_bytes;
+        self
+    }
+
+    /// Set maximum nesting of embedded messages, deeper payloads are kept as bytes
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
     pub fn deserialize_fields(&self, into: &[u8]) -> Result<(Vec<Box<dyn FieldTrait>>, u64)> {
+        self.deserialize_fields_at(into, 0, 0)
+            .map(|((fields, _), index)| (fields, index))
+    }
+
+    /// Deserialize fields of a message nested `depth` times, `offset` is the
+    /// absolute offset of `into`
+    ///
+    /// Returns ((fields, spans of fields), bytes readed)
+    fn deserialize_fields_at(
+        &self,
+        into: &[u8],
+        depth: usize,
+        offset: usize,
+    ) -> Result<(SpannedFields, u64)> {
         let mut fields = Vec::new();
+        let mut spans = Vec::new();
+        let mut groups = OpenGroups::default();
         let mut index: u64 = 0;
         while index != into.len() as u64 {
             log::debug!(
@@ -223,17 +1170,54 @@ impl<'a> PartialParser<'a> {
                 into.len()
             );
             let mut found = false;
-            for field_type in self.fields_order.iter() {
+            for field_type in matching_types(self.fields_order, &into[index as usize..]) {
                 match *field_type {
+                    FieldType::Embedded if depth >= self.max_depth => {
+                        log::info!(
+                            "Deserialization: max depth {} reached, skip {:}",
+                            self.max_depth,
+                            field_type
+                        );
+                        continue;
+                    }
                     FieldType::Embedded => {
-                        match try_deserialize_specific_field(&into[index as usize..], *field_type) {
+                        match try_deserialize_specific_field(
+                            &into[index as usize..],
+                            *field_type,
+                            &self.options,
+                        ) {
                             Ok((mut s_em, i)) => {
                                 log::info!("Deserialization: deserialize as {:} (size: {:}) successed {:}\n\n", field_type, i, s_em.repr());
-                                match s_em.as_any().downcast_mut::<EmbeddedField>() {
+                                let start = offset + index as usize;
+                                let mut span = Span {
+                                    start,
+                                    end: start + i as usize,
+                                    fields: Vec::new(),
+                                };
+                                match s_em.as_embedded() {
                                     Some(b) => match &b.raw {
                                         Some(data) => {
-                                            let embedded = match self.deserialize_fields(&data) {
-                                                Ok((s, _)) => s,
+                                            let payload = span.end - data.len();
+                                            let embedded = match self.deserialize_fields_at(
+                                                data,
+                                                depth + 1,
+                                                payload,
+                                            ) {
+                                                Ok(((s, nested), readed))
+                                                    if readed == data.len() as u64 =>
+                                                {
+                                                    span.fields = nested;
+                                                    s
+                                                }
+                                                // Trailing bytes of the payload are not
+                                                // fields, it is not a nested message
+                                                Ok((_, readed)) => {
+                                                    log::info!(
+                                                            "Deserialization: {} trailing bytes in embedded payload",
+                                                            data.len() as u64 - readed
+                                                        );
+                                                    continue;
+                                                }
                                                 Err(e) => {
                                                     log::info!("{:}", e);
                                                     continue;
@@ -256,6 +1240,7 @@ impl<'a> PartialParser<'a> {
                                     }
                                 };
                                 fields.push(s_em);
+                                spans.push(span);
                                 index += i;
                                 found = true;
                                 break;
@@ -267,10 +1252,24 @@ impl<'a> PartialParser<'a> {
                         };
                     }
                     _ => {
-                        match try_deserialize_specific_field(&into[index as usize..], *field_type) {
+                        match try_deserialize_specific_field(
+                            &into[index as usize..],
+                            *field_type,
+                            &self.options,
+                        ) {
                             Ok((s, i)) => {
                                 log::info!("deserialization: deserialize as {:}(size: {:}) successed{:}\n\n", field_type, i, s.repr());
+                                // The prefix ends before a mismatched end of group
+                                if let Err(e) = groups.track(s.as_ref(), fields.len()) {
+                                    log::info!("{:}", e);
+                                    break;
+                                }
                                 fields.push(s);
+                                spans.push(Span {
+                                    start: offset + index as usize,
+                                    end: offset + (index + i) as usize,
+                                    fields: Vec::new(),
+                                });
                                 index += i;
                                 found = true;
                                 break;
@@ -283,22 +1282,70 @@ impl<'a> PartialParser<'a> {
                     }
                 }
             }
-            if found == false {
-                return Ok((fields, index));
+            if !found {
+                break;
             }
         }
-        Ok((fields, index))
+        let index = groups.cut(&mut fields, &mut spans, offset, index as usize) as u64;
+        Ok(((fields, spans), index))
+    }
+
+    /// Set minimum count of top-level fields of parses kept by `deserialize_map`
+    ///
+    /// Scanning a blob yields many spurious parses of one or two fields, a real
+    /// message usually has more.
+    pub fn with_min_fields(mut self, min_fields: usize) -> Self {
+        self.min_fields = min_fields;
+        self
+    }
+
+    /// Set maximum count of start offsets tried by `deserialize_map`,
+    /// `DEFAULT_SCAN_LIMIT` by default
+    pub fn with_scan_limit(mut self, scan_limit: usize) -> Self {
+        self.scan_limit = scan_limit;
+        self
     }
 
+    /// Set distance between start offsets tried by `deserialize_map`, 1 by default
+    ///
+    /// Larger strides scan huge inputs faster but miss messages starting between
+    /// the tried offsets.
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.stride = stride.max(1);
+        self
+    }
+
+    /// Start offsets of `into` tried by `deserialize_map`
+    pub fn scan_offsets(&self, into: &[u8]) -> impl Iterator<Item = usize> {
+        (0..into.len()).step_by(self.stride).take(self.scan_limit)
+    }
+
+    /// Whether `deserialize_map` stops before the last start offset of `into`
+    pub fn scan_truncated(&self, into: &[u8]) -> bool {
+        into.len().div_ceil(self.stride) > self.scan_limit
+    }
+
+    /// Decode messages starting at every scanned offset of `into`
+    ///
+    /// At most `scan_limit` offsets, `stride` bytes apart, are tried.
     pub fn deserialize_map(&self, into: &[u8]) -> BTreeMap<(usize, usize), Message> {
         let mut hashmap = BTreeMap::new();
 
-        for start_bytes in 0..into.len() as usize {
+        if self.scan_truncated(into) {
+            log::warn!(
+                "Deserialization: scan stopped after {} of {} bytes",
+                self.scan_limit * self.stride,
+                into.len()
+            );
+        }
+
+        for start_bytes in self.scan_offsets(into) {
             if let Ok((message, end_bytes)) = self.deserialize_fields(&into[start_bytes..]) {
-                if !message.is_empty() {
+                if !message.is_empty() && message.len() >= self.min_fields {
                     hashmap.insert(
                         (start_bytes, start_bytes + end_bytes as usize),
-                        Message::new("Generated".to_string(), Some(message)),
+                        Message::new("Generated".to_string(), Some(message))
+                            .with_syntax(self.syntax),
                     );
                 }
             }
@@ -306,11 +1353,31 @@ impl<'a> PartialParser<'a> {
 
         hashmap
     }
+
+    /// Find the parse covering the most bytes, ties are broken by the earliest start
+    ///
+    /// Returns (start, end, message)
+    pub fn best_parse(&self, into: &[u8]) -> Option<(usize, usize, Message)> {
+        let mut best: Option<(usize, usize, Message)> = None;
+
+        for ((start, end), message) in self.deserialize_map(into) {
+            let better = match &best {
+                Some((best_start, best_end, _)) => end - start > best_end - best_start,
+                None => true,
+            };
+            if better {
+                best = Some((start, end, message));
+            }
+        }
+
+        best
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::proto::codec::write_varint;
 
     #[test]
     fn test_deserialize() {
@@ -326,4 +1393,773 @@ mod test {
             println!("data[{:x}:{:x}] - {:?}", bounds.0, bounds.1, value);
         }
     }
+
+    #[test]
+    fn test_require_full() {
+        // field 1 = 150 followed by one trailing byte
+        let buffer = [0x08, 0x96, 0x01, 0x00];
+
+        let parser = FullParser::new().with_require_full(true);
+        let message = parser.deserialize(&buffer[..3]).unwrap();
+        assert_eq!(message.fields.len(), 1);
+
+        let error = parser.deserialize(&buffer).unwrap_err();
+        assert!(format!("{}", error).contains("1 bytes left over at 0x3"));
+    }
+
+    #[test]
+    fn test_string_mode() {
+        // field 1 = "héllo"
+        let buffer = [0x0a, 0x06, 0x68, 0xc3, 0xa9, 0x6c, 0x6c, 0x6f];
+
+        let mut message = FullParser::new().deserialize(&buffer).unwrap();
+        assert!(message.fields[0]
+            .as_any()
+            .downcast_mut::<BytesField>()
+            .is_some());
+
+        let mut message = FullParser::new()
+            .with_string_mode(StringMode::Utf8)
+            .deserialize(&buffer)
+            .unwrap();
+        let field = message.fields[0]
+            .as_any()
+            .downcast_mut::<StringField>()
+            .unwrap();
+        assert_eq!(field.0.data, "héllo");
+    }
+
+    #[test]
+    fn test_best_parse() {
+        // junk followed by field 1 = 150, field 2 = "abc"
+        let buffer = [
+            0xff, 0xff, 0xff, 0x08, 0x96, 0x01, 0x12, 0x03, 0x61, 0x62, 0x63,
+        ];
+
+        let (start, end, message) = PartialParser::new().best_parse(&buffer).unwrap();
+        assert_eq!((start, end), (3, buffer.len()));
+        assert_eq!(message.fields.len(), 2);
+
+        assert!(PartialParser::new().best_parse(&buffer[..3]).is_none());
+    }
+
+    #[test]
+    fn test_max_depth() {
+        fn nested(depth: usize) -> Vec<u8> {
+            // innermost field 1 = 1, wrapped into field 1 `depth` times
+            let mut buffer = vec![0x08, 0x01];
+            for _ in 0..depth {
+                let mut wrapped = vec![0x0a];
+                write_varint(buffer.len() as u64, &mut wrapped);
+                wrapped.extend(buffer);
+                buffer = wrapped;
+            }
+            buffer
+        }
+
+        let buffer = nested(5);
+        let mut message = FullParser::new()
+            .with_max_depth(3)
+            .deserialize(&buffer)
+            .unwrap();
+        let mut fields = &mut message.fields;
+        for _ in 0..3 {
+            let embedded = fields[0].as_any().downcast_mut::<EmbeddedField>().unwrap();
+            fields = &mut embedded.field.data.fields;
+        }
+        assert!(fields[0].as_any().downcast_mut::<BytesField>().is_some());
+
+        let (fields, _) = PartialParser::new()
+            .with_max_depth(3)
+            .deserialize_fields(&buffer)
+            .unwrap();
+        assert_eq!(fields.len(), 1);
+
+        // Far deeper than the default limit
+        let buffer = nested(1000);
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+        assert_eq!(message.fields.len(), 1);
+    }
+
+    #[test]
+    fn test_detect_maps() {
+        // field 3 = {1: "a", 2: 1}, field 3 = {1: "b", 2: 2}
+        let buffer = [
+            0x1a, 0x05, 0x0a, 0x01, 0x61, 0x10, 0x01, 0x1a, 0x05, 0x0a, 0x01, 0x62, 0x10, 0x02,
+        ];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+
+        assert_eq!(message.fields.len(), 1);
+        assert_eq!(message.fields[0].field_type(), FieldType::Map);
+        assert_eq!(
+            message.to_proto(),
+            "message Generated {\n\tmap<string, int32> param0 = 3; // Example: {\"a\": 1, \"b\": 2}\n}"
+        );
+        assert!(message.reencode_matches(&buffer));
+
+        let (_, spans) = FullParser::new().deserialize_with_spans(&buffer).unwrap();
+        assert_eq!((spans[0].start, spans[0].end), (0, 14));
+        assert_eq!(spans[0].fields.len(), 2);
+
+        // A single entry stays an embedded message
+        let message = FullParser::new().deserialize(&buffer[..7]).unwrap();
+        assert_eq!(message.fields[0].field_type(), FieldType::Embedded);
+
+        // field 1 = {1: "a", 2: 1}, field 3 = 5, field 1 = {1: "b", 2: 2}
+        let buffer = [
+            0x0a, 0x05, 0x0a, 0x01, 0x61, 0x10, 0x01, 0x18, 0x05, 0x0a, 0x05, 0x0a, 0x01, 0x62,
+            0x10, 0x02,
+        ];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+        let types: Vec<FieldType> = message.fields.iter().map(|x| x.field_type()).collect();
+        assert_eq!(
+            types,
+            [FieldType::Embedded, FieldType::Int32, FieldType::Embedded]
+        );
+        assert_eq!(message.serialize(), buffer);
+    }
+
+    #[test]
+    fn test_random_input_no_panic() {
+        // xorshift64, deterministic so failures are reproducible
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let full = FullParser::new().with_max_depth(8);
+        let partial = PartialParser::new().with_max_depth(8);
+        for _ in 0..10_000 {
+            let len = (next() % 48) as usize;
+            let buffer: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+
+            let _ = full.deserialize(&buffer);
+            let _ = partial.best_parse(&buffer);
+            for field_type in SIMPLE_FIELDS_ORDER.iter().chain(&[FieldType::Map]) {
+                let _ =
+                    try_deserialize_specific_field(&buffer, *field_type, &DecodeOptions::default());
+            }
+        }
+    }
+
+    #[test]
+    fn test_lenient() {
+        // field 1 = 150, garbage (field 2, wire type 7, unterminated varint)
+        let buffer = [0x08, 0x96, 0x01, 0x17, 0xff, 0xff];
+        assert!(FullParser::new().deserialize(&buffer).is_err());
+        assert!(SimpleParser::new().deserialize(&buffer).is_err());
+
+        let full = FullParser::new()
+            .with_lenient(true)
+            .deserialize(&buffer)
+            .unwrap();
+        let simple = SimpleParser::new()
+            .with_lenient(true)
+            .deserialize(&buffer)
+            .unwrap();
+        for mut message in [full, simple] {
+            assert_eq!(message.fields.len(), 2);
+            assert_eq!(message.fields[0].as_int32().unwrap().0.data, 150);
+            let unknown = message.fields[1].as_bytes().unwrap();
+            assert_eq!(unknown.0.number, 2);
+            assert_eq!(unknown.0.data, [0x17, 0xff, 0xff]);
+        }
+
+        // garbage, field 1 = 1
+        let buffer = [0x07, 0x08, 0x01];
+        let mut message = FullParser::new()
+            .with_lenient(true)
+            .deserialize(&buffer)
+            .unwrap();
+        assert_eq!(message.fields[0].as_bytes().unwrap().0.data, [0x07]);
+        assert_eq!(message.fields[1].as_int32().unwrap().0.data, 1);
+    }
+
+    #[test]
+    fn test_float_scoring() {
+        // field 1 fixed32 = 1, field 2 fixed32 = 1.0f32
+        let buffer = [0x0d, 0x01, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x80, 0x3f];
+        let mut message = FullParser::new().deserialize(&buffer).unwrap();
+        assert_eq!(message.fields[0].as_fixed32().unwrap().0.data, 1);
+        assert_eq!(message.fields[1].as_float().unwrap().0.data, 1.0);
+
+        let mut message = FullParser::new()
+            .with_float_threshold(0.5)
+            .deserialize(&buffer)
+            .unwrap();
+        assert_eq!(message.fields[1].as_float().unwrap().0.data, 1.0);
+
+        // field 2 fixed32 = 4.0f32
+        let buffer = [0x15, 0x00, 0x00, 0x80, 0x40];
+        let mut message = FullParser::new()
+            .with_float_threshold(2.0)
+            .deserialize(&buffer)
+            .unwrap();
+        assert!(message.fields[0].as_fixed32().is_some());
+
+        // field 1 fixed64 = 2, field 2 fixed64 = -2.5f64
+        let mut buffer = vec![0x09, 0x02, 0, 0, 0, 0, 0, 0, 0];
+        buffer.push(0x11);
+        buffer.extend_from_slice(&(-2.5f64).to_le_bytes());
+        let mut message = FullParser::new().deserialize(&buffer).unwrap();
+        assert_eq!(message.fields[0].as_fixed64().unwrap().0.data, 2);
+        assert_eq!(message.fields[1].as_double().unwrap().0.data, -2.5);
+
+        assert!(!is_plausible_float(f64::NAN, DEFAULT_FLOAT_THRESHOLD));
+        assert!(!is_plausible_float(1.4e-45, DEFAULT_FLOAT_THRESHOLD));
+        assert!(!is_plausible_float(3.0e38, DEFAULT_FLOAT_THRESHOLD));
+    }
+
+    #[test]
+    fn test_classify() {
+        // field 1 = 150
+        let buffer = [0x08, 0x96, 0x01];
+        let result = FullParser::new().classify(&buffer);
+        assert_eq!(
+            result.len(),
+            SIMPLE_FIELDS_ORDER.len() + UNORDERED_TYPES.len()
+        );
+
+        let consumed = |result: &[(FieldType, Result<u64>)], field_type: FieldType| {
+            result
+                .iter()
+                .find(|x| x.0 == field_type)
+                .and_then(|x| x.1.as_ref().ok().copied())
+        };
+        assert_eq!(consumed(&result, FieldType::Int32), Some(3));
+        assert_eq!(consumed(&result, FieldType::Int64), Some(3));
+        assert_eq!(consumed(&result, FieldType::String), None);
+        assert_eq!(consumed(&result, FieldType::Fixed64), None);
+        assert_eq!(consumed(&result, FieldType::Map), None);
+
+        // The parser order and options apply
+        let parser = FullParser::new()
+            .with_order(&[FieldType::UInt64, FieldType::Map])
+            .with_max_varint_bytes(1);
+        let types: Vec<FieldType> = parser.classify(&buffer).iter().map(|x| x.0).collect();
+        assert_eq!(
+            types,
+            [
+                FieldType::UInt64,
+                FieldType::Map,
+                FieldType::Enum,
+                FieldType::Repeated
+            ]
+        );
+        assert_eq!(consumed(&parser.classify(&buffer), FieldType::UInt64), None);
+    }
+
+    #[test]
+    fn test_with_order() {
+        // field 1 = "abc"
+        let buffer = [0x0a, 0x03, 0x61, 0x62, 0x63];
+        let order = [FieldType::Bytes, FieldType::String];
+
+        let mut message = FullParser::new().deserialize(&buffer).unwrap();
+        assert!(message.fields[0].as_string().is_some());
+
+        let mut message = FullParser::new()
+            .with_order(&order)
+            .deserialize(&buffer)
+            .unwrap();
+        assert_eq!(message.fields[0].as_bytes().unwrap().0.data, b"abc");
+        let mut message = SimpleParser::new()
+            .with_order(&order)
+            .deserialize(&buffer)
+            .unwrap();
+        assert!(message.fields[0].as_bytes().is_some());
+
+        let order: Vec<FieldType> = "bytes,string"
+            .split(',')
+            .map(|x| x.parse().unwrap())
+            .collect();
+        assert_eq!(order, [FieldType::Bytes, FieldType::String]);
+        assert!("text".parse::<FieldType>().is_err());
+    }
+
+    #[test]
+    fn test_field_type_display() {
+        assert_eq!(format!("{}", FieldType::SInt64), "FieldType::SInt64[5]");
+        assert_eq!(format!("{}", FieldType::Map), "FieldType::Map[20]");
+    }
+
+    #[test]
+    fn test_spans() {
+        // field 1 = 150, field 2 = {1: 1, 2: "ab"}, field 3 = 1
+        let buffer = [
+            0x08, 0x96, 0x01, 0x12, 0x06, 0x08, 0x01, 0x12, 0x02, 0x61, 0x62, 0x18, 0x01,
+        ];
+
+        let (message, spans) = FullParser::new().deserialize_with_spans(&buffer).unwrap();
+        assert_eq!(message.fields.len(), spans.len());
+        let ranges: Vec<(usize, usize)> = spans.iter().map(|x| (x.start, x.end)).collect();
+        assert_eq!(ranges, [(0, 3), (3, 11), (11, 13)]);
+
+        let nested: Vec<(usize, usize)> =
+            spans[1].fields.iter().map(|x| (x.start, x.end)).collect();
+        assert_eq!(nested, [(5, 7), (7, 11)]);
+        for level in [&spans, &spans[1].fields].iter() {
+            for pair in level.windows(2) {
+                assert!(pair[0].end <= pair[1].start);
+            }
+        }
+        assert!(spans[0].fields.is_empty());
+
+        // field 1 = {1: "a", 2: 1}, field 1 = {1: "b", 2: 2}, field 3 = 5,
+        // field 1 = {1: "c", 2: 3}
+        let buffer = [
+            0x0a, 0x05, 0x0a, 0x01, 0x61, 0x10, 0x01, 0x0a, 0x05, 0x0a, 0x01, 0x62, 0x10, 0x02,
+            0x18, 0x05, 0x0a, 0x05, 0x0a, 0x01, 0x63, 0x10, 0x03,
+        ];
+        let (message, spans) = FullParser::new().deserialize_with_spans(&buffer).unwrap();
+        assert_eq!(message.fields[0].field_type(), FieldType::Map);
+        let ranges: Vec<(usize, usize)> = spans.iter().map(|x| (x.start, x.end)).collect();
+        assert_eq!(ranges, [(0, 14), (14, 16), (16, 23)]);
+        for pair in spans.windows(2) {
+            assert!(pair[0].end <= pair[1].start);
+        }
+    }
+
+    #[test]
+    fn test_embedded_fallback_to_bytes() {
+        // field 1 = FF FF FF (neither string nor message), field 2 = 1
+        let buffer = [0x0a, 0x03, 0xff, 0xff, 0xff, 0x10, 0x01];
+
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+        let types: Vec<FieldType> = message.fields.iter().map(|x| x.field_type()).collect();
+        assert_eq!(types, [FieldType::Bytes, FieldType::Int32]);
+
+        // Bytes are kept even when not among the types to try
+        let order = [FieldType::Int32, FieldType::Embedded];
+        let (message, spans) = FullParser::new()
+            .with_order(&order)
+            .deserialize_with_spans(&buffer)
+            .unwrap();
+        assert_eq!(message.fields[0].field_type(), FieldType::Bytes);
+        assert_eq!((spans[0].start, spans[0].end), (0, 5));
+        assert!(message.reencode_matches(&buffer));
+    }
+
+    #[test]
+    fn test_min_fields() {
+        // noise, then field 1 = 150, field 2 = "abc", field 3 = 1, field 4 = 2
+        let buffer = [
+            0xff, 0xff, 0x08, 0x96, 0x01, 0x12, 0x03, 0x61, 0x62, 0x63, 0x18, 0x01, 0x20, 0x02,
+        ];
+
+        let map = PartialParser::new().deserialize_map(&buffer);
+        assert!(map.values().any(|x| x.fields.len() == 1));
+
+        let map = PartialParser::new()
+            .with_min_fields(4)
+            .deserialize_map(&buffer);
+        assert_eq!(map.keys().collect::<Vec<_>>(), [&(2, buffer.len())]);
+        assert_eq!(map[&(2, buffer.len())].fields.len(), 4);
+    }
+
+    #[test]
+    fn test_truncated_embedded() {
+        // field 1 = 150, field 2 declares 10 bytes, only 2 follow
+        let buffer = [0x08, 0x96, 0x01, 0x12, 0x0a, 0x08, 0x01];
+
+        // Embedded and bytes are both tried at the truncated field and rejected
+        let results = FullParser::new().classify(&buffer[3..]);
+        for field_type in [FieldType::Embedded, FieldType::Bytes].iter() {
+            let (_, result) = results.iter().find(|(x, _)| x == field_type).unwrap();
+            assert_eq!(
+                result.as_ref().unwrap_err().error_type(),
+                ErrorType::Truncated
+            );
+        }
+
+        let error = FullParser::new().deserialize(&buffer).unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::Truncated);
+
+        let message = FullParser::new()
+            .with_lenient(true)
+            .deserialize(&buffer)
+            .unwrap();
+        assert_eq!(message.fields[0].field_type(), FieldType::Int32);
+        assert_eq!(message.fields[1].number(), 2);
+    }
+
+    #[test]
+    fn test_error_types() {
+        // field 1 = varint cut off after the first byte
+        let error = FullParser::new().deserialize(&[0x08, 0x96]).unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::UnexpectedEof);
+
+        // field 1 = {1: 1} below the maximum depth of 0
+        let order = [FieldType::Embedded];
+        let error = FullParser::new()
+            .with_order(&order)
+            .with_max_depth(0)
+            .deserialize(&[0x0a, 0x02, 0x08, 0x01])
+            .unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::RecursionLimit);
+
+        // field 0 = 1
+        let error = FullParser::new().deserialize(&[0x00, 0x01]).unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::InvalidFieldNumber);
+    }
+
+    #[test]
+    fn test_stats() {
+        // field 1 = 150, field 2 = {1: 1, 2: FF FE}, field 3 = 3
+        let buffer = [
+            0x08, 0x96, 0x01, 0x12, 0x06, 0x08, 0x01, 0x12, 0x02, 0xff, 0xfe, 0x18, 0x03,
+        ];
+        let (message, stats) = FullParser::new().deserialize_with_stats(&buffer).unwrap();
+
+        assert_eq!(message.fields.len(), 3);
+        assert_eq!(
+            stats,
+            ParseStats {
+                wire_types: [3, 0, 2, 0, 0, 0],
+                embedded: 1,
+                max_depth: 1,
+                raw_bytes: 2,
+            }
+        );
+        assert_eq!(
+            stats.to_string(),
+            "varint 3, fixed64 0, length-delimited 2, start group 0, end group 0, fixed32 0; \
+             embedded 1, max depth 1, raw bytes 2"
+        );
+
+        // field 1 = {1: "a", 2: {1: 1}}, field 1 = {1: "b", 2: {1: 2}}
+        let buffer = [
+            0x0a, 0x07, 0x0a, 0x01, 0x61, 0x12, 0x02, 0x08, 0x01, 0x0a, 0x07, 0x0a, 0x01, 0x62,
+            0x12, 0x02, 0x08, 0x02,
+        ];
+        let (message, stats) = FullParser::new().deserialize_with_stats(&buffer).unwrap();
+
+        assert_eq!(message.fields[0].field_type(), FieldType::Map);
+        assert_eq!(
+            stats,
+            ParseStats {
+                wire_types: [2, 0, 3, 0, 0, 0],
+                embedded: 2,
+                max_depth: 1,
+                raw_bytes: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_raw_fields() {
+        // field 1 = 150, field 2 = {1: 1, 2: "ab"}, field 3 = 1
+        let buffer = [
+            0x08, 0x96, 0x01, 0x12, 0x06, 0x08, 0x01, 0x12, 0x02, 0x61, 0x62, 0x18, 0x01,
+        ];
+
+        let (message, raw) = FullParser::new().deserialize_with_raw(&buffer).unwrap();
+        assert_eq!(message.fields.len(), raw.len());
+        let concatenated: Vec<u8> = raw.iter().flat_map(|x| x.bytes.clone()).collect();
+        assert_eq!(concatenated, buffer);
+
+        let nested: Vec<u8> = raw[1].fields.iter().flat_map(|x| x.bytes.clone()).collect();
+        assert_eq!(nested, buffer[5..11]);
+        for (field, raw) in message.fields.iter().zip(raw.iter()) {
+            assert_eq!(field.serialize(), raw.bytes);
+        }
+    }
+
+    #[test]
+    fn test_max_length() {
+        // field 1 claims u32::MAX bytes
+        let mut buffer = vec![0x0a];
+        write_varint(u32::MAX as u64, &mut buffer);
+        buffer.extend_from_slice(&[0x61; 8]);
+
+        assert!(PartialParser::new()
+            .deserialize_map(&buffer)
+            .values()
+            .all(|x| x.fields.iter().all(|x| x.encoded_len() <= buffer.len())));
+        let error = FullParser::new().deserialize(&buffer).unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::Truncated);
+
+        // field 1 = "abcd", allowed by default but over a cap of 3 bytes
+        let buffer = [0x0a, 0x04, 0x61, 0x62, 0x63, 0x64];
+        assert!(FullParser::new().deserialize(&buffer).is_ok());
+        let error = FullParser::new()
+            .with_max_length(3)
+            .deserialize(&buffer)
+            .unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::LengthLimit);
+        let (fields, readed) = PartialParser::new()
+            .with_max_length(3)
+            .deserialize_fields(&buffer)
+            .unwrap();
+        assert!(fields.is_empty());
+        assert_eq!(readed, 0);
+    }
+
+    #[test]
+    fn test_scan_limit() {
+        // Two messages of 3 fields, 9 bytes each
+        let buffer = [
+            0x08, 0x01, 0x10, 0x02, 0x1a, 0x03, 0x61, 0x62, 0x63, 0x08, 0x04, 0x10, 0x05, 0x1a,
+            0x03, 0x64, 0x65, 0x66,
+        ];
+        let partial = PartialParser::new().with_min_fields(3);
+        assert!(!partial.scan_truncated(&buffer));
+        assert!(partial.deserialize_map(&buffer).contains_key(&(9, 18)));
+
+        let partial = partial.with_scan_limit(4);
+        assert!(partial.scan_truncated(&buffer));
+        assert_eq!(
+            partial.scan_offsets(&buffer).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+        let map = partial.deserialize_map(&buffer);
+        assert!(!map.is_empty());
+        assert!(map.keys().all(|&(start, _)| start < 4));
+
+        let partial = PartialParser::new().with_stride(9).with_min_fields(3);
+        assert!(!partial.scan_truncated(&buffer));
+        assert_eq!(partial.scan_offsets(&buffer).collect::<Vec<_>>(), [0, 9]);
+        let map = partial.deserialize_map(&buffer);
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), [(0, 18), (9, 18)]);
+    }
+
+    #[test]
+    fn test_embedded_trailing_bytes() {
+        // Field 3 holds field 1 = 1 followed by two bytes which are not a field
+        let buffer = [0x1a, 0x04, 0x08, 0x01, 0xff, 0xff];
+
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+        assert_eq!(message.fields[0].field_type(), FieldType::Bytes);
+
+        let (mut fields, readed) = PartialParser::new().deserialize_fields(&buffer).unwrap();
+        assert_eq!(readed, 6);
+        assert_eq!(fields[0].field_type(), FieldType::Bytes);
+        assert!(fields[0].as_embedded().is_none());
+
+        // Without the trailing bytes the payload is a nested message
+        let buffer = [0x1a, 0x02, 0x08, 0x01];
+        let (mut fields, _) = PartialParser::new().deserialize_fields(&buffer).unwrap();
+        assert_eq!(fields[0].as_embedded().unwrap().field.data.fields.len(), 1);
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+        assert_eq!(message.fields[0].field_type(), FieldType::Embedded);
+    }
+
+    #[test]
+    fn test_wire_type_skipped() {
+        let options = DecodeOptions::default();
+        // Types of the key wire type get the result of decoding with them
+        let check = |into: &[u8], wire_type: VariantTypeRaw| {
+            for (field_type, result) in FullParser::new().classify(into) {
+                let result = result.map_err(|e| e.error_type());
+                match field_type.wire_type() == wire_type {
+                    true => assert_eq!(
+                        result,
+                        try_deserialize_specific_field(into, field_type, &options)
+                            .map(|(_, i)| i)
+                            .map_err(|e| e.error_type()),
+                        "{}",
+                        field_type
+                    ),
+                    false => assert_eq!(result, Err(ErrorType::IncorrectType), "{}", field_type),
+                }
+            }
+        };
+
+        // field 1 = 150 is never tried as string, bytes or message
+        check(&[0x08, 0x96, 0x01], VariantTypeRaw::Varint);
+        // field 2 = "ab" is never tried as a varint or fixed type
+        check(&[0x12, 0x02, 0x61, 0x62], VariantTypeRaw::Buffer);
+
+        // A malformed key is left to the field to report
+        let result =
+            try_deserialize_specific_field(&[0x88], FieldType::String, &DecodeOptions::default());
+        assert_eq!(result.err().unwrap().error_type(), ErrorType::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_matching_types() {
+        let buffer = [0x12, 0x02, 0x61, 0x62];
+        let types: Vec<FieldType> = matching_types(SIMPLE_FIELDS_ORDER, &buffer)
+            .copied()
+            .collect();
+        assert_eq!(
+            types,
+            [FieldType::String, FieldType::Embedded, FieldType::Bytes]
+        );
+
+        let varint = [0x08, 0x96, 0x01];
+        assert!(matching_types(SIMPLE_FIELDS_ORDER, &varint)
+            .all(|x| x.wire_type() == VariantTypeRaw::Varint));
+        assert_eq!(
+            matching_types(SIMPLE_FIELDS_ORDER, &[0x80]).count(),
+            SIMPLE_FIELDS_ORDER.len()
+        );
+
+        // Varint types first in order are still not tried on a buffer field
+        let order = [FieldType::Int32, FieldType::Fixed64, FieldType::Bytes];
+        let message = FullParser::new()
+            .with_order(&order)
+            .deserialize(&buffer)
+            .unwrap();
+        assert_eq!(message.fields[0].field_type(), FieldType::Bytes);
+        let message = SimpleParser::new()
+            .with_order(&order)
+            .deserialize(&varint)
+            .unwrap();
+        assert_eq!(message.fields[0].field_type(), FieldType::Int32);
+        let (fields, _) = PartialParser::new()
+            .with_order(&order)
+            .deserialize_fields(&buffer)
+            .unwrap();
+        assert_eq!(fields[0].field_type(), FieldType::Bytes);
+    }
+
+    #[test]
+    fn test_max_varint_bytes() {
+        // field 1 = 0xFFFFFFFFF, a 6 byte varint
+        let buffer = [0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        assert!(FullParser::new().deserialize(&buffer).is_ok());
+
+        let parser = FullParser::new().with_max_varint_bytes(5);
+        assert!(parser.deserialize(&buffer).is_err());
+        assert!(SimpleParser::new()
+            .with_max_varint_bytes(5)
+            .deserialize(&buffer)
+            .is_err());
+        assert!(PartialParser::new()
+            .with_max_varint_bytes(5)
+            .deserialize_fields(&buffer)
+            .unwrap()
+            .0
+            .is_empty());
+
+        // field 1 = 0xFFFFFFFF takes 5 bytes
+        let buffer = [0x08, 0xff, 0xff, 0xff, 0xff, 0x0f];
+        assert!(parser.deserialize(&buffer).is_ok());
+
+        // field 2 = "ab" with its length written in 6 bytes
+        let buffer = [0x12, 0x82, 0x80, 0x80, 0x80, 0x80, 0x00, 0x61, 0x62];
+        assert!(FullParser::new().deserialize(&buffer).is_ok());
+        assert!(parser.deserialize(&buffer).is_err());
+    }
+
+    #[test]
+    fn test_empty_input() {
+        for message in [
+            FullParser::new().deserialize(&[]).unwrap(),
+            FullParser::new()
+                .with_require_full(true)
+                .deserialize(&[])
+                .unwrap(),
+            SimpleParser::new().deserialize(&[]).unwrap(),
+        ]
+        .iter()
+        {
+            assert!(message.fields.is_empty());
+            assert!(message.serialize().is_empty());
+        }
+        assert_eq!(PartialParser::new().deserialize_fields(&[]).unwrap().1, 0);
+        assert!(PartialParser::new().deserialize_map(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_partial_parser_prefix() {
+        // field 1 = 150, field 2 = "ab", then bytes which are not a field
+        let buffer = [0x08, 0x96, 0x01, 0x12, 0x02, 0x61, 0x62, 0xff, 0xff];
+        let message = Parser::deserialize(&PartialParser::new(), &buffer).unwrap();
+        assert_eq!(message.serialize(), buffer[..7]);
+        assert!(FullParser::new()
+            .with_require_full(true)
+            .deserialize(&buffer)
+            .is_err());
+    }
+
+    #[test]
+    fn test_group_matching() {
+        // group 1 { 2: 1 } closed as group 1
+        let closed = [0x0b, 0x10, 0x01, 0x0c];
+        // group 1 { 2: 1 } closed as group 2
+        let mismatched = [0x0b, 0x10, 0x01, 0x14];
+
+        let message = FullParser::default().deserialize(&closed).unwrap();
+        let types: Vec<FieldType> = message.fields.iter().map(|x| x.field_type()).collect();
+        assert_eq!(
+            types,
+            vec![FieldType::StartGroup, FieldType::Int32, FieldType::EndGroup]
+        );
+        assert_eq!(message.fields[2].number(), 1);
+        assert!(SimpleParser::default().deserialize(&closed).is_ok());
+
+        assert!(FullParser::default().deserialize(&mismatched).is_err());
+        assert!(SimpleParser::default().deserialize(&mismatched).is_err());
+        assert!(SimpleParser::default().deserialize(&[0x0c]).is_err());
+        let (fields, readed) = PartialParser::default()
+            .deserialize_fields(&mismatched)
+            .unwrap();
+        assert_eq!((fields.len(), readed), (2, 3));
+
+        // group 1 { 2: 1 } never closed, field 3 = {group 1 { 2: 1 }}
+        let unclosed = [0x0b, 0x10, 0x01];
+        let nested = [0x1a, 0x03, 0x0b, 0x10, 0x01];
+        assert!(FullParser::default().deserialize(&unclosed).is_err());
+        assert!(SimpleParser::default().deserialize(&unclosed).is_err());
+        let message = FullParser::default().deserialize(&nested).unwrap();
+        assert_eq!(message.fields[0].field_type(), FieldType::Bytes);
+        let (fields, readed) = PartialParser::default()
+            .deserialize_fields(&unclosed)
+            .unwrap();
+        assert_eq!((fields.len(), readed), (0, 0));
+        // field 1 = 150, then the unclosed group
+        let prefixed = [0x08, 0x96, 0x01, 0x0b, 0x10, 0x01];
+        let (fields, readed) = PartialParser::default()
+            .deserialize_fields(&prefixed)
+            .unwrap();
+        assert_eq!((fields.len(), readed), (1, 3));
+        let (message, consumed) = FullParser::new()
+            .deserialize_with_consumed(&prefixed)
+            .unwrap();
+        assert_eq!((message.fields.len(), consumed), (1, 3));
+    }
+
+    #[test]
+    fn test_deserialize_with_consumed() {
+        // field 1 = 150
+        let buffer = [0x08, 0x96, 0x01];
+        let (message, consumed) = FullParser::new()
+            .deserialize_with_consumed(&buffer)
+            .unwrap();
+        assert_eq!((message.fields.len(), consumed), (1, 3));
+        assert_eq!(trailing_warning(consumed, buffer.len()), None);
+
+        // field 1 = 150 followed by two bytes which are not a field
+        let buffer = [0x08, 0x96, 0x01, 0xff, 0xff];
+        let (message, consumed) = FullParser::new()
+            .deserialize_with_consumed(&buffer)
+            .unwrap();
+        assert_eq!((message.fields.len(), consumed), (1, 3));
+        assert_eq!(
+            trailing_warning(consumed, buffer.len()).unwrap(),
+            "consumed 3 of 5 bytes, 2 trailing bytes at 0x3"
+        );
+        assert!(FullParser::new().deserialize(&buffer).is_err());
+    }
+
+    #[test]
+    fn test_decode_one() {
+        // field 1 = 150, field 2 = "ab"
+        let buffer = [0x08, 0x96, 0x01, 0x12, 0x02, 0x61, 0x62];
+        let (field, rest) = decode_one(&buffer).unwrap();
+        assert_eq!(field.number(), 1);
+        assert_eq!(field.example(), "150");
+        assert_eq!(rest, &buffer[3..]);
+        assert_eq!(rest[0], 0x12);
+
+        let (field, rest) = decode_one(rest).unwrap();
+        assert_eq!(field.field_type(), FieldType::String);
+        assert!(rest.is_empty());
+        assert!(decode_one(rest).is_err());
+    }
 }
@@ -0,0 +1,219 @@
+use crate::proto::error::{Error, ErrorType, Result};
+use std::ops::Add;
+
+/// Maximum number of bytes in a varint encoding a 64-bit value
+pub const MAX_VARINT_BYTES: usize = 10;
+
+/// Write `var` as a varint to the end of `gen`
+pub fn write_varint(var: u64, gen: &mut Vec<u8>) {
+    let mut x = var;
+    match x {
+        0 => gen.push(0),
+        _ => {
+            while x != 0 {
+                gen.push(((x & 0x7F) as u8) | (((x >> 7) != 0) as u8) << 0x7);
+                x >>= 7;
+            }
+        }
+    }
+}
+
+/// Number of bytes in the varint encoding of `var`
+pub fn varint_len(var: u64) -> usize {
+    match var {
+        0 => 1,
+        _ => (64 - var.leading_zeros() as usize).div_ceil(7),
+    }
+}
+
+/// Whether `readed` bytes are more than the varint encoding of `value` takes
+///
+/// Overlong encodings, e.g. `[0x96, 0x00]` for 22, are valid but not written by
+/// canonical encoders.
+pub fn is_overlong_varint(value: u64, readed: u64) -> bool {
+    readed as usize > varint_len(value)
+}
+
+/// Read a varint from the start of `gen`
+///
+/// Varints longer than `MAX_VARINT_BYTES`, overflowing 64 bits or cut off before
+/// the terminating byte are rejected.
+///
+/// Returns (result, bytes readed)
+pub fn read_varint(gen: &[u8]) -> Result<(u64, u64)> {
+    read_varint_max(gen, MAX_VARINT_BYTES)
+}
+
+/// Read a varint of at most `max_bytes` bytes from the start of `gen`
+///
+/// A 64-bit value never takes more than `MAX_VARINT_BYTES`, larger limits act
+/// as `MAX_VARINT_BYTES`.
+///
+/// Returns (result, bytes readed)
+pub fn read_varint_max(gen: &[u8], max_bytes: usize) -> Result<(u64, u64)> {
+    let max_bytes = max_bytes.min(MAX_VARINT_BYTES);
+    let mut result: u64 = 0;
+    let mut readed: u64 = 0;
+    for (i, x) in gen.iter().enumerate() {
+        if i == max_bytes {
+            return Err(Error::new(
+                &format!("varint is longer than {} bytes", max_bytes),
+                Some(ErrorType::IncorrectData),
+            ));
+        }
+        // The last byte holds only the highest bit of a 64-bit value
+        if i == MAX_VARINT_BYTES - 1 && (x & 0x7F) > 1 {
+            return Err(Error::new(
+                "varint overflows 64-bit value",
+                Some(ErrorType::IncorrectData),
+            ));
+        }
+        result |= ((x & 0x7F) as u64) << (i * 7);
+        if x >> 7 == 0 {
+            readed = (i + 1) as u64;
+            break;
+        }
+    }
+    if readed == 0 {
+        return Err(Error::new(
+            "unterminated varint, data ended before the last byte",
+            Some(ErrorType::UnexpectedEof),
+        ));
+    }
+    log::trace!(
+        "VarInt: bytes {} -> <result {}[{}], {}[{}]>",
+        &gen[0..readed as usize]
+            .iter()
+            .fold(String::new(), |s, x| s.add(&format!("{:02x} ", x))),
+        result,
+        result,
+        readed,
+        readed,
+    );
+    Ok((result, readed))
+}
+
+/// ZigZag Encoding for sint32
+pub fn zigzag_encode32(var: i32) -> u64 {
+    match (var as u32) >> 31 {
+        0 => (((var as u32) << 1) ^ ((var as u32) >> 31)) as u64,
+        _ => ((((var as u32) ^ 0x7FFFFFFF) << 1) ^ ((var as u32) >> 31)) as u64,
+    }
+}
+
+/// ZigZag Decoding for sint32
+pub fn zigzag_decode32(var: u64) -> i32 {
+    match var & 0x1 {
+        0 => ((var << 31) ^ (var >> 1)) as i32,
+        _ => ((var << 31) ^ (var >> 1) ^ 0x7FFFFFFF) as i32,
+    }
+}
+
+/// ZigZag Encoding for sint64
+pub fn zigzag_encode64(var: i64) -> u64 {
+    match (var as u64) >> 63 {
+        0 => ((var as u64) << 1) ^ ((var as u64) >> 63),
+        _ => (((var as u64) ^ 0x7FFFFFFFFFFFFFFF) << 1) ^ ((var as u64) >> 63),
+    }
+}
+
+/// ZigZag Decoding for sint64
+pub fn zigzag_decode64(var: u64) -> i64 {
+    match var & 0x1 {
+        0 => ((var << 63) ^ (var >> 1)) as i64,
+        _ => ((var << 63) ^ (var >> 1) ^ 0x7FFFFFFFFFFFFFFF) as i64,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    quickcheck! {
+        fn prop_varint_roundtrip(var: u64) -> bool {
+            let mut gen = Vec::new();
+            write_varint(var, &mut gen);
+            gen.len() == varint_len(var) && read_varint(&gen).unwrap() == (var, gen.len() as u64)
+        }
+
+        fn prop_zigzag32_roundtrip(var: i32) -> bool {
+            zigzag_decode32(zigzag_encode32(var)) == var
+        }
+
+        fn prop_zigzag64_roundtrip(var: i64) -> bool {
+            zigzag_decode64(zigzag_encode64(var)) == var
+        }
+    }
+
+    #[test]
+    fn test_read_varint_max() {
+        let five = [0xff, 0xff, 0xff, 0xff, 0x0f];
+        let six = [0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        assert_eq!(read_varint_max(&five, 5).unwrap(), (0xffff_ffff, 5));
+        assert!(read_varint_max(&six, 5).is_err());
+        assert_eq!(
+            read_varint_max(&six, 20).unwrap(),
+            read_varint(&six).unwrap()
+        );
+        assert!(read_varint_max(&[0x01], 0).is_err());
+    }
+
+    #[test]
+    fn test_is_overlong_varint() {
+        let (value, readed) = read_varint(&[0x96, 0x00]).unwrap();
+        assert!(is_overlong_varint(value, readed));
+        let (value, readed) = read_varint(&[0x96, 0x01]).unwrap();
+        assert!(!is_overlong_varint(value, readed));
+        assert!(!is_overlong_varint(0, 1));
+        assert!(is_overlong_varint(0, 2));
+    }
+
+    #[test]
+    fn test_zigzag() {
+        for (var, encoded) in [(0, 0), (-1, 1), (1, 2), (-2, 3), (i32::MAX, 0xFFFFFFFE)].iter() {
+            assert_eq!(zigzag_encode32(*var), *encoded);
+            assert_eq!(zigzag_encode64(*var as i64), *encoded);
+        }
+        assert_eq!(zigzag_encode32(i32::MIN), 0xFFFFFFFF);
+        assert_eq!(zigzag_encode64(i64::MIN), u64::MAX);
+        assert_eq!(zigzag_decode64(u64::MAX), i64::MIN);
+    }
+
+    #[test]
+    fn test_read_varint() {
+        assert_eq!(read_varint(&[0x00]).unwrap(), (0, 1));
+        assert_eq!(read_varint(&[0xAC, 0x02]).unwrap(), (300, 2));
+        assert_eq!(
+            read_varint(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]).unwrap(),
+            (u64::MAX, 10)
+        );
+        assert!(
+            read_varint(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00])
+                .is_err()
+        );
+        assert!(
+            read_varint(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_read_varint_unterminated() {
+        assert!(read_varint(&[0x80, 0x80, 0x80]).is_err());
+        assert!(read_varint(&[]).is_err());
+    }
+
+    #[test]
+    fn test_zigzag_roundtrip() {
+        for var in [0, 1, -1, 2, -2, i32::MAX, i32::MIN].iter() {
+            assert_eq!(zigzag_decode32(zigzag_encode32(*var)), *var);
+        }
+        for var in [0, 1, -1, i32::MIN as i64, i64::MAX, i64::MIN].iter() {
+            assert_eq!(zigzag_decode64(zigzag_encode64(*var)), *var);
+        }
+        assert_eq!(zigzag_encode32(i32::MIN), u32::MAX as u64);
+        assert_eq!(zigzag_decode32(u32::MAX as u64), i32::MIN);
+        assert_eq!(zigzag_encode64(i64::MIN), u64::MAX);
+        assert_eq!(zigzag_decode64(u64::MAX), i64::MIN);
+    }
+}
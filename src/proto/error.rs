@@ -2,24 +2,32 @@ use core::fmt::{self, Debug, Display};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+#[derive(Default, Clone, Copy, PartialEq)]
 pub enum ErrorType {
+    #[default]
     GeneralError,
     ParserError,
     GeneratorError,
     IncorrectType,
     IncorrectData,
+    /// Length-delimited payload runs past the end of the buffer
+    Truncated,
+    /// Nesting of embedded messages exceeds the parser's maximum depth
+    RecursionLimit,
+    /// Data ends in the middle of a varint or a fixed-size value
+    UnexpectedEof,
+    /// Tag number is 0, reserved or above the maximum
+    InvalidFieldNumber,
+    /// Declared length exceeds the parser's maximum length
+    LengthLimit,
 }
 
 #[derive(Default)]
 pub struct Error {
     details: String,
     type_: ErrorType,
-}
-
-impl Default for ErrorType {
-    fn default() -> ErrorType {
-        ErrorType::GeneralError
-    }
+    /// Underlying error that caused this one
+    source: Option<Box<dyn std::error::Error + Send + Sync + 'static>>,
 }
 
 impl Error {
@@ -28,8 +36,36 @@ impl Error {
         Error {
             details: msg.to_string(),
             type_: type_.unwrap_or(ErrorType::GeneralError),
+            source: None,
         }
     }
+
+    /// Create a new error caused by `source`
+    pub fn with_source<E>(msg: &str, type_: Option<ErrorType>, source: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Error {
+            source: Some(Box::new(source)),
+            ..Error::new(msg, type_)
+        }
+    }
+
+    /// Create an error for a declared payload size exceeding the remaining bytes
+    pub fn truncated(size: u64, remaining: u64) -> Self {
+        Error::new(
+            &format!(
+                "declared size {} exceeds remaining {} bytes",
+                size, remaining
+            ),
+            Some(ErrorType::Truncated),
+        )
+    }
+
+    /// Type of the error
+    pub fn error_type(&self) -> ErrorType {
+        self.type_
+    }
 }
 
 impl Debug for ErrorType {
@@ -43,6 +79,11 @@ impl Debug for ErrorType {
                 ErrorType::GeneratorError => "GeneratorError",
                 ErrorType::IncorrectType => "IncorrectType",
                 ErrorType::IncorrectData => "IncorrectData",
+                ErrorType::Truncated => "Truncated",
+                ErrorType::RecursionLimit => "RecursionLimit",
+                ErrorType::UnexpectedEof => "UnexpectedEof",
+                ErrorType::InvalidFieldNumber => "InvalidFieldNumber",
+                ErrorType::LengthLimit => "LengthLimit",
             }
         )
     }
@@ -59,6 +100,11 @@ impl Display for ErrorType {
                 ErrorType::GeneratorError => "GeneratorError",
                 ErrorType::IncorrectType => "IncorrectType",
                 ErrorType::IncorrectData => "IncorrectData",
+                ErrorType::Truncated => "Truncated",
+                ErrorType::RecursionLimit => "RecursionLimit",
+                ErrorType::UnexpectedEof => "UnexpectedEof",
+                ErrorType::InvalidFieldNumber => "InvalidFieldNumber",
+                ErrorType::LengthLimit => "LengthLimit",
             }
         )
     }
@@ -75,3 +121,38 @@ impl Display for Error {
         write!(f, "{}: {{ {} }}", self.type_, self.details)
     }
 }
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|x| x.as_ref() as &(dyn std::error::Error + 'static))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error as StdError;
+
+    #[test]
+    fn test_source() {
+        let bytes = vec![0xff, 0xfe];
+        let utf8_error = String::from_utf8(bytes).unwrap_err().utf8_error();
+        let error =
+            Error::with_source("invalid string", Some(ErrorType::IncorrectData), utf8_error);
+
+        assert_eq!(error.error_type(), ErrorType::IncorrectData);
+        assert_eq!(error.to_string(), "IncorrectData: { invalid string }");
+        let source = error.source().unwrap();
+        assert_eq!(
+            source.downcast_ref::<std::str::Utf8Error>(),
+            Some(&utf8_error)
+        );
+
+        assert!(Error::new("no source", None).source().is_none());
+
+        let boxed: Box<dyn StdError> = Box::new(error);
+        assert!(boxed.source().is_some());
+    }
+}
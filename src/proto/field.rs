@@ -1,11 +1,19 @@
 #![allow(dead_code)]
 
 use core::any::Any;
-use core::convert::From;
+use core::convert::{From, TryFrom};
 use core::fmt;
 use core::ops::Add;
-
+use core::str::FromStr;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::proto::codec::{
+    is_overlong_varint, read_varint, read_varint_max, write_varint, zigzag_decode32,
+    zigzag_decode64, zigzag_encode32, zigzag_encode64,
+};
 use crate::proto::error::{Error, ErrorType, Result};
+use crate::proto::message::Message;
 use crate::proto::utils::*;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -134,6 +142,8 @@ pub enum FieldType {
     Repeated,
     StartGroup,
     EndGroup,
+    /// Protobuf map, encoded as repeated embedded key/value entries
+    Map,
 }
 
 impl fmt::Display for FieldType {
@@ -162,12 +172,102 @@ impl fmt::Display for FieldType {
                 FieldType::Repeated => "FieldType::Repeated",
                 FieldType::StartGroup => "FieldType::StartGroup",
                 FieldType::EndGroup => "FieldType::EndGroup",
+                FieldType::Map => "FieldType::Map",
             },
             *self as u8
         )
     }
 }
 
+impl VariantTypeRaw {
+    /// Whether the payload is prefixed with its length
+    pub fn is_length_delimited(&self) -> bool {
+        *self == VariantTypeRaw::Buffer
+    }
+
+    /// Short lowercase name of the wire type
+    pub fn wire_name(&self) -> &'static str {
+        match *self {
+            VariantTypeRaw::Varint => "varint",
+            VariantTypeRaw::Double => "i64",
+            VariantTypeRaw::Buffer => "len",
+            VariantTypeRaw::StartGroup => "sgroup",
+            VariantTypeRaw::EndGroup => "egroup",
+            VariantTypeRaw::Float => "i32",
+            VariantTypeRaw::Undefined => "undefined",
+        }
+    }
+}
+
+impl FieldType {
+    /// Wire type used to encode the field
+    pub fn wire_type(&self) -> VariantTypeRaw {
+        VariantTypeRaw::from(*self)
+    }
+
+    /// Name of the type in `.proto` syntax
+    pub fn proto_type(&self) -> &'static str {
+        match *self {
+            FieldType::Int32 => "int32",
+            FieldType::Int64 => "int64",
+            FieldType::UInt32 => "uint32",
+            FieldType::UInt64 => "uint64",
+            FieldType::SInt32 => "sint32",
+            FieldType::SInt64 => "sint64",
+            FieldType::Bool => "bool",
+            FieldType::Fixed64 => "fixed64",
+            FieldType::SFixed64 => "sfixed64",
+            FieldType::Double => "double",
+            FieldType::String => "string",
+            FieldType::Bytes | FieldType::Repeated => "bytes",
+            FieldType::Fixed32 => "fixed32",
+            FieldType::SFixed32 => "sfixed32",
+            FieldType::Float => "float",
+            FieldType::Enum => "enum",
+            FieldType::Embedded => "message",
+            FieldType::StartGroup | FieldType::EndGroup => "group",
+            FieldType::Map => "map",
+        }
+    }
+}
+
+impl FromStr for FieldType {
+    type Err = Error;
+
+    /// Parse a `.proto` type name, `message` and `group` stand for embedded
+    /// messages and groups
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "int32" => Ok(FieldType::Int32),
+            "int64" => Ok(FieldType::Int64),
+            "uint32" => Ok(FieldType::UInt32),
+            "uint64" => Ok(FieldType::UInt64),
+            "sint32" => Ok(FieldType::SInt32),
+            "sint64" => Ok(FieldType::SInt64),
+            "bool" => Ok(FieldType::Bool),
+            "fixed64" => Ok(FieldType::Fixed64),
+            "sfixed64" => Ok(FieldType::SFixed64),
+            "double" => Ok(FieldType::Double),
+            "fixed32" => Ok(FieldType::Fixed32),
+            "sfixed32" => Ok(FieldType::SFixed32),
+            "float" => Ok(FieldType::Float),
+            "string" => Ok(FieldType::String),
+            "bytes" => Ok(FieldType::Bytes),
+            "message" => Ok(FieldType::Embedded),
+            "group" => Ok(FieldType::StartGroup),
+            _ => Err(Error::new(
+                &format!(
+                    "unknown field type `{}`, expected one of int32, int64, uint32, uint64, \
+                     sint32, sint64, bool, fixed64, sfixed64, double, fixed32, sfixed32, \
+                     float, string, bytes, message, group",
+                    name
+                ),
+                Some(ErrorType::ParserError),
+            )),
+        }
+    }
+}
+
 impl From<FieldType> for VariantTypeRaw {
     fn from(item: FieldType) -> Self {
         match item {
@@ -180,9 +280,11 @@ impl From<FieldType> for VariantTypeRaw {
             | FieldType::Bool
             | FieldType::Enum => VariantTypeRaw::Varint,
             FieldType::Fixed64 | FieldType::SFixed64 | FieldType::Double => VariantTypeRaw::Double,
-            FieldType::Embedded | FieldType::Repeated | FieldType::Bytes | FieldType::String => {
-                VariantTypeRaw::Buffer
-            }
+            FieldType::Embedded
+            | FieldType::Repeated
+            | FieldType::Bytes
+            | FieldType::String
+            | FieldType::Map => VariantTypeRaw::Buffer,
             FieldType::StartGroup => VariantTypeRaw::StartGroup,
             FieldType::EndGroup => VariantTypeRaw::EndGroup,
             FieldType::Fixed32 | FieldType::SFixed32 | FieldType::Float => VariantTypeRaw::Float,
@@ -200,19 +302,20 @@ impl From<FieldType> for Box<dyn FieldTrait> {
             FieldType::SInt32 => Box::new(SInt32Field::default()),
             FieldType::SInt64 => Box::new(SInt64Field::default()),
             FieldType::Bool => Box::new(BoolField::default()),
-            FieldType::Enum => Box::new(Field::default()), //Box::new(EnumField::default()),
+            FieldType::Enum => Box::new(EnumField::default()),
             FieldType::Fixed64 => Box::new(Fixed64Field::default()),
             FieldType::SFixed64 => Box::new(SFixed64Field::default()),
             FieldType::Double => Box::new(DoubleField::default()),
             FieldType::Embedded => Box::new(EmbeddedField::default()),
-            FieldType::Repeated => Box::new(Field::default()), //Box::new(RepeatedField::default()),
+            FieldType::Repeated => Box::new(Field::with_type(FieldType::Repeated)), //Box::new(RepeatedField::default()),
             FieldType::Bytes => Box::new(BytesField::default()),
             FieldType::String => Box::new(StringField::default()),
             FieldType::StartGroup => Box::new(StartGroupField::default()),
-            FieldType::EndGroup => Box::new(Field::default()), //Box::new(EndGroupField::default()),
+            FieldType::EndGroup => Box::new(EndGroupField::default()),
             FieldType::Fixed32 => Box::new(Fixed32Field::default()),
-            FieldType::SFixed32 => Box::new(Fixed64Field::default()),
+            FieldType::SFixed32 => Box::new(SFixed32Field::default()),
             FieldType::Float => Box::new(FloatField::default()),
+            FieldType::Map => Box::new(MapField::default()),
         }
     }
 }
@@ -243,6 +346,223 @@ impl fmt::Display for FieldLabel {
     }
 }
 
+/// Validation applied to the payload of string fields
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StringMode {
+    /// Printable 7-bit ASCII only, rejects most binary data (default)
+    Ascii,
+    /// Any valid UTF-8 text
+    Utf8,
+}
+
+/// Radix of integer values in `repr` and `.proto` examples
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Radix {
+    /// Written as `0x7b`
+    Hex,
+    /// Written as `123`
+    Dec,
+}
+
+/// Byte order of fixed32/fixed64 payloads
+///
+/// Only decoding is affected, fields are always serialized little-endian.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Endianness {
+    /// Standard protobuf encoding (default)
+    Little,
+    /// Used by some proprietary framings
+    Big,
+}
+
+impl Endianness {
+    /// Payload of a fixed field reordered to little-endian
+    fn to_le<const N: usize>(self, ptr: &[u8]) -> [u8; N] {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(&ptr[..N]);
+        if self == Endianness::Big {
+            bytes.reverse();
+        }
+        bytes
+    }
+}
+
+/// Options used by parsers while decoding a single field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecodeOptions {
+    /// Validation of string fields
+    pub string_mode: StringMode,
+    /// Byte order of fixed32, sfixed32, float, fixed64, sfixed64 and double
+    pub endianness: Endianness,
+    /// Largest accepted payload of a length-delimited field
+    pub max_length: u64,
+    /// Longest accepted key, value or length varint, larger limits act as
+    /// `MAX_VARINT_BYTES`
+    pub max_varint_bytes: usize,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            string_mode: StringMode::Ascii,
+            endianness: Endianness::Little,
+            max_length: u64::MAX,
+            max_varint_bytes: MAX_VARINT_BYTES,
+        }
+    }
+}
+
+/// Default number of bytes shown in examples of bytes fields
+pub const DEFAULT_MAX_EXAMPLE_BYTES: usize = 32;
+
+/// Placeholder name of a rendered field from its type and declaration index
+///
+/// Closures can be used as well, e.g. `Arc::new(|_, index| format!("f{}", index))`.
+pub type NamingStrategy = Arc<dyn Fn(FieldType, usize) -> String + Send + Sync>;
+
+/// Default naming: `param0`, `param1`, ...
+pub fn param_name(_type: FieldType, index: usize) -> String {
+    format!("param{}", index)
+}
+
+/// Naming by inferred kind of value, e.g. `num_1`, `str_field_2`, `nested_msg_4`
+pub fn semantic_name(type_: FieldType, index: usize) -> String {
+    let kind = match type_ {
+        FieldType::String => "str_field",
+        FieldType::Bytes => "bytes_field",
+        FieldType::Embedded => "nested_msg",
+        FieldType::Map => "map_field",
+        FieldType::Bool => "flag",
+        FieldType::Float | FieldType::Double => "real",
+        FieldType::StartGroup | FieldType::EndGroup => "group",
+        _ => "num",
+    };
+    format!("{}_{}", kind, index)
+}
+
+/// Options used while rendering fields as `.proto`
+#[derive(Clone)]
+pub struct RenderOptions {
+    /// Bytes shown in examples of bytes fields, longer payloads end with `...`
+    pub max_example_bytes: usize,
+    /// Indentation of one nesting level, e.g. `"\t"` or `"  "`
+    pub indent: String,
+    /// Names of rendered fields, `param_name` by default
+    pub naming: NamingStrategy,
+    /// Render bytes payloads made of canonical varints as packed `repeated`
+    pub detect_packed: bool,
+    /// Render declarations by ascending field number instead of parse order
+    pub sorted: bool,
+    /// Radix of integer values, `None` keeps hex in `repr` and decimal in
+    /// examples
+    pub radix: Option<Radix>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            max_example_bytes: DEFAULT_MAX_EXAMPLE_BYTES,
+            indent: "\t".to_string(),
+            naming: Arc::new(param_name),
+            detect_packed: false,
+            sorted: false,
+            radix: None,
+        }
+    }
+}
+
+impl fmt::Debug for RenderOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RenderOptions")
+            .field("max_example_bytes", &self.max_example_bytes)
+            .field("indent", &self.indent)
+            .field("naming", &"<fn>")
+            .field("detect_packed", &self.detect_packed)
+            .field("sorted", &self.sorted)
+            .field("radix", &self.radix)
+            .finish()
+    }
+}
+
+impl RenderOptions {
+    /// Indent every line of `lines` by one level, nested lines keep their
+    /// own indentation so depth `n` ends up as `indent.repeat(n)`
+    pub fn indent_lines(&self, lines: &[String]) -> String {
+        lines.iter().fold(String::new(), |data_repr, x| {
+            let nested = format!("\n{}", self.indent);
+            data_repr.add(&format!("{}{}", nested, x.replace('\n', &nested)))
+        })
+    }
+}
+
+/// Hex example of `data` showing at most `max` bytes
+fn hex_example(data: &[u8], max: usize) -> String {
+    let hex = data
+        .iter()
+        .take(max)
+        .map(|x| format!("{:02X}", x))
+        .collect::<Vec<String>>()
+        .join(" ");
+    match data.len() > max {
+        true => format!("{} ...", hex),
+        false => hex,
+    }
+}
+
+/// `data` as text when it is valid UTF-8 without control characters other
+/// than whitespace
+pub(crate) fn printable_str(data: &[u8]) -> Option<&str> {
+    let text = std::str::from_utf8(data).ok()?;
+    match !text.is_empty() && text.chars().all(|x| !x.is_control() || x.is_whitespace()) {
+        true => Some(text),
+        false => None,
+    }
+}
+
+/// `// as string: ..` comment for printable bytes, empty otherwise
+fn as_string_comment(data: &[u8]) -> String {
+    printable_str(data)
+        .map(|x| format!(" // as string: {:?}", x))
+        .unwrap_or_default()
+}
+
+/// Readings of a 32-bit varint wire value as each 32-bit varint type, e.g.
+/// ` // int32: -1, uint32: 4294967295, sint32: -2147483648`
+///
+/// The decoded type is a guess, the comment lets the reader pick another one.
+fn varint32_comment(raw: u32) -> String {
+    format!(
+        " // int32: {}, uint32: {}, sint32: {}",
+        raw as i32,
+        raw,
+        zigzag_decode32(raw as u64)
+    )
+}
+
+/// Readings of a 64-bit varint wire value as each 64-bit varint type, see
+/// `varint32_comment`
+fn varint64_comment(raw: u64) -> String {
+    format!(
+        " // int64: {}, uint64: {}, sint64: {}",
+        raw as i64,
+        raw,
+        zigzag_decode64(raw)
+    )
+}
+
+/// Low and high 32-bit halves of a fixed64 value and their floats, for
+/// formats packing two fixed32 values in one fixed64
+fn fixed64_halves_comment(value: u64) -> String {
+    let (low, high) = (value as u32, (value >> 32) as u32);
+    format!(
+        " // halves: low {:#010x} (float {:?}), high {:#010x} (float {:?})",
+        low,
+        f32::from_bits(low),
+        high,
+        f32::from_bits(high)
+    )
+}
+
 /// A Protobuf Field
 #[derive(Debug, Clone, PartialEq)]
 pub struct Field<T> {
@@ -256,6 +576,12 @@ pub struct Field<T> {
     pub number: u64,
     /// Data
     pub data: T,
+    /// Varint value decoded from more bytes than needed, e.g. `[0x96, 0x00]`
+    /// for 22
+    ///
+    /// Only values of varint fields are checked, overlong keys and lengths are
+    /// counted by `encoder_fingerprint`.
+    pub non_canonical: bool,
 }
 
 impl Default for Field<Vec<u8>> {
@@ -266,6 +592,20 @@ impl Default for Field<Vec<u8>> {
             type_: FieldType::Bytes,
             number: 0,
             data: Vec::new(),
+            non_canonical: false,
+        }
+    }
+}
+
+impl Field<Vec<u8>> {
+    /// Raw field standing in for a type without its own implementation
+    ///
+    /// The type is kept after deserialization, so the field reports the type it
+    /// was created for.
+    fn with_type(type_: FieldType) -> Self {
+        Field {
+            type_,
+            ..Field::default()
         }
     }
 }
@@ -273,9 +613,81 @@ impl Default for Field<Vec<u8>> {
 pub trait FieldTrait {
     fn serialize(&self) -> Vec<u8>;
     fn serialize_into(&self, into: &mut Vec<u8>);
+    /// Length of `serialize` output, computed without building it
+    fn encoded_len(&self) -> usize;
     fn deserialize(&mut self, into: &[u8]) -> Result<u64>;
+    /// Deserialize using parser provided `DecodeOptions`
+    fn deserialize_with(&mut self, into: &[u8], _options: &DecodeOptions) -> Result<u64> {
+        self.deserialize(into)
+    }
     fn as_any(&mut self) -> &mut dyn Any;
+    /// Shared counterpart of `as_any`
+    fn as_any_ref(&self) -> &dyn Any;
+    /// Clone the field behind a trait object
+    fn clone_box(&self) -> Box<dyn FieldTrait>;
+    /// Whether `other` has the same type and encodes to the same bytes
+    ///
+    /// Names and labels are not compared, they don't affect the wire format.
+    fn eq_dyn(&self, other: &dyn FieldTrait) -> bool {
+        self.field_type() == other.field_type() && self.serialize() == other.serialize()
+    }
     fn repr(&self) -> String;
+    /// Debug representation using `RenderOptions`
+    fn repr_with(&self, _options: &RenderOptions) -> String {
+        self.repr()
+    }
+    /// Tag number of the field
+    fn number(&self) -> u64;
+    /// Name of the field, empty unless set by `set_name`
+    fn name(&self) -> &str;
+    /// Set name of the field, e.g. from a known schema
+    fn set_name(&mut self, name: &str);
+    /// Set rule of the field, e.g. `Repeated` for a tag seen several times
+    fn set_rule(&mut self, rule: FieldLabel);
+    /// Rule of the field, `Optional` unless set by `set_rule`
+    fn rule(&self) -> FieldLabel;
+    /// Protobuf type of the field
+    fn field_type(&self) -> FieldType;
+    /// Decoded value as shown in `.proto` comments
+    fn example(&self) -> String;
+    /// Decoded value shortened according to `RenderOptions`
+    fn example_with(&self, _options: &RenderOptions) -> String {
+        self.example()
+    }
+    /// Declaration of the field in `.proto` syntax
+    fn to_str(&self, name: &str) -> String;
+    /// Declaration of the field using `RenderOptions`
+    fn to_str_with(&self, name: &str, _options: &RenderOptions) -> String {
+        self.to_str(name)
+    }
+    /// Field in protoscope text syntax, e.g. `1: 150`
+    fn to_protoscope(&self) -> String {
+        format!(
+            "{}: {}",
+            self.number(),
+            protoscope_value(self.field_type(), &self.example())
+        )
+    }
+}
+
+/// Value in protoscope syntax from the decoded type and example
+fn protoscope_value(type_: FieldType, example: &str) -> String {
+    match type_ {
+        FieldType::SInt32 | FieldType::SInt64 => format!("{}z", example),
+        FieldType::Fixed64 | FieldType::SFixed64 => format!("{}i64", example),
+        FieldType::Fixed32 | FieldType::SFixed32 | FieldType::Float => format!("{}i32", example),
+        // Integral literals are varints in protoscope, keep doubles fractional
+        FieldType::Double if example.chars().all(|x| x.is_ascii_digit() || x == '-') => {
+            format!("{}.0", example)
+        }
+        FieldType::String => format!("{{{}}}", example),
+        FieldType::Bytes | FieldType::Repeated | FieldType::Embedded | FieldType::Map => {
+            format!("{{`{}`}}", example.replace(' ', "").to_lowercase())
+        }
+        FieldType::StartGroup => "SGROUP".to_string(),
+        FieldType::EndGroup => "EGROUP".to_string(),
+        _ => example.to_string(),
+    }
 }
 
 // impl fmt::Display for dyn FieldTrait {
@@ -285,31 +697,92 @@ pub trait FieldTrait {
 impl<T> Field<T> {
     fn new(name: String, rule: FieldLabel, type_: FieldType, number: u64, data: T) -> Self {
         Field {
-            name: name,
-            rule: rule,
-            type_: type_,
-            number: number,
-            data: data,
+            name,
+            rule,
+            type_,
+            number,
+            data,
+            non_canonical: false,
         }
     }
 
+    /// Length of the encoded key
+    fn key_len(&self) -> usize {
+        varint_len(generate_key(self.number, self.type_.wire_type() as u8))
+    }
+
     fn repr(&self, data_repr: &str) -> String {
         format!(
-            "{:#x} {} <{} == {}> = {}",
+            "{:#x} {} <{} == {}> = {}{}",
             self.number,
             self.rule,
             self.type_,
-            VariantTypeRaw::from(self.type_),
-            data_repr.clone()
+            self.type_.wire_type(),
+            data_repr,
+            self.non_canonical_comment()
+        )
+    }
+
+    /// `// non-canonical varint` comment of an overlong varint value
+    fn non_canonical_comment(&self) -> &'static str {
+        match self.non_canonical {
+            true => " // non-canonical varint",
+            false => "",
+        }
+    }
+
+    /// Stored name of the field, `name` if none was set
+    fn name_or<'a>(&'a self, name: &'a str) -> &'a str {
+        stored_name_or(&self.name, name)
+    }
+
+    fn to_str(&self, name: &str, example: &str) -> String {
+        format!(
+            "{} {} {} = {}; // Example: {}{}",
+            self.rule.to_string().to_lowercase(),
+            self.type_.proto_type(),
+            self.name_or(name),
+            self.number,
+            example,
+            self.non_canonical_comment()
         )
     }
 }
 
+impl<T: fmt::Display + fmt::LowerHex> Field<T> {
+    /// Integer value written in `radix`
+    fn radix_value(&self, radix: Radix) -> String {
+        match radix {
+            Radix::Hex => format!("{:#x}", self.data),
+            Radix::Dec => format!("{}", self.data),
+        }
+    }
+
+    /// `repr` of the value in `options.radix`, hex without one, followed by
+    /// `comment`
+    fn repr_radix(&self, options: &RenderOptions, comment: &str) -> String {
+        self.repr(&self.radix_value(options.radix.unwrap_or(Radix::Hex))) + comment
+    }
+
+    /// Example of the value in `options.radix`, decimal without one
+    fn example_radix(&self, options: &RenderOptions) -> String {
+        self.radix_value(options.radix.unwrap_or(Radix::Dec))
+    }
+}
+
 impl FieldTrait for Field<Vec<u8>> {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
         let data_repr = self.data.iter().fold(String::new(), |data_repr, x| {
             data_repr.add(&format!(" {:02X}", x))
@@ -317,12 +790,52 @@ impl FieldTrait for Field<Vec<u8>> {
         self.repr(&data_repr)
     }
 
+    fn number(&self) -> u64 {
+        self.number
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.type_
+    }
+
+    fn example(&self) -> String {
+        hex_example(&self.data, self.data.len())
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        hex_example(&self.data, options.max_example_bytes)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.to_str(name, &self.example_with(options))
+    }
+
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.number, VariantTypeRaw::from(self.type_) as u8),
+        write_varint(
+            generate_key(self.number, self.type_.wire_type() as u8),
             into,
         );
-        serialize_varint_into(self.data.len() as u64, into);
+        write_varint(self.data.len() as u64, into);
         into.extend_from_slice(&self.data);
     }
 
@@ -332,11 +845,20 @@ impl FieldTrait for Field<Vec<u8>> {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.key_len() + varint_len(self.data.len() as u64) + self.data.len()
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Buffer`
-        if type_int != VariantTypeRaw::Buffer as u8 {
+        if !VariantTypeRaw::from(type_int).is_length_delimited() {
             return Err(Error::new(
                 &format!(
                     "expected `{}` found `{}`",
@@ -349,29 +871,22 @@ impl FieldTrait for Field<Vec<u8>> {
 
         if readed as usize >= into.len() {
             return Err(Error::new(
-                &format!("insufficient amount of data to continue parsing"),
+                "insufficient amount of data to continue parsing",
                 Some(ErrorType::IncorrectData),
             ));
         }
 
-        let (size, readed_1) = deserialize_varint(&into[readed as usize..])?;
-        if (readed + readed_1 + size) as usize > into.len() {
-            return Err(Error::new(
-                &format!(
-                    "expected {} bytes, found `{}`",
-                    (readed + readed_1 + size),
-                    into.len()
-                ),
-                Some(ErrorType::IncorrectData),
-            ));
+        let (size, readed_1) = read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let remaining = into.len() as u64 - (readed + readed_1);
+        if size > remaining {
+            return Err(Error::truncated(size, remaining));
         }
         let value =
             into[(readed + readed_1) as usize..(readed + readed_1 + size) as usize].to_vec();
         self.data = value;
         self.number = index;
-        self.type_ = FieldType::Bytes;
 
-        Ok(readed + readed + readed_1 + size)
+        Ok(readed + readed_1 + size)
     }
 }
 
@@ -380,24 +895,27 @@ impl FieldTrait for Field<Vec<u8>> {
 pub struct Int32Field(pub Field<i32>);
 
 impl Int32Field {
-    fn new(name: String, number: u64, data: i32) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::Int32, number, data),
-        }
+    pub fn new(name: String, number: u64, data: i32) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::Int32,
+            number,
+            data,
+        ))
     }
 }
 
 impl Default for Int32Field {
     fn default() -> Self {
-        Int32Field {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::Int32,
-                number: 0,
-                data: 0,
-            },
-        }
+        Int32Field(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::Int32,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
     }
 }
 
@@ -406,103 +924,69 @@ impl FieldTrait for Int32Field {
         self
     }
 
-    fn repr(&self) -> String {
-        self.0.repr(&format!("{:#x}", self.0.data))
+    fn as_any_ref(&self) -> &dyn Any {
+        self
     }
 
-    fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
-            into,
-        );
-        serialize_varint_into(self.0.data as u64, into);
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
     }
 
-    fn serialize(&self) -> Vec<u8> {
-        let mut gen = Vec::new();
-        self.serialize_into(&mut gen);
-        gen
+    fn repr(&self) -> String {
+        self.repr_with(&RenderOptions::default())
     }
 
-    fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
-        let (index, type_int) = parse_key(key);
-        // Check Type if queal to `VariantTypeRaw::Varint`
-        if type_int != VariantTypeRaw::Varint as u8 {
-            return Err(Error::new(
-                &format!(
-                    "expected `{}` found `{}`",
-                    VariantTypeRaw::Varint,
-                    VariantTypeRaw::from(type_int)
-                ),
-                Some(ErrorType::IncorrectType),
-            ));
-        }
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        self.0
+            .repr_radix(options, &varint32_comment(self.0.data as u32))
+    }
 
-        if readed as usize >= into.len() {
-            return Err(Error::new(
-                &format!("insufficient amount of data to continue parsing"),
-                Some(ErrorType::IncorrectData),
-            ));
-        }
+    fn number(&self) -> u64 {
+        self.0.number
+    }
 
-        let (value, readed_x) = deserialize_varint(&into[readed as usize..])?;
-        if (value >> 0x32) != 0 {
-            return Err(Error::new(
-                "expected `Int32` found `U/Int64`",
-                Some(ErrorType::IncorrectData),
-            ));
-        }
+    fn name(&self) -> &str {
+        &self.0.name
+    }
 
-        self.0.data = value as i32;
-        self.0.number = index;
-        self.0.type_ = FieldType::Int32;
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
 
-        Ok(readed + readed_x)
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
     }
-}
 
-/// Filed with type Int64
-#[derive(Debug, Clone, PartialEq)]
-pub struct Int64Field(pub Field<i64>);
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
 
-impl Int64Field {
-    fn new(name: String, number: u64, data: i64) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::Int64, number, data),
-        }
+    fn field_type(&self) -> FieldType {
+        self.0.type_
     }
-}
 
-impl Default for Int64Field {
-    fn default() -> Self {
-        Int64Field {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::Int64,
-                number: 0,
-                data: 0,
-            },
-        }
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
     }
-}
 
-impl FieldTrait for Int64Field {
-    fn as_any(&mut self) -> &mut dyn Any {
-        self
+    fn example_with(&self, options: &RenderOptions) -> String {
+        self.0.example_radix(options)
     }
 
-    fn repr(&self) -> String {
-        self.0.repr(&format!("{:#x}", self.0.data))
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options)) + &varint32_comment(self.0.data as u32)
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
-        serialize_varint_into(self.0.data as u64, into);
+        write_varint(self.0.data as u64, into);
     }
 
     fn serialize(&self) -> Vec<u8> {
@@ -511,9 +995,18 @@ impl FieldTrait for Int64Field {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + varint_len(self.0.data as u64)
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Varint`
         if type_int != VariantTypeRaw::Varint as u8 {
             return Err(Error::new(
@@ -528,14 +1021,29 @@ impl FieldTrait for Int64Field {
 
         if readed as usize >= into.len() {
             return Err(Error::new(
-                &format!("insufficient amount of data to continue parsing"),
+                "insufficient amount of data to continue parsing",
                 Some(ErrorType::IncorrectData),
             ));
         }
 
-        let (value, readed_x) = deserialize_varint(&into[readed as usize..])?;
+        let (value, readed_x) =
+            read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let non_canonical = is_overlong_varint(value, readed_x);
+        // Negative values are sign-extended to 64 bits and take 10 bytes, some
+        // encoders write them as 32-bit values instead
+        let value = match u32::try_from(value) {
+            Ok(value) => Ok(value as i32),
+            Err(_) => i32::try_from(value as i64),
+        }
+        .map_err(|_| {
+            Error::new(
+                "expected `Int32` found `U/Int64`",
+                Some(ErrorType::IncorrectData),
+            )
+        })?;
 
-        self.0.data = value as i64;
+        self.0.data = value;
+        self.0.non_canonical = non_canonical;
         self.0.number = index;
         self.0.type_ = FieldType::Int32;
 
@@ -543,134 +1051,231 @@ impl FieldTrait for Int64Field {
     }
 }
 
-/// Filed with type UInt32
+/// Filed with type Enum
+///
+/// Enum values are encoded as int32 varints.
 #[derive(Debug, Clone, PartialEq)]
-pub struct UInt32Field(pub Field<u32>);
-
-impl UInt32Field {
-    fn new(name: String, number: u64, data: u32) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::UInt32, number, data),
-        }
+pub struct EnumField(pub Field<i32>);
+
+impl EnumField {
+    pub fn new(name: String, number: u64, data: i32) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::Enum,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for UInt32Field {
+impl Default for EnumField {
     fn default() -> Self {
-        UInt32Field {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::UInt32,
-                number: 0,
-                data: 0,
-            },
-        }
+        EnumField(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::Enum,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for UInt32Field {
+impl FieldTrait for EnumField {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        self.0.repr(&format!("{:#x}", self.0.data))
+        self.repr_with(&RenderOptions::default())
     }
 
-    fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
-            into,
-        );
-        serialize_varint_into(self.0.data as u64, into);
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        self.0.repr_radix(options, "")
     }
 
-    fn serialize(&self) -> Vec<u8> {
-        let mut gen = Vec::new();
-        self.serialize_into(&mut gen);
-        gen
+    fn number(&self) -> u64 {
+        self.0.number
     }
 
-    fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
-        let (index, type_int) = parse_key(key);
-        // Check Type if queal to `VariantTypeRaw::Varint`
-        if type_int != VariantTypeRaw::Varint as u8 {
-            return Err(Error::new(
-                &format!(
-                    "expected `{}` found `{}`",
-                    VariantTypeRaw::Varint,
-                    VariantTypeRaw::from(type_int)
-                ),
-                Some(ErrorType::IncorrectType),
-            ));
-        }
+    fn name(&self) -> &str {
+        &self.0.name
+    }
 
-        if readed as usize >= into.len() {
-            return Err(Error::new(
-                &format!("insufficient amount of data to continue parsing"),
-                Some(ErrorType::IncorrectData),
-            ));
-        }
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
 
-        let (value, readed_x) = deserialize_varint(&into[readed as usize..])?;
-        if (value >> 0x32) != 0 {
-            return Err(Error::new(
-                "expected `UInt32` found `U/Int64`",
-                Some(ErrorType::IncorrectData),
-            ));
-        }
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
 
-        self.0.data = value as u32;
-        self.0.number = index;
-        self.0.type_ = FieldType::UInt32;
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
 
-        Ok(readed + readed_x)
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        self.0.example_radix(options)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options))
+    }
+
+    fn serialize_into(&self, into: &mut Vec<u8>) {
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
+            into,
+        );
+        write_varint(self.0.data as u64, into);
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut gen = Vec::new();
+        self.serialize_into(&mut gen);
+        gen
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + varint_len(self.0.data as u64)
+    }
+
+    fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        // Same wire format as int32, only the declared type differs
+        let mut field = Int32Field::default();
+        let readed = field.deserialize_with(into, options)?;
+
+        self.0 = Field {
+            type_: FieldType::Enum,
+            ..field.0
+        };
+
+        Ok(readed)
     }
 }
 
-/// Filed with type UInt64
+/// Filed with type Int64
 #[derive(Debug, Clone, PartialEq)]
-pub struct UInt64Field(pub Field<u64>);
+pub struct Int64Field(pub Field<i64>);
 
-impl UInt64Field {
-    fn new(name: String, number: u64, data: u64) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::UInt64, number, data),
-        }
+impl Int64Field {
+    pub fn new(name: String, number: u64, data: i64) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::Int64,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for UInt64Field {
+impl Default for Int64Field {
     fn default() -> Self {
-        UInt64Field {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::UInt64,
-                number: 0,
-                data: 0,
-            },
-        }
+        Int64Field(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::Int64,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for UInt64Field {
+impl FieldTrait for Int64Field {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        self.0.repr(&format!("{:#x}", self.0.data))
+        self.repr_with(&RenderOptions::default())
+    }
+
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        self.0
+            .repr_radix(options, &varint64_comment(self.0.data as u64))
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        self.0.example_radix(options)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options)) + &varint64_comment(self.0.data as u64)
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
-        serialize_varint_into(self.0.data as u64, into);
+        write_varint(self.0.data as u64, into);
     }
 
     fn serialize(&self) -> Vec<u8> {
@@ -679,9 +1284,18 @@ impl FieldTrait for UInt64Field {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + varint_len(self.0.data as u64)
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Varint`
         if type_int != VariantTypeRaw::Varint as u8 {
             return Err(Error::new(
@@ -696,62 +1310,120 @@ impl FieldTrait for UInt64Field {
 
         if readed as usize >= into.len() {
             return Err(Error::new(
-                &format!("insufficient amount of data to continue parsing"),
+                "insufficient amount of data to continue parsing",
                 Some(ErrorType::IncorrectData),
             ));
         }
 
-        let (value, readed_x) = deserialize_varint(&into[readed as usize..])?;
+        let (value, readed_x) =
+            read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let non_canonical = is_overlong_varint(value, readed_x);
 
-        self.0.data = value as u64;
+        self.0.data = value as i64;
+        self.0.non_canonical = non_canonical;
         self.0.number = index;
-        self.0.type_ = FieldType::Int32;
+        self.0.type_ = FieldType::Int64;
 
         Ok(readed + readed_x)
     }
 }
 
-/// Filed with type SInt32
+/// Filed with type UInt32
 #[derive(Debug, Clone, PartialEq)]
-pub struct SInt32Field(pub Field<i32>);
+pub struct UInt32Field(pub Field<u32>);
 
-impl SInt32Field {
-    fn new(name: String, number: u64, data: i32) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::UInt32, number, data),
-        }
+impl UInt32Field {
+    pub fn new(name: String, number: u64, data: u32) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::UInt32,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for SInt32Field {
+impl Default for UInt32Field {
     fn default() -> Self {
-        SInt32Field {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::SInt32,
-                number: 0,
-                data: 0,
-            },
-        }
+        UInt32Field(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::UInt32,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for SInt32Field {
+impl FieldTrait for UInt32Field {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        self.0.repr(&format!("{:#x}", self.0.data))
+        self.repr_with(&RenderOptions::default())
+    }
+
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        self.0.repr_radix(options, &varint32_comment(self.0.data))
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        self.0.example_radix(options)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options)) + &varint32_comment(self.0.data)
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
-        serialize_varint_into(encode_zigzag_s32(self.0.data), into);
+        write_varint(self.0.data as u64, into);
     }
 
     fn serialize(&self) -> Vec<u8> {
@@ -760,9 +1432,18 @@ impl FieldTrait for SInt32Field {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + varint_len(self.0.data as u64)
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Varint`
         if type_int != VariantTypeRaw::Varint as u8 {
             return Err(Error::new(
@@ -777,20 +1458,23 @@ impl FieldTrait for SInt32Field {
 
         if readed as usize >= into.len() {
             return Err(Error::new(
-                &format!("insufficient amount of data to continue parsing"),
+                "insufficient amount of data to continue parsing",
                 Some(ErrorType::IncorrectData),
             ));
         }
 
-        let (value, readed_x) = deserialize_varint(&into[readed as usize..])?;
+        let (value, readed_x) =
+            read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let non_canonical = is_overlong_varint(value, readed_x);
         if (value >> 0x32) != 0 {
             return Err(Error::new(
-                "expected `SUInt32` found `U/Int64`",
+                "expected `UInt32` found `U/Int64`",
                 Some(ErrorType::IncorrectData),
             ));
         }
 
-        self.0.data = decode_zigzag_s32(value);
+        self.0.data = value as u32;
+        self.0.non_canonical = non_canonical;
         self.0.number = index;
         self.0.type_ = FieldType::UInt32;
 
@@ -800,45 +1484,100 @@ impl FieldTrait for SInt32Field {
 
 /// Filed with type UInt64
 #[derive(Debug, Clone, PartialEq)]
-pub struct SInt64Field(pub Field<i64>);
+pub struct UInt64Field(pub Field<u64>);
 
-impl SInt64Field {
-    fn new(name: String, number: u64, data: i64) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::SInt64, number, data),
-        }
+impl UInt64Field {
+    pub fn new(name: String, number: u64, data: u64) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::UInt64,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for SInt64Field {
+impl Default for UInt64Field {
     fn default() -> Self {
-        SInt64Field {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::SInt64,
-                number: 0,
-                data: 0,
-            },
-        }
+        UInt64Field(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::UInt64,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for SInt64Field {
+impl FieldTrait for UInt64Field {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        self.0.repr(&format!("{:#x}", self.0.data))
+        self.repr_with(&RenderOptions::default())
+    }
+
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        self.0.repr_radix(options, &varint64_comment(self.0.data))
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        self.0.example_radix(options)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options)) + &varint64_comment(self.0.data)
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
-        serialize_varint_into(encode_zigzag_s64(self.0.data), into);
+        write_varint(self.0.data, into);
     }
 
     fn serialize(&self) -> Vec<u8> {
@@ -847,9 +1586,18 @@ impl FieldTrait for SInt64Field {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + varint_len(self.0.data)
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Varint`
         if type_int != VariantTypeRaw::Varint as u8 {
             return Err(Error::new(
@@ -864,73 +1612,144 @@ impl FieldTrait for SInt64Field {
 
         if readed as usize >= into.len() {
             return Err(Error::new(
-                &format!("insufficient amount of data to continue parsing"),
+                "insufficient amount of data to continue parsing",
                 Some(ErrorType::IncorrectData),
             ));
         }
 
-        let (value, readed_x) = deserialize_varint(&into[readed as usize..])?;
+        let (value, readed_x) =
+            read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let non_canonical = is_overlong_varint(value, readed_x);
 
-        self.0.data = decode_zigzag_s64(value);
+        self.0.data = value;
+        self.0.non_canonical = non_canonical;
         self.0.number = index;
-        self.0.type_ = FieldType::Int32;
+        self.0.type_ = FieldType::UInt64;
 
         Ok(readed + readed_x)
     }
 }
 
-/// Filed with type Bool
+/// Filed with type SInt32
 #[derive(Debug, Clone, PartialEq)]
-pub struct BoolField(pub Field<bool>);
+pub struct SInt32Field(pub Field<i32>);
 
-impl BoolField {
-    fn new(name: String, number: u64, data: bool) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::Bool, number, data),
-        }
+impl SInt32Field {
+    pub fn new(name: String, number: u64, data: i32) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::SInt32,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for BoolField {
+impl Default for SInt32Field {
     fn default() -> Self {
-        BoolField {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::Bool,
-                number: 0,
-                data: false,
-            },
-        }
+        SInt32Field(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::SInt32,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for BoolField {
+impl FieldTrait for SInt32Field {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        self.0.repr(&format!("{:}", self.0.data))
+        self.repr_with(&RenderOptions::default())
     }
 
-    fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
-            into,
-        );
-        serialize_varint_into(self.0.data as u64, into);
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        self.0.repr_radix(
+            options,
+            &varint32_comment(zigzag_encode32(self.0.data) as u32),
+        )
     }
 
-    fn serialize(&self) -> Vec<u8> {
-        let mut gen = Vec::new();
-        self.serialize_into(&mut gen);
-        gen
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        self.0.example_radix(options)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options))
+            + &varint32_comment(zigzag_encode32(self.0.data) as u32)
+    }
+
+    fn serialize_into(&self, into: &mut Vec<u8>) {
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
+            into,
+        );
+        write_varint(zigzag_encode32(self.0.data), into);
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut gen = Vec::new();
+        self.serialize_into(&mut gen);
+        gen
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + varint_len(zigzag_encode32(self.0.data))
     }
 
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Varint`
         if type_int != VariantTypeRaw::Varint as u8 {
             return Err(Error::new(
@@ -945,67 +1764,128 @@ impl FieldTrait for BoolField {
 
         if readed as usize >= into.len() {
             return Err(Error::new(
-                &format!("insufficient amount of data to continue parsing"),
+                "insufficient amount of data to continue parsing",
                 Some(ErrorType::IncorrectData),
             ));
         }
 
-        let (value, readed_x) = deserialize_varint(&into[readed as usize..])?;
-        if (value >> 0x1) != 0 {
+        let (value, readed_x) =
+            read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let non_canonical = is_overlong_varint(value, readed_x);
+        if (value >> 0x32) != 0 {
             return Err(Error::new(
-                "expected `Boolean` found `U/Int32/64`",
+                "expected `SUInt32` found `U/Int64`",
                 Some(ErrorType::IncorrectData),
             ));
         }
-        self.0.data = value != 0;
+
+        self.0.data = zigzag_decode32(value);
+        self.0.non_canonical = non_canonical;
         self.0.number = index;
-        self.0.type_ = FieldType::Bool;
+        self.0.type_ = FieldType::SInt32;
 
         Ok(readed + readed_x)
     }
 }
 
-/// Filed with type Fixed32
+/// Filed with type UInt64
 #[derive(Debug, Clone, PartialEq)]
-pub struct Fixed32Field(pub Field<i32>);
+pub struct SInt64Field(pub Field<i64>);
 
-impl Fixed32Field {
-    fn new(name: String, number: u64, data: i32) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::Fixed32, number, data),
-        }
+impl SInt64Field {
+    pub fn new(name: String, number: u64, data: i64) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::SInt64,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for Fixed32Field {
+impl Default for SInt64Field {
     fn default() -> Self {
-        Fixed32Field {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::Fixed32,
-                number: 0,
-                data: 0,
-            },
-        }
+        SInt64Field(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::SInt64,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for Fixed32Field {
+impl FieldTrait for SInt64Field {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        self.0.repr(&format!("{:#x}", self.0.data))
+        self.repr_with(&RenderOptions::default())
+    }
+
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        self.0
+            .repr_radix(options, &varint64_comment(zigzag_encode64(self.0.data)))
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        self.0.example_radix(options)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options))
+            + &varint64_comment(zigzag_encode64(self.0.data))
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
-        into.extend_from_slice(&self.0.data.to_le_bytes());
+        write_varint(zigzag_encode64(self.0.data), into);
     }
 
     fn serialize(&self) -> Vec<u8> {
@@ -1014,81 +1894,137 @@ impl FieldTrait for Fixed32Field {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + varint_len(zigzag_encode64(self.0.data))
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Varint`
-        if type_int != VariantTypeRaw::Float as u8 {
+        if type_int != VariantTypeRaw::Varint as u8 {
             return Err(Error::new(
                 &format!(
                     "expected `{}` found `{}`",
-                    VariantTypeRaw::Float,
+                    VariantTypeRaw::Varint,
                     VariantTypeRaw::from(type_int)
                 ),
                 Some(ErrorType::IncorrectType),
             ));
         }
 
-        if (readed + 4) as usize > into.len() {
+        if readed as usize >= into.len() {
             return Err(Error::new(
-                &format!("expected {} bytes, found `{}`", (readed + 4), into.len()),
+                "insufficient amount of data to continue parsing",
                 Some(ErrorType::IncorrectData),
             ));
         }
 
-        let ptr = &into[readed as usize..(readed + 4) as usize];
-        let value = i32::from_le_bytes([ptr[0], ptr[1], ptr[2], ptr[3]]);
-        let readed_x = 0x04;
+        let (value, readed_x) =
+            read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let non_canonical = is_overlong_varint(value, readed_x);
 
-        self.0.data = value;
+        self.0.data = zigzag_decode64(value);
+        self.0.non_canonical = non_canonical;
         self.0.number = index;
-        self.0.type_ = FieldType::Fixed32;
+        self.0.type_ = FieldType::SInt64;
 
         Ok(readed + readed_x)
     }
 }
 
-/// Filed with type SFixed32
+/// Filed with type Bool
+///
+/// Like other protobuf implementations, any varint is accepted and every
+/// nonzero value decodes as `true`.
 #[derive(Debug, Clone, PartialEq)]
-pub struct SFixed32Field(pub Field<u32>);
+pub struct BoolField(pub Field<bool>);
 
-impl SFixed32Field {
-    fn new(name: String, number: u64, data: u32) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::Fixed32, number, data),
-        }
+impl BoolField {
+    pub fn new(name: String, number: u64, data: bool) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::Bool,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for SFixed32Field {
+impl Default for BoolField {
     fn default() -> Self {
-        SFixed32Field {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::SFixed32,
-                number: 0,
-                data: 0,
-            },
-        }
+        BoolField(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::Bool,
+            number: 0,
+            data: false,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for SFixed32Field {
+impl FieldTrait for BoolField {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        self.0.repr(&format!("{:#x}", self.0.data))
+        self.0.repr(&format!("{:}", self.0.data))
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.0.to_str(name, &self.example())
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
-        into.extend_from_slice(&self.0.data.to_le_bytes());
+        write_varint(self.0.data as u64, into);
     }
 
     fn serialize(&self) -> Vec<u8> {
@@ -1097,77 +2033,143 @@ impl FieldTrait for SFixed32Field {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + varint_len(self.0.data as u64)
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Varint`
-        if type_int != VariantTypeRaw::Float as u8 {
+        if type_int != VariantTypeRaw::Varint as u8 {
             return Err(Error::new(
                 &format!(
                     "expected `{}` found `{}`",
-                    VariantTypeRaw::Float,
+                    VariantTypeRaw::Varint,
                     VariantTypeRaw::from(type_int)
                 ),
                 Some(ErrorType::IncorrectType),
             ));
         }
 
-        if (readed + 4) as usize > into.len() {
+        if readed as usize >= into.len() {
             return Err(Error::new(
-                &format!("expected {} bytes, found `{}`", (readed + 4), into.len()),
+                "insufficient amount of data to continue parsing",
                 Some(ErrorType::IncorrectData),
             ));
         }
-        let ptr = &into[readed as usize..(readed + 4) as usize];
-        let value = u32::from_le_bytes([ptr[0], ptr[1], ptr[2], ptr[3]]);
-        let readed_x = 0x04;
 
-        self.0.data = value;
+        // Any varint is a valid bool on the wire, nonzero values are `true`
+        let (value, readed_x) =
+            read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let non_canonical = is_overlong_varint(value, readed_x);
+        self.0.data = value != 0;
+        self.0.non_canonical = non_canonical;
         self.0.number = index;
-        self.0.type_ = FieldType::SFixed32;
+        self.0.type_ = FieldType::Bool;
 
         Ok(readed + readed_x)
     }
 }
 
-/// Filed with type Float
+/// Filed with type Fixed32
 #[derive(Debug, Clone, PartialEq)]
-pub struct FloatField(pub Field<f32>);
+pub struct Fixed32Field(pub Field<i32>);
 
-impl FloatField {
-    fn new(name: String, number: u64, data: f32) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::Float, number, data),
-        }
+impl Fixed32Field {
+    pub fn new(name: String, number: u64, data: i32) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::Fixed32,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for FloatField {
+impl Default for Fixed32Field {
     fn default() -> Self {
-        FloatField {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::Float,
-                number: 0,
-                data: 0.0,
-            },
-        }
+        Fixed32Field(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::Fixed32,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for FloatField {
+impl FieldTrait for Fixed32Field {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        self.0.repr(&format!("{:}", self.0.data))
+        self.repr_with(&RenderOptions::default())
+    }
+
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        self.0.repr_radix(options, "")
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        self.0.example_radix(options)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options))
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
         into.extend_from_slice(&self.0.data.to_le_bytes());
@@ -1179,9 +2181,18 @@ impl FieldTrait for FloatField {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + 4
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Varint`
         if type_int != VariantTypeRaw::Float as u8 {
             return Err(Error::new(
@@ -1197,145 +2208,249 @@ impl FieldTrait for FloatField {
         if (readed + 4) as usize > into.len() {
             return Err(Error::new(
                 &format!("expected {} bytes, found `{}`", (readed + 4), into.len()),
-                Some(ErrorType::IncorrectData),
+                Some(ErrorType::UnexpectedEof),
             ));
         }
 
-        let ptr = &into[readed as usize..4];
-        let value = f32::from_le_bytes([ptr[0], ptr[1], ptr[2], ptr[3]]);
+        let ptr = &into[readed as usize..(readed + 4) as usize];
+        let value = i32::from_le_bytes(options.endianness.to_le(ptr));
         let readed_x = 0x04;
 
         self.0.data = value;
         self.0.number = index;
-        self.0.type_ = FieldType::Float;
+        self.0.type_ = FieldType::Fixed32;
 
         Ok(readed + readed_x)
     }
 }
 
-/// Filed with type Fixed64
+/// Filed with type SFixed32
 #[derive(Debug, Clone, PartialEq)]
-pub struct Fixed64Field(pub Field<i64>);
+pub struct SFixed32Field(pub Field<i32>);
 
-impl Fixed64Field {
-    fn new(name: String, number: u64, data: i64) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::Fixed64, number, data),
-        }
+impl SFixed32Field {
+    pub fn new(name: String, number: u64, data: i32) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::SFixed32,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for Fixed64Field {
+impl Default for SFixed32Field {
     fn default() -> Self {
-        Fixed64Field {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::Fixed64,
-                number: 0,
-                data: 0,
-            },
-        }
+        SFixed32Field(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::SFixed32,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for Fixed64Field {
+impl FieldTrait for SFixed32Field {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        self.0.repr(&format!("{:#x}", self.0.data))
+        self.repr_with(&RenderOptions::default())
     }
 
-    fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
-            into,
-        );
-        into.extend_from_slice(&self.0.data.to_le_bytes());
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        self.0.repr_radix(options, "")
     }
 
-    fn serialize(&self) -> Vec<u8> {
-        let mut gen = Vec::new();
-        self.serialize_into(&mut gen);
-        gen
+    fn number(&self) -> u64 {
+        self.0.number
     }
 
-    fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
-        let (index, type_int) = parse_key(key);
-        // Check Type if queal to `VariantTypeRaw::Varint`
-        if type_int != VariantTypeRaw::Double as u8 {
-            return Err(Error::new(
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        self.0.example_radix(options)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options))
+    }
+
+    fn serialize_into(&self, into: &mut Vec<u8>) {
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
+            into,
+        );
+        into.extend_from_slice(&self.0.data.to_le_bytes());
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut gen = Vec::new();
+        self.serialize_into(&mut gen);
+        gen
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + 4
+    }
+
+    fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
+        let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
+        // Check Type if queal to `VariantTypeRaw::Varint`
+        if type_int != VariantTypeRaw::Float as u8 {
+            return Err(Error::new(
                 &format!(
                     "expected `{}` found `{}`",
-                    VariantTypeRaw::Double,
+                    VariantTypeRaw::Float,
                     VariantTypeRaw::from(type_int)
                 ),
                 Some(ErrorType::IncorrectType),
             ));
         }
-        if (readed + 8) as usize > into.len() {
+
+        if (readed + 4) as usize > into.len() {
             return Err(Error::new(
-                &format!("expected {} bytes, found `{}`", (readed + 8), into.len()),
-                Some(ErrorType::IncorrectData),
+                &format!("expected {} bytes, found `{}`", (readed + 4), into.len()),
+                Some(ErrorType::UnexpectedEof),
             ));
         }
-
-        let ptr = &into[readed as usize..(readed + 8) as usize];
-        println!("Len {:?}", ptr.len());
-        let value = i64::from_le_bytes([
-            ptr[0], ptr[1], ptr[2], ptr[3], ptr[4], ptr[5], ptr[6], ptr[7],
-        ]);
-        let readed_x = 0x08;
+        let ptr = &into[readed as usize..(readed + 4) as usize];
+        let value = i32::from_le_bytes(options.endianness.to_le(ptr));
+        let readed_x = 0x04;
 
         self.0.data = value;
         self.0.number = index;
-        self.0.type_ = FieldType::Fixed64;
+        self.0.type_ = FieldType::SFixed32;
 
         Ok(readed + readed_x)
     }
 }
 
-/// Filed with type SFixed64
+/// Filed with type Float
 #[derive(Debug, Clone, PartialEq)]
-pub struct SFixed64Field(pub Field<u64>);
+pub struct FloatField(pub Field<f32>);
 
-impl SFixed64Field {
-    fn new(name: String, number: u64, data: u64) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::Fixed64, number, data),
-        }
+impl FloatField {
+    pub fn new(name: String, number: u64, data: f32) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::Float,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for SFixed64Field {
+impl Default for FloatField {
     fn default() -> Self {
-        SFixed64Field {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::SFixed64,
-                number: 0,
-                data: 0,
-            },
-        }
+        FloatField(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::Float,
+            number: 0,
+            data: 0.0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for SFixed64Field {
+impl FieldTrait for FloatField {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        self.0.repr(&format!("{:#x}", self.0.data))
+        self.0.repr(&format!("{:}", self.0.data))
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.0.to_str(name, &self.example())
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
         into.extend_from_slice(&self.0.data.to_le_bytes());
@@ -1347,80 +2462,143 @@ impl FieldTrait for SFixed64Field {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + 4
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Varint`
-        if type_int != VariantTypeRaw::Double as u8 {
+        if type_int != VariantTypeRaw::Float as u8 {
             return Err(Error::new(
                 &format!(
                     "expected `{}` found `{}`",
-                    VariantTypeRaw::Double,
+                    VariantTypeRaw::Float,
                     VariantTypeRaw::from(type_int)
                 ),
                 Some(ErrorType::IncorrectType),
             ));
         }
 
-        if (readed + 8) as usize > into.len() {
+        if (readed + 4) as usize > into.len() {
             return Err(Error::new(
-                &format!("expected {} bytes, found `{}`", (readed + 8), into.len()),
-                Some(ErrorType::IncorrectData),
+                &format!("expected {} bytes, found `{}`", (readed + 4), into.len()),
+                Some(ErrorType::UnexpectedEof),
             ));
         }
 
-        let ptr = &into[readed as usize..(readed + 8) as usize];
-        let value = u64::from_le_bytes([
-            ptr[0], ptr[1], ptr[2], ptr[3], ptr[4], ptr[5], ptr[6], ptr[7],
-        ]);
-        let readed_x = 0x08;
+        let ptr = &into[readed as usize..(readed + 4) as usize];
+        let value = f32::from_le_bytes(options.endianness.to_le(ptr));
+        let readed_x = 0x04;
 
         self.0.data = value;
         self.0.number = index;
-        self.0.type_ = FieldType::SFixed64;
+        self.0.type_ = FieldType::Float;
 
         Ok(readed + readed_x)
     }
 }
 
-/// Filed with type SFixed64
+/// Filed with type Fixed64
 #[derive(Debug, Clone, PartialEq)]
-pub struct DoubleField(pub Field<f64>);
+pub struct Fixed64Field(pub Field<i64>);
 
-impl DoubleField {
-    fn new(name: String, number: u64, data: f64) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::Double, number, data),
-        }
+impl Fixed64Field {
+    pub fn new(name: String, number: u64, data: i64) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::Fixed64,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for DoubleField {
+impl Default for Fixed64Field {
     fn default() -> Self {
-        DoubleField {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::Double,
-                number: 0,
-                data: 0.0,
-            },
-        }
+        Fixed64Field(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::Fixed64,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for DoubleField {
+impl FieldTrait for Fixed64Field {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        self.0.repr(&format!("{:}", self.0.data))
+        self.repr_with(&RenderOptions::default())
+    }
+
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        self.0.repr_radix(options, "")
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        self.0.example_radix(options)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options))
+            + &fixed64_halves_comment(self.0.data as u64)
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
         into.extend_from_slice(&self.0.data.to_le_bytes());
@@ -1432,9 +2610,18 @@ impl FieldTrait for DoubleField {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + 8
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Varint`
         if type_int != VariantTypeRaw::Double as u8 {
             return Err(Error::new(
@@ -1449,74 +2636,118 @@ impl FieldTrait for DoubleField {
         if (readed + 8) as usize > into.len() {
             return Err(Error::new(
                 &format!("expected {} bytes, found `{}`", (readed + 8), into.len()),
-                Some(ErrorType::IncorrectData),
+                Some(ErrorType::UnexpectedEof),
             ));
         }
 
         let ptr = &into[readed as usize..(readed + 8) as usize];
-        let value = f64::from_le_bytes([
-            ptr[0], ptr[1], ptr[2], ptr[3], ptr[4], ptr[5], ptr[6], ptr[7],
-        ]);
+        let value = i64::from_le_bytes(options.endianness.to_le(ptr));
         let readed_x = 0x08;
 
         self.0.data = value;
         self.0.number = index;
-        self.0.type_ = FieldType::Double;
+        self.0.type_ = FieldType::Fixed64;
 
         Ok(readed + readed_x)
     }
 }
 
-/// Filed with type String
+/// Filed with type SFixed64
 #[derive(Debug, Clone, PartialEq)]
-pub struct StringField(pub Field<String>);
+pub struct SFixed64Field(pub Field<i64>);
 
-impl StringField {
-    fn new(name: String, number: u64, data: String) -> Self {
-        Self {
-            0: Field::new(name, FieldLabel::Optional, FieldType::String, number, data),
-        }
+impl SFixed64Field {
+    pub fn new(name: String, number: u64, data: i64) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::SFixed64,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for StringField {
+impl Default for SFixed64Field {
     fn default() -> Self {
-        StringField {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::String,
-                number: 0,
-                data: "".to_string(),
-            },
-        }
+        SFixed64Field(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::SFixed64,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for StringField {
+impl FieldTrait for SFixed64Field {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        let data_repr = self
-            .0
-            .data
-            .as_bytes()
-            .iter()
-            .fold(String::new(), |data_repr, x| {
-                data_repr.add(&format!(" {:02X} ", x))
-            });
-        self.0.repr(&format!("{:} ({:})", &self.0.data, &data_repr))
+        self.repr_with(&RenderOptions::default())
+    }
+
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        self.0.repr_radix(options, "")
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        self.0.example_radix(options)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options))
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
-        serialize_varint_into(self.0.data.len() as u64, into);
-        into.extend_from_slice(&self.0.data.as_bytes());
+        into.extend_from_slice(&self.0.data.to_le_bytes());
     }
 
     fn serialize(&self) -> Vec<u8> {
@@ -1525,113 +2756,133 @@ impl FieldTrait for StringField {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + 8
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
-        // Check Type if queal to `VariantTypeRaw::Buffer`
-        if type_int != VariantTypeRaw::Buffer as u8 {
+        check_field_number(index)?;
+        // Check Type if queal to `VariantTypeRaw::Varint`
+        if type_int != VariantTypeRaw::Double as u8 {
             return Err(Error::new(
                 &format!(
                     "expected `{}` found `{}`",
-                    VariantTypeRaw::Buffer,
+                    VariantTypeRaw::Double,
                     VariantTypeRaw::from(type_int)
                 ),
                 Some(ErrorType::IncorrectType),
             ));
         }
-        if readed as usize >= into.len() {
-            return Err(Error::new(
-                &format!("insufficient amount of data to continue parsing"),
-                Some(ErrorType::IncorrectData),
-            ));
-        }
 
-        let (size, readed_1) = deserialize_varint(&into[readed as usize..])?;
-        if (readed + readed_1 + size) as usize > into.len() {
+        if (readed + 8) as usize > into.len() {
             return Err(Error::new(
-                &format!(
-                    "expected {} bytes, found `{}`",
-                    (readed + readed_1 + size),
-                    into.len()
-                ),
-                Some(ErrorType::IncorrectData),
+                &format!("expected {} bytes, found `{}`", (readed + 8), into.len()),
+                Some(ErrorType::UnexpectedEof),
             ));
         }
 
-        let str_vec =
-            into[(readed + readed_1) as usize..(readed + readed_1 + size) as usize].to_vec();
-
-        if let Some(_) = str_vec.iter().find(|&&x| x < 0x20 || x > 0x7F) {
-            return Err(Error::new(
-                &format!("Failed to create String from bytes(non ASCII)"),
-                Some(ErrorType::IncorrectData),
-            ))?;
-        }
-
-        let value = String::from_utf8(str_vec).or(Err(Error::new(
-            &format!("Failed to create String from bytes"),
-            Some(ErrorType::IncorrectData),
-        )))?;
+        let ptr = &into[readed as usize..(readed + 8) as usize];
+        let value = i64::from_le_bytes(options.endianness.to_le(ptr));
+        let readed_x = 0x08;
 
         self.0.data = value;
         self.0.number = index;
-        self.0.type_ = FieldType::String;
+        self.0.type_ = FieldType::SFixed64;
 
-        Ok(readed + readed_1 + size)
+        Ok(readed + readed_x)
     }
 }
 
-/// Filed with type Bytes
+/// Filed with type SFixed64
 #[derive(Debug, Clone, PartialEq)]
-pub struct BytesField(pub Field<Vec<u8>>);
+pub struct DoubleField(pub Field<f64>);
 
-impl BytesField {
-    fn new(name: String, number: u64, data: &[u8]) -> Self {
-        Self {
-            0: Field::new(
-                name,
-                FieldLabel::Optional,
-                FieldType::Bytes,
-                number,
-                data.to_vec(),
-            ),
-        }
+impl DoubleField {
+    pub fn new(name: String, number: u64, data: f64) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::Double,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for BytesField {
+impl Default for DoubleField {
     fn default() -> Self {
-        BytesField {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::Bytes,
-                number: 0,
-                data: Vec::new(),
-            },
-        }
+        DoubleField(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::Double,
+            number: 0,
+            data: 0.0,
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for BytesField {
+impl FieldTrait for DoubleField {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
     fn repr(&self) -> String {
-        let data_repr = self.0.data.iter().fold(String::new(), |data_repr, x| {
-            data_repr.add(&format!(" {:02X}", x))
-        });
-        self.0.repr(&data_repr)
+        self.0.repr(&format!("{:}", self.0.data))
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.0.to_str(name, &self.example())
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
-        serialize_varint_into(self.0.data.len() as u64, into);
-        into.extend_from_slice(&self.0.data);
+        into.extend_from_slice(&self.0.data.to_le_bytes());
     }
 
     fn serialize(&self) -> Vec<u8> {
@@ -1640,217 +2891,141 @@ impl FieldTrait for BytesField {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + 8
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
-        // Check Type if queal to `VariantTypeRaw::Buffer`
-        if type_int != VariantTypeRaw::Buffer as u8 {
+        check_field_number(index)?;
+        // Check Type if queal to `VariantTypeRaw::Varint`
+        if type_int != VariantTypeRaw::Double as u8 {
             return Err(Error::new(
                 &format!(
                     "expected `{}` found `{}`",
-                    VariantTypeRaw::Buffer,
+                    VariantTypeRaw::Double,
                     VariantTypeRaw::from(type_int)
                 ),
                 Some(ErrorType::IncorrectType),
             ));
         }
-        let (size, readed_1) = deserialize_varint(&into[readed as usize..])?;
-        if (readed + readed_1 + size) as usize > into.len() {
+        if (readed + 8) as usize > into.len() {
             return Err(Error::new(
-                &format!(
-                    "expected {} bytes, found `{}`",
-                    (readed + readed_1 + size),
-                    into.len()
-                ),
-                Some(ErrorType::IncorrectData),
+                &format!("expected {} bytes, found `{}`", (readed + 8), into.len()),
+                Some(ErrorType::UnexpectedEof),
             ));
         }
-        let value =
-            into[(readed + readed_1) as usize..(readed + readed_1 + size) as usize].to_vec();
+
+        let ptr = &into[readed as usize..(readed + 8) as usize];
+        let value = f64::from_le_bytes(options.endianness.to_le(ptr));
+        let readed_x = 0x08;
+
         self.0.data = value;
         self.0.number = index;
-        self.0.type_ = FieldType::Bytes;
+        self.0.type_ = FieldType::Double;
 
-        Ok(readed + readed_1 + size)
+        Ok(readed + readed_x)
     }
 }
 
-/// Filed with type StartGroup
-/// TODO: change type
+/// Filed with type String
 #[derive(Debug, Clone, PartialEq)]
-pub struct StartGroupField(pub Field<i32>);
+pub struct StringField(pub Field<String>);
 
-impl StartGroupField {
-    fn new(name: String, number: u64, data: i32) -> Self {
-        Self {
-            0: Field::new(
-                name,
-                FieldLabel::Optional,
-                FieldType::StartGroup,
-                number,
-                data,
-            ),
-        }
+impl StringField {
+    pub fn new(name: String, number: u64, data: String) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::String,
+            number,
+            data,
+        ))
     }
 }
 
-impl Default for StartGroupField {
+impl Default for StringField {
     fn default() -> Self {
-        StartGroupField {
-            0: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::StartGroup,
-                number: 0,
-                data: 0,
-            },
-        }
+        StringField(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::String,
+            number: 0,
+            data: "".to_string(),
+            non_canonical: false,
+        })
     }
 }
 
-impl FieldTrait for StartGroupField {
+impl FieldTrait for StringField {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
 
-    fn repr(&self) -> String {
-        self.0.repr(&format!("{:#x}", self.0.data))
+    fn as_any_ref(&self) -> &dyn Any {
+        self
     }
 
-    fn serialize_into(&self, into: &mut Vec<u8>) {
-        serialize_varint_into(
-            generate_key(self.0.number, VariantTypeRaw::from(self.0.type_) as u8),
-            into,
-        );
-        //serialize_varint_into(self.0.data as u64, into);
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
     }
 
-    fn serialize(&self) -> Vec<u8> {
-        let mut gen = Vec::new();
-        self.serialize_into(&mut gen);
-        gen
+    fn repr(&self) -> String {
+        let data_repr = self
+            .0
+            .data
+            .as_bytes()
+            .iter()
+            .fold(String::new(), |data_repr, x| {
+                data_repr.add(&format!(" {:02X} ", x))
+            });
+        self.0.repr(&format!("{:} ({:})", &self.0.data, &data_repr))
     }
 
-    fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
-        let (index, type_int) = parse_key(key);
-        // Check Type if queal to `VariantTypeRaw::StartGroup`
-        if type_int != VariantTypeRaw::StartGroup as u8 {
-            return Err(Error::new(
-                &format!(
-                    "expected `{}` found `{}`",
-                    VariantTypeRaw::StartGroup,
-                    VariantTypeRaw::from(type_int)
-                ),
-                Some(ErrorType::IncorrectType),
-            ));
-        }
-        // let (value, readed_x) = deserialize_varint(&into[readed as usize..])?;
-        // if (value >> 0x32) != 0 {
-        //     return Err(Error::new(
-        //         "expected `Int32` found `U/Int64`",
-        //         Some(ErrorType::IncorrectData),
-        //     ));
-        // }
-
-        self.0.data = 0;
-        self.0.number = index;
-        self.0.type_ = FieldType::Int32;
-
-        Ok(readed)
+    fn number(&self) -> u64 {
+        self.0.number
     }
-}
-
-pub struct FieldsVector {
-    pub fields: Vec<Box<dyn FieldTrait>>,
-}
 
-impl Default for FieldsVector {
-    fn default() -> Self {
-        Self { fields: Vec::new() }
+    fn name(&self) -> &str {
+        &self.0.name
     }
-}
-
-/// Filed with type Embedded
-pub struct EmbeddedField {
-    pub field: Field<FieldsVector>,
-    pub raw: Option<Vec<u8>>,
-}
 
-impl EmbeddedField {
-    fn new(name: String, number: u64, data: FieldsVector) -> Self {
-        Self {
-            field: Field::new(name, FieldLabel::Optional, FieldType::Bytes, number, data),
-            raw: None,
-        }
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
     }
-}
 
-impl Default for EmbeddedField {
-    fn default() -> Self {
-        EmbeddedField {
-            field: Field {
-                name: "".to_string(),
-                rule: FieldLabel::Optional,
-                type_: FieldType::Bytes,
-                number: 0,
-                data: FieldsVector::default(),
-            },
-            raw: None,
-        }
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
     }
-}
 
-impl FieldTrait for EmbeddedField {
-    fn as_any(&mut self) -> &mut dyn Any {
-        self
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
     }
 
-    fn repr(&self) -> String {
-        let raw = match &self.raw {
-            None => "".to_string(),
-            Some(data) => format!(
-                "{:}",
-                data.iter().fold(String::new(), |data_repr, x| {
-                    data_repr.add(&format!(" {:02X}", x))
-                })
-            ),
-        };
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
 
-        let fields = match self.field.data.fields.len() > 0 {
-            false => "".to_string(),
-            true => format!(
-                "{:}",
-                self.field
-                    .data
-                    .fields
-                    .iter()
-                    .fold(String::new(), |data_repr, x| {
-                        data_repr.add(&format!("\n\t{}", x.repr()))
-                    })
-            ),
-        };
+    fn example(&self) -> String {
+        format!("{:?}", self.0.data)
+    }
 
-        self.field.repr(&format!("Raw <{}> {}", raw, fields))
+    fn to_str(&self, name: &str) -> String {
+        self.0.to_str(name, &self.example())
     }
 
     fn serialize_into(&self, into: &mut Vec<u8>) {
-        let mut embedded = Vec::new();
-        self.field
-            .data
-            .fields
-            .iter()
-            .for_each(|x| x.serialize_into(&mut embedded));
-
-        serialize_varint_into(
-            generate_key(
-                self.field.number,
-                VariantTypeRaw::from(self.field.type_) as u8,
-            ),
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
             into,
         );
-        serialize_varint_into(embedded.len() as u64, into);
-        into.extend(&embedded);
+        write_varint(self.0.data.len() as u64, into);
+        into.extend_from_slice(self.0.data.as_bytes());
     }
 
     fn serialize(&self) -> Vec<u8> {
@@ -1859,11 +3034,20 @@ impl FieldTrait for EmbeddedField {
         gen
     }
 
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + varint_len(self.0.data.len() as u64) + self.0.data.len()
+    }
+
     fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
-        let (key, readed) = deserialize_varint(into)?;
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
         let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
         // Check Type if queal to `VariantTypeRaw::Buffer`
-        if type_int != VariantTypeRaw::Buffer as u8 {
+        if !VariantTypeRaw::from(type_int).is_length_delimited() {
             return Err(Error::new(
                 &format!(
                     "expected `{}` found `{}`",
@@ -1875,53 +3059,1341 @@ impl FieldTrait for EmbeddedField {
         }
         if readed as usize >= into.len() {
             return Err(Error::new(
-                &format!("insufficient amount of data to continue parsing"),
+                "insufficient amount of data to continue parsing",
                 Some(ErrorType::IncorrectData),
             ));
         }
-        let (size, readed_1) = deserialize_varint(&into[readed as usize..])?;
-        if (readed + readed_1 + size) as usize > into.len() {
+
+        let (size, readed_1) = read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let remaining = into.len() as u64 - (readed + readed_1);
+        if size > remaining {
+            return Err(Error::truncated(size, remaining));
+        }
+
+        let str_vec =
+            into[(readed + readed_1) as usize..(readed + readed_1 + size) as usize].to_vec();
+
+        if options.string_mode == StringMode::Ascii
+            && str_vec.iter().any(|x| !(0x20..=0x7F).contains(x))
+        {
             return Err(Error::new(
-                &format!(
-                    "expected {} bytes, found `{}`",
-                    (readed + readed_1 + size),
-                    into.len()
-                ),
+                "Failed to create String from bytes(non ASCII)",
                 Some(ErrorType::IncorrectData),
-            ));
+            ))?;
         }
-        self.raw =
-            Some(into[(readed + readed_1) as usize..(readed + readed_1 + size) as usize].to_vec());
-        self.field.data = FieldsVector::default();
-        self.field.number = index;
-        self.field.type_ = FieldType::Embedded;
+
+        let value = String::from_utf8(str_vec).map_err(|e| {
+            Error::with_source(
+                "Failed to create String from bytes",
+                Some(ErrorType::IncorrectData),
+                e,
+            )
+        })?;
+
+        self.0.data = value;
+        self.0.number = index;
+        self.0.type_ = FieldType::String;
 
         Ok(readed + readed_1 + size)
     }
 }
 
-#[cfg(test)]
-mod test {
-    use crate::proto::field::*;
+/// Filed with type Bytes
+#[derive(Debug, Clone, PartialEq)]
+pub struct BytesField(pub Field<Vec<u8>>);
 
-    #[test]
-    fn serialization() {
-        fn check<T: FieldTrait>(field: T, proto: &[u8]) {
-            let proto_vec: Vec<u8> = field.serialize();
-            assert_eq!(proto, &proto_vec);
-        }
-        // Check Int32
-        check(
-            Int32Field::new("".to_string(), 1, -0xFFFFFF),
-            &[
-                0x8, 0x81, 0x80, 0x80, 0xf8, 0xff, 0xff, 0xff, 0xff, 0xff, 0x1,
-            ],
-        );
-        // Check Int64
-        check(
-            Int64Field::new("".to_string(), 1, -0xFFFFFFFFFFFFFF),
-            &[
-                0x8, 0x81, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0xff, 0x1,
+impl BytesField {
+    pub fn new(name: String, number: u64, data: &[u8]) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::Bytes,
+            number,
+            data.to_vec(),
+        ))
+    }
+
+    /// Bytes which could not be classified as a field
+    ///
+    /// Numbered after the key the bytes start with, 0 if no key fits.
+    pub fn unknown(data: &[u8]) -> Self {
+        let number = read_varint(data)
+            .map(|(key, _)| parse_key(key).0)
+            .ok()
+            .filter(|&x| x <= MAX_FIELD_NUMBER)
+            .unwrap_or(0);
+        BytesField::new("".to_string(), number, data)
+    }
+}
+
+impl Default for BytesField {
+    fn default() -> Self {
+        BytesField(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::Bytes,
+            number: 0,
+            data: Vec::new(),
+            non_canonical: false,
+        })
+    }
+}
+
+impl FieldTrait for BytesField {
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
+    fn repr(&self) -> String {
+        let data_repr = self.0.data.iter().fold(String::new(), |data_repr, x| {
+            data_repr.add(&format!(" {:02X}", x))
+        });
+        self.0.repr(&data_repr) + &as_string_comment(&self.0.data)
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        hex_example(&self.0.data, self.0.data.len())
+    }
+
+    fn example_with(&self, options: &RenderOptions) -> String {
+        hex_example(&self.0.data, options.max_example_bytes)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        self.0.to_str(name, &self.example_with(options)) + &as_string_comment(&self.0.data)
+    }
+
+    fn serialize_into(&self, into: &mut Vec<u8>) {
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
+            into,
+        );
+        write_varint(self.0.data.len() as u64, into);
+        into.extend_from_slice(&self.0.data);
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut gen = Vec::new();
+        self.serialize_into(&mut gen);
+        gen
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.key_len() + varint_len(self.0.data.len() as u64) + self.0.data.len()
+    }
+
+    fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
+        let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
+        // Check Type if queal to `VariantTypeRaw::Buffer`
+        if !VariantTypeRaw::from(type_int).is_length_delimited() {
+            return Err(Error::new(
+                &format!(
+                    "expected `{}` found `{}`",
+                    VariantTypeRaw::Buffer,
+                    VariantTypeRaw::from(type_int)
+                ),
+                Some(ErrorType::IncorrectType),
+            ));
+        }
+
+        let (size, readed_1) = read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let remaining = into.len() as u64 - (readed + readed_1);
+        if size > remaining {
+            return Err(Error::truncated(size, remaining));
+        }
+        let value =
+            into[(readed + readed_1) as usize..(readed + readed_1 + size) as usize].to_vec();
+        self.0.data = value;
+        self.0.number = index;
+        self.0.type_ = FieldType::Bytes;
+
+        Ok(readed + readed_1 + size)
+    }
+}
+
+/// Filed with type StartGroup
+/// TODO: change type
+#[derive(Debug, Clone, PartialEq)]
+pub struct StartGroupField(pub Field<i32>);
+
+impl StartGroupField {
+    pub fn new(name: String, number: u64, data: i32) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::StartGroup,
+            number,
+            data,
+        ))
+    }
+}
+
+impl Default for StartGroupField {
+    fn default() -> Self {
+        StartGroupField(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::StartGroup,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
+    }
+}
+
+impl FieldTrait for StartGroupField {
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
+    fn repr(&self) -> String {
+        self.0.repr(&format!("{:#x}", self.0.data))
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.0.to_str(name, &self.example())
+    }
+
+    fn serialize_into(&self, into: &mut Vec<u8>) {
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
+            into,
+        );
+        //write_varint(self.0.data as u64, into);
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut gen = Vec::new();
+        self.serialize_into(&mut gen);
+        gen
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.key_len()
+    }
+
+    fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
+        let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
+        // Check Type if queal to `VariantTypeRaw::StartGroup`
+        if type_int != VariantTypeRaw::StartGroup as u8 {
+            return Err(Error::new(
+                &format!(
+                    "expected `{}` found `{}`",
+                    VariantTypeRaw::StartGroup,
+                    VariantTypeRaw::from(type_int)
+                ),
+                Some(ErrorType::IncorrectType),
+            ));
+        }
+        // let (value, readed_x) = read_varint(&into[readed as usize..])?;
+        // if (value >> 0x32) != 0 {
+        //     return Err(Error::new(
+        //         "expected `Int32` found `U/Int64`",
+        //         Some(ErrorType::IncorrectData),
+        //     ));
+        // }
+
+        self.0.data = 0;
+        self.0.number = index;
+        self.0.type_ = FieldType::StartGroup;
+
+        Ok(readed)
+    }
+}
+
+/// Filed with type EndGroup, closes the group with the same number
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndGroupField(pub Field<i32>);
+
+impl EndGroupField {
+    pub fn new(name: String, number: u64, data: i32) -> Self {
+        Self(Field::new(
+            name,
+            FieldLabel::Optional,
+            FieldType::EndGroup,
+            number,
+            data,
+        ))
+    }
+}
+
+impl Default for EndGroupField {
+    fn default() -> Self {
+        EndGroupField(Field {
+            name: "".to_string(),
+            rule: FieldLabel::Optional,
+            type_: FieldType::EndGroup,
+            number: 0,
+            data: 0,
+            non_canonical: false,
+        })
+    }
+}
+
+impl FieldTrait for EndGroupField {
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
+    fn repr(&self) -> String {
+        self.0.repr(&format!("{:#x}", self.0.data))
+    }
+
+    fn number(&self) -> u64 {
+        self.0.number
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.0.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.0.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.0.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.0.type_
+    }
+
+    fn example(&self) -> String {
+        format!("{}", self.0.data)
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.0.to_str(name, &self.example())
+    }
+
+    fn serialize_into(&self, into: &mut Vec<u8>) {
+        write_varint(
+            generate_key(self.0.number, self.0.type_.wire_type() as u8),
+            into,
+        );
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut gen = Vec::new();
+        self.serialize_into(&mut gen);
+        gen
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.key_len()
+    }
+
+    fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
+        let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
+        // Check Type if queal to `VariantTypeRaw::EndGroup`
+        if type_int != VariantTypeRaw::EndGroup as u8 {
+            return Err(Error::new(
+                &format!(
+                    "expected `{}` found `{}`",
+                    VariantTypeRaw::EndGroup,
+                    VariantTypeRaw::from(type_int)
+                ),
+                Some(ErrorType::IncorrectType),
+            ));
+        }
+        self.0.data = 0;
+        self.0.number = index;
+        self.0.type_ = FieldType::EndGroup;
+
+        Ok(readed)
+    }
+}
+
+impl Clone for Box<dyn FieldTrait> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl PartialEq for Box<dyn FieldTrait> {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_dyn(other.as_ref())
+    }
+}
+
+#[derive(Default, Clone, PartialEq)]
+pub struct FieldsVector {
+    pub fields: Vec<Box<dyn FieldTrait>>,
+}
+
+/// Values of a payload holding two or more minimally encoded varints
+fn packed_varints(data: &[u8]) -> Option<Vec<u64>> {
+    let mut values = Vec::new();
+    let mut index = 0;
+    while index < data.len() {
+        let (value, readed) = read_varint(&data[index..]).ok()?;
+        if varint_len(value) != readed as usize {
+            return None;
+        }
+        values.push(value);
+        index += readed as usize;
+    }
+    match values.len() >= 2 {
+        true => Some(values),
+        false => None,
+    }
+}
+
+/// Declaration of a bytes field whose payload looks like a packed repeated
+/// varint field
+fn packed_to_str(field: &dyn FieldTrait, name: &str) -> Option<String> {
+    let bytes = field.as_any_ref().downcast_ref::<BytesField>()?;
+    if printable_str(&bytes.0.data).is_some() {
+        return None;
+    }
+    let values = packed_varints(&bytes.0.data)?;
+    let type_ = match values.iter().all(|&x| x <= i32::MAX as u64) {
+        true => FieldType::Int32,
+        false => FieldType::Int64,
+    };
+    let examples: Vec<String> = values.iter().map(|&x| (x as i64).to_string()).collect();
+    Some(format!(
+        "repeated {} {} = {}; // Example: [{}] // packed",
+        type_.proto_type(),
+        name,
+        field.number(),
+        examples.join(", ")
+    ))
+}
+
+/// Render fields as `.proto` declarations
+///
+/// Scalar fields sharing a tag number and type are rendered as a single
+/// `repeated` declaration listing every observed value.
+pub fn fields_to_str(fields: &[Box<dyn FieldTrait>]) -> Vec<String> {
+    fields_to_str_with(fields, &RenderOptions::default())
+}
+
+/// Render fields as `.proto` declarations using `RenderOptions`
+pub fn fields_to_str_with(fields: &[Box<dyn FieldTrait>], options: &RenderOptions) -> Vec<String> {
+    field_declarations(fields, options)
+        .into_iter()
+        .map(|x| x.line)
+        .collect()
+}
+
+/// `stored` name of a field, `name` if none was set
+///
+/// Names set on the field, e.g. by a schema, override generated ones.
+fn stored_name_or<'a>(stored: &'a str, name: &'a str) -> &'a str {
+    match stored.is_empty() {
+        true => name,
+        false => stored,
+    }
+}
+
+/// `name` or, if a field or message type of that name is already declared,
+/// `name_2`, `name_3`, ...
+///
+/// Embedded fields declare a message type named after the field, see
+/// `message_type_name`.
+fn unique_name(
+    name: &str,
+    type_: FieldType,
+    names: &mut HashSet<String>,
+    types: &mut HashSet<String>,
+) -> String {
+    let embedded = type_ == FieldType::Embedded;
+    let taken = |x: &str| names.contains(x) || (embedded && types.contains(&message_type_name(x)));
+    let name = match taken(name) {
+        false => name.to_string(),
+        true => (2..)
+            .map(|i| format!("{}_{}", name, i))
+            .find(|x| !taken(x))
+            .unwrap_or_default(),
+    };
+    if embedded {
+        types.insert(message_type_name(&name));
+    }
+    names.insert(name.clone());
+    name
+}
+
+/// Index of the EndGroup closing the group started at `start`, `fields.len()`
+/// when it is never closed
+fn group_end(fields: &[Box<dyn FieldTrait>], start: usize) -> usize {
+    let mut depth = 0;
+    for (i, field) in fields.iter().enumerate().skip(start) {
+        match field.field_type() {
+            FieldType::StartGroup => depth += 1,
+            FieldType::EndGroup if depth == 1 => return i,
+            FieldType::EndGroup => depth -= 1,
+            _ => {}
+        }
+    }
+    fields.len()
+}
+
+/// Declaration of embedded fields sharing a tag number as one `repeated` field
+/// of a single message type
+///
+/// The message type declares the fields of every entry, each as many times as
+/// the entry holding it most often.
+fn repeated_embedded_to_str(
+    fields: &[Box<dyn FieldTrait>],
+    group: &[usize],
+    name: &str,
+    options: &RenderOptions,
+) -> Option<String> {
+    let entries = group
+        .iter()
+        .map(|&j| fields[j].as_any_ref().downcast_ref::<EmbeddedField>())
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut merged: Vec<Box<dyn FieldTrait>> = Vec::new();
+    for entry in entries.iter() {
+        let entry_fields = &entry.field.data.fields;
+        for (k, x) in entry_fields.iter().enumerate() {
+            let same =
+                |y: &dyn FieldTrait| y.number() == x.number() && y.field_type() == x.field_type();
+            let occurrence = entry_fields[..=k]
+                .iter()
+                .filter(|y| same(y.as_ref()))
+                .count();
+            if merged.iter().filter(|y| same(y.as_ref())).count() < occurrence {
+                merged.push(x.clone_box());
+            }
+        }
+    }
+
+    let mut repeated = (*entries.first()?).clone();
+    repeated.field.data.fields = merged;
+    repeated.field.rule = FieldLabel::Repeated;
+    repeated.field.name = name.to_string();
+    repeated.raw = None;
+    Some(repeated.to_str_with(name, options))
+}
+
+/// `.proto` declaration of a field along with its position in parse order
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDeclaration {
+    /// Index in the rendered fields of the first field declared, i.e. order of
+    /// the field in the decoded bytes
+    pub original_index: usize,
+    pub number: u64,
+    pub line: String,
+}
+
+/// Render fields as `.proto` declarations keeping their parse order indices
+///
+/// Declarations follow parse order unless `RenderOptions::sorted` is set,
+/// names are given in parse order either way. Fields of a group are declared in
+/// its body.
+pub fn field_declarations(
+    fields: &[Box<dyn FieldTrait>],
+    options: &RenderOptions,
+) -> Vec<FieldDeclaration> {
+    let mut declarations = Vec::new();
+    let mut rendered = vec![false; fields.len()];
+    let mut names = HashSet::new();
+    let mut types = HashSet::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        // The end of a group is declared by its start
+        if rendered[i] || field.field_type() == FieldType::EndGroup {
+            continue;
+        }
+        let generated = (options.naming)(field.field_type(), declarations.len());
+        let preferred = stored_name_or(field.name(), &generated);
+        let name = unique_name(preferred, field.field_type(), &mut names, &mut types);
+
+        if field.field_type() == FieldType::StartGroup {
+            let end = group_end(fields, i);
+            rendered[i..end].iter_mut().for_each(|x| *x = true);
+            let body = field_declarations(&fields[i + 1..end], options);
+            let lines: Vec<String> = body.into_iter().map(|x| x.line).collect();
+            declarations.push(FieldDeclaration {
+                original_index: i,
+                number: field.number(),
+                line: format!(
+                    "{} group {} = {} {{{}\n}}",
+                    field.rule().to_string().to_lowercase(),
+                    message_type_name(&name),
+                    field.number(),
+                    options.indent_lines(&lines)
+                ),
+            });
+            continue;
+        }
+
+        let group: Vec<usize> = (i..fields.len())
+            .filter(|&j| {
+                fields[j].number() == field.number() && fields[j].field_type() == field.field_type()
+            })
+            .collect();
+        group.iter().for_each(|&j| rendered[j] = true);
+
+        if group.len() == 1 {
+            let packed = match options.detect_packed {
+                true => packed_to_str(field.as_ref(), &name),
+                false => None,
+            };
+            // A stored name taken by an earlier field is replaced
+            let line = packed.unwrap_or_else(|| match name == preferred {
+                true => field.to_str_with(&name, options),
+                false => {
+                    let mut renamed = field.clone_box();
+                    renamed.set_name(&name);
+                    renamed.to_str_with(&name, options)
+                }
+            });
+            declarations.push(FieldDeclaration {
+                original_index: i,
+                number: field.number(),
+                line,
+            });
+        } else if let Some(line) = repeated_embedded_to_str(fields, &group, &name, options) {
+            declarations.push(FieldDeclaration {
+                original_index: i,
+                number: field.number(),
+                line,
+            });
+        } else {
+            let examples: Vec<String> = group
+                .iter()
+                .map(|&j| fields[j].example_with(options))
+                .collect();
+            // Only scalars of fixed or varint wire types can be packed
+            let unpacked = match field.field_type().wire_type() {
+                VariantTypeRaw::Varint | VariantTypeRaw::Float | VariantTypeRaw::Double => {
+                    " // unpacked"
+                }
+                _ => "",
+            };
+            let line = format!(
+                "repeated {} {} = {}; // Example: [{}]{}",
+                field.field_type().proto_type(),
+                name,
+                field.number(),
+                examples.join(", "),
+                unpacked
+            );
+            declarations.push(FieldDeclaration {
+                original_index: i,
+                number: field.number(),
+                line,
+            });
+        }
+    }
+
+    if options.sorted {
+        declarations.sort_by_key(|x| x.number);
+    }
+    declarations
+}
+
+/// Filed with type Embedded
+#[derive(Clone, PartialEq)]
+pub struct EmbeddedField {
+    pub field: Field<FieldsVector>,
+    pub raw: Option<Vec<u8>>,
+}
+
+impl EmbeddedField {
+    pub fn new(name: String, number: u64, data: FieldsVector) -> Self {
+        let mut raw = Vec::new();
+        data.fields.iter().for_each(|x| x.serialize_into(&mut raw));
+        Self {
+            field: Field::new(
+                name,
+                FieldLabel::Optional,
+                FieldType::Embedded,
+                number,
+                data,
+            ),
+            raw: Some(raw),
+        }
+    }
+
+    /// Nested fields as a standalone message named after the field
+    ///
+    /// Returns `None` when the payload was not decoded into fields.
+    pub fn as_message(&self) -> Option<Message> {
+        if self.field.data.fields.is_empty() {
+            return None;
+        }
+        Some(Message::new(
+            message_type_name(&self.field.name),
+            Some(self.field.data.fields.clone()),
+        ))
+    }
+}
+
+/// snake_case field name as CamelCase type name, `nested_msg_1` -> `NestedMsg1`
+fn message_type_name(name: &str) -> String {
+    match name.is_empty() {
+        true => "Embedded".to_string(),
+        false => name
+            .split('_')
+            .map(|part| {
+                let mut chars = part.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            })
+            .collect(),
+    }
+}
+
+impl Default for EmbeddedField {
+    fn default() -> Self {
+        EmbeddedField {
+            field: Field {
+                name: "".to_string(),
+                rule: FieldLabel::Optional,
+                type_: FieldType::Bytes,
+                number: 0,
+                data: FieldsVector::default(),
+                non_canonical: false,
+            },
+            raw: None,
+        }
+    }
+}
+
+impl FieldTrait for EmbeddedField {
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
+    fn repr(&self) -> String {
+        self.repr_with(&RenderOptions::default())
+    }
+
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        let raw = match &self.raw {
+            None => "".to_string(),
+            Some(data) => data
+                .iter()
+                .fold(String::new(), |data_repr, x| {
+                    data_repr.add(&format!(" {:02X}", x))
+                })
+                .to_string(),
+        };
+
+        let fields = match !self.field.data.fields.is_empty() {
+            false => "".to_string(),
+            true => self
+                .field
+                .data
+                .fields
+                .iter()
+                .fold(String::new(), |data_repr, x| {
+                    data_repr.add(&format!("\n\t{}", x.repr_with(options)))
+                })
+                .to_string(),
+        };
+
+        self.field.repr(&format!("Raw <{}> {}", raw, fields))
+    }
+
+    fn number(&self) -> u64 {
+        self.field.number
+    }
+
+    fn name(&self) -> &str {
+        &self.field.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.field.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.field.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.field.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.field.type_
+    }
+
+    fn example(&self) -> String {
+        match &self.raw {
+            None => "".to_string(),
+            Some(data) => data
+                .iter()
+                .fold(String::new(), |data_repr, x| {
+                    data_repr.add(&format!(" {:02X}", x))
+                })
+                .trim_start()
+                .to_string(),
+        }
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        self.to_str_with(name, &RenderOptions::default())
+    }
+
+    fn to_str_with(&self, name: &str, options: &RenderOptions) -> String {
+        let name = self.field.name_or(name);
+        let message_name = message_type_name(name);
+        let fields = options.indent_lines(&fields_to_str_with(&self.field.data.fields, options));
+
+        let length = match &self.raw {
+            Some(raw) => format!(" // {} bytes", raw.len()),
+            None => "".to_string(),
+        };
+
+        format!(
+            "message {} {{{}\n}}\n{} {} {} = {};{}",
+            message_name,
+            fields,
+            self.field.rule.to_string().to_lowercase(),
+            message_name,
+            name,
+            self.field.number,
+            length
+        )
+    }
+
+    fn to_protoscope(&self) -> String {
+        if self.field.data.fields.is_empty() {
+            return format!(
+                "{}: {}",
+                self.field.number,
+                protoscope_value(FieldType::Bytes, &self.example())
+            );
+        }
+        let fields = self
+            .field
+            .data
+            .fields
+            .iter()
+            .fold(String::new(), |data_repr, x| {
+                data_repr.add(&format!("\n  {}", x.to_protoscope().replace('\n', "\n  ")))
+            });
+        format!("{}: {{{}\n}}", self.field.number, fields)
+    }
+
+    fn serialize_into(&self, into: &mut Vec<u8>) {
+        let mut embedded = Vec::new();
+        self.field
+            .data
+            .fields
+            .iter()
+            .for_each(|x| x.serialize_into(&mut embedded));
+
+        write_varint(
+            generate_key(self.field.number, self.field.type_.wire_type() as u8),
+            into,
+        );
+        write_varint(embedded.len() as u64, into);
+        into.extend(&embedded);
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut gen = Vec::new();
+        self.serialize_into(&mut gen);
+        gen
+    }
+
+    fn encoded_len(&self) -> usize {
+        let embedded: usize = self.field.data.fields.iter().map(|x| x.encoded_len()).sum();
+        self.field.key_len() + varint_len(embedded as u64) + embedded
+    }
+
+    fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
+        let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
+        // Check Type if queal to `VariantTypeRaw::Buffer`
+        if !VariantTypeRaw::from(type_int).is_length_delimited() {
+            return Err(Error::new(
+                &format!(
+                    "expected `{}` found `{}`",
+                    VariantTypeRaw::Buffer,
+                    VariantTypeRaw::from(type_int)
+                ),
+                Some(ErrorType::IncorrectType),
+            ));
+        }
+        if readed as usize >= into.len() {
+            return Err(Error::new(
+                "insufficient amount of data to continue parsing",
+                Some(ErrorType::IncorrectData),
+            ));
+        }
+        let (size, readed_1) = read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let remaining = into.len() as u64 - (readed + readed_1);
+        if size > remaining {
+            return Err(Error::truncated(size, remaining));
+        }
+        self.raw =
+            Some(into[(readed + readed_1) as usize..(readed + readed_1 + size) as usize].to_vec());
+        self.field.data = FieldsVector::default();
+        self.field.number = index;
+        self.field.type_ = FieldType::Embedded;
+
+        Ok(readed + readed_1 + size)
+    }
+}
+
+/// Key/value pair of a map entry
+pub type MapEntry = (Box<dyn FieldTrait>, Box<dyn FieldTrait>);
+
+/// Filed with type Map
+///
+/// Maps are encoded as repeated embedded entries holding the key as field 1 and
+/// the value as field 2, each entry is one pair of `data`.
+#[derive(Clone, PartialEq)]
+pub struct MapField {
+    pub field: Field<Vec<MapEntry>>,
+    /// Type of keys, used to decode entries
+    pub key_type: FieldType,
+    /// Type of values, used to decode entries
+    pub value_type: FieldType,
+}
+
+impl MapField {
+    pub fn new(
+        number: u64,
+        key_type: FieldType,
+        value_type: FieldType,
+        data: Vec<MapEntry>,
+    ) -> Self {
+        Self {
+            field: Field::new(
+                "".to_string(),
+                FieldLabel::Repeated,
+                FieldType::Map,
+                number,
+                data,
+            ),
+            key_type,
+            value_type,
+        }
+    }
+}
+
+impl Default for MapField {
+    fn default() -> Self {
+        MapField::new(0, FieldType::String, FieldType::Bytes, Vec::new())
+    }
+}
+
+impl FieldTrait for MapField {
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn FieldTrait> {
+        Box::new(self.clone())
+    }
+
+    fn repr(&self) -> String {
+        self.repr_with(&RenderOptions::default())
+    }
+
+    fn repr_with(&self, options: &RenderOptions) -> String {
+        let data_repr = self.field.data.iter().fold(String::new(), |data_repr, x| {
+            data_repr.add(&format!(
+                "\n\t{}\n\t{}",
+                x.0.repr_with(options),
+                x.1.repr_with(options)
+            ))
+        });
+        self.field.repr(&data_repr)
+    }
+
+    fn number(&self) -> u64 {
+        self.field.number
+    }
+
+    fn name(&self) -> &str {
+        &self.field.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.field.name = name.to_string();
+    }
+
+    fn set_rule(&mut self, rule: FieldLabel) {
+        self.field.rule = rule;
+    }
+
+    fn rule(&self) -> FieldLabel {
+        self.field.rule
+    }
+
+    fn field_type(&self) -> FieldType {
+        self.field.type_
+    }
+
+    fn example(&self) -> String {
+        let pairs: Vec<String> = self
+            .field
+            .data
+            .iter()
+            .map(|x| format!("{}: {}", x.0.example(), x.1.example()))
+            .collect();
+        format!("{{{}}}", pairs.join(", "))
+    }
+
+    fn to_str(&self, name: &str) -> String {
+        format!(
+            "map<{}, {}> {} = {}; // Example: {}",
+            self.key_type.proto_type(),
+            self.value_type.proto_type(),
+            self.field.name_or(name),
+            self.field.number,
+            self.example()
+        )
+    }
+
+    fn to_protoscope(&self) -> String {
+        self.field
+            .data
+            .iter()
+            .map(|x| {
+                format!(
+                    "{}: {{{} {}}}",
+                    self.field.number,
+                    x.0.to_protoscope(),
+                    x.1.to_protoscope()
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn serialize_into(&self, into: &mut Vec<u8>) {
+        for (key, value) in self.field.data.iter() {
+            let mut entry = Vec::new();
+            key.serialize_into(&mut entry);
+            value.serialize_into(&mut entry);
+
+            write_varint(
+                generate_key(self.field.number, self.field.type_.wire_type() as u8),
+                into,
+            );
+            write_varint(entry.len() as u64, into);
+            into.extend(&entry);
+        }
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut gen = Vec::new();
+        self.serialize_into(&mut gen);
+        gen
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.field
+            .data
+            .iter()
+            .map(|(key, value)| {
+                let entry = key.encoded_len() + value.encoded_len();
+                self.field.key_len() + varint_len(entry as u64) + entry
+            })
+            .sum()
+    }
+
+    /// Deserialize a single entry using `key_type` and `value_type`, the entry
+    /// is appended to already decoded ones
+    fn deserialize(&mut self, into: &[u8]) -> Result<u64> {
+        self.deserialize_with(into, &DecodeOptions::default())
+    }
+
+    fn deserialize_with(&mut self, into: &[u8], options: &DecodeOptions) -> Result<u64> {
+        let (key, readed) = read_varint_max(into, options.max_varint_bytes)?;
+        let (index, type_int) = parse_key(key);
+        check_field_number(index)?;
+        // Check Type if queal to `VariantTypeRaw::Buffer`
+        if !VariantTypeRaw::from(type_int).is_length_delimited() {
+            return Err(Error::new(
+                &format!(
+                    "expected `{}` found `{}`",
+                    VariantTypeRaw::Buffer,
+                    VariantTypeRaw::from(type_int)
+                ),
+                Some(ErrorType::IncorrectType),
+            ));
+        }
+        let (size, readed_1) = read_varint_max(&into[readed as usize..], options.max_varint_bytes)?;
+        let remaining = into.len() as u64 - (readed + readed_1);
+        if size > remaining {
+            return Err(Error::truncated(size, remaining));
+        }
+        let entry = &into[(readed + readed_1) as usize..(readed + readed_1 + size) as usize];
+
+        let mut map_key: Box<dyn FieldTrait> = self.key_type.into();
+        let key_len = map_key.deserialize_with(entry, options)?;
+        let mut map_value: Box<dyn FieldTrait> = self.value_type.into();
+        let value_len = map_value.deserialize_with(&entry[key_len as usize..], options)?;
+        if map_key.number() != 1 || map_value.number() != 2 || key_len + value_len != size {
+            return Err(Error::new(
+                "map entry must hold only key (field 1) and value (field 2)",
+                Some(ErrorType::IncorrectData),
+            ));
+        }
+
+        self.field.data.push((map_key, map_value));
+        self.field.number = index;
+        self.field.type_ = FieldType::Map;
+
+        Ok(readed + readed_1 + size)
+    }
+}
+
+/// Typed downcasting of boxed fields, built on `FieldTrait::as_any`
+pub trait FieldExt {
+    /// Concrete field if the box holds a `T`
+    fn downcast<T: FieldTrait + 'static>(&mut self) -> Option<&mut T>;
+
+    fn as_int32(&mut self) -> Option<&mut Int32Field> {
+        self.downcast()
+    }
+
+    fn as_int64(&mut self) -> Option<&mut Int64Field> {
+        self.downcast()
+    }
+
+    fn as_uint32(&mut self) -> Option<&mut UInt32Field> {
+        self.downcast()
+    }
+
+    fn as_uint64(&mut self) -> Option<&mut UInt64Field> {
+        self.downcast()
+    }
+
+    fn as_sint32(&mut self) -> Option<&mut SInt32Field> {
+        self.downcast()
+    }
+
+    fn as_sint64(&mut self) -> Option<&mut SInt64Field> {
+        self.downcast()
+    }
+
+    fn as_bool(&mut self) -> Option<&mut BoolField> {
+        self.downcast()
+    }
+
+    fn as_fixed32(&mut self) -> Option<&mut Fixed32Field> {
+        self.downcast()
+    }
+
+    fn as_sfixed32(&mut self) -> Option<&mut SFixed32Field> {
+        self.downcast()
+    }
+
+    fn as_float(&mut self) -> Option<&mut FloatField> {
+        self.downcast()
+    }
+
+    fn as_fixed64(&mut self) -> Option<&mut Fixed64Field> {
+        self.downcast()
+    }
+
+    fn as_sfixed64(&mut self) -> Option<&mut SFixed64Field> {
+        self.downcast()
+    }
+
+    fn as_double(&mut self) -> Option<&mut DoubleField> {
+        self.downcast()
+    }
+
+    fn as_string(&mut self) -> Option<&mut StringField> {
+        self.downcast()
+    }
+
+    fn as_bytes(&mut self) -> Option<&mut BytesField> {
+        self.downcast()
+    }
+
+    fn as_start_group(&mut self) -> Option<&mut StartGroupField> {
+        self.downcast()
+    }
+
+    fn as_end_group(&mut self) -> Option<&mut EndGroupField> {
+        self.downcast()
+    }
+
+    fn as_embedded(&mut self) -> Option<&mut EmbeddedField> {
+        self.downcast()
+    }
+
+    fn as_map(&mut self) -> Option<&mut MapField> {
+        self.downcast()
+    }
+}
+
+impl FieldExt for Box<dyn FieldTrait> {
+    fn downcast<T: FieldTrait + 'static>(&mut self) -> Option<&mut T> {
+        self.as_any().downcast_mut::<T>()
+    }
+}
+
+/// Construct a concrete field by deserializing it, e.g.
+/// `let (field, readed) = Int32Field::deserialize_into(bytes)?`
+pub trait DeserializeInto: FieldTrait + Default + Sized {
+    /// Deserialize a field from the start of `into`
+    ///
+    /// Returns (field, bytes readed)
+    fn deserialize_into(into: &[u8]) -> Result<(Self, u64)> {
+        Self::deserialize_into_with(into, &DecodeOptions::default())
+    }
+
+    /// Deserialize a field from the start of `into` using `DecodeOptions`
+    fn deserialize_into_with(into: &[u8], options: &DecodeOptions) -> Result<(Self, u64)> {
+        let mut field = Self::default();
+        let readed = field.deserialize_with(into, options)?;
+        Ok((field, readed))
+    }
+}
+
+impl<T: FieldTrait + Default> DeserializeInto for T {}
+
+#[cfg(test)]
+mod test {
+    use crate::proto::field::*;
+
+    #[test]
+    fn serialization() {
+        fn check<T: FieldTrait>(field: T, proto: &[u8]) {
+            let proto_vec: Vec<u8> = field.serialize();
+            assert_eq!(proto, &proto_vec);
+        }
+        // Check Int32
+        check(
+            Int32Field::new("".to_string(), 1, -0xFFFFFF),
+            &[
+                0x8, 0x81, 0x80, 0x80, 0xf8, 0xff, 0xff, 0xff, 0xff, 0xff, 0x1,
+            ],
+        );
+        // Check Int64
+        check(
+            Int64Field::new("".to_string(), 1, -0xFFFFFFFFFFFFFF),
+            &[
+                0x8, 0x81, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0xff, 0x1,
             ],
         );
         // Check UInt32
@@ -1964,4 +4436,764 @@ mod test {
             &[130, 1, 10, 116, 101, 115, 116, 32, 118, 97, 108, 117, 101],
         );
     }
+
+    #[test]
+    fn deserialize_bool() {
+        fn check(proto: &[u8], value: bool) {
+            let mut field = BoolField::default();
+            assert_eq!(field.deserialize(proto).unwrap(), proto.len() as u64);
+            assert_eq!(field.0.data, value);
+        }
+
+        check(&[0x8, 0x0], false);
+        check(&[0x8, 0x1], true);
+        // Non-canonical encoders may emit values other than 0 or 1
+        check(&[0x8, 0x2], true);
+    }
+
+    #[test]
+    fn deserialize_string_mode() {
+        // field 1 = "héllo"
+        let proto = [0x0a, 0x06, 0x68, 0xc3, 0xa9, 0x6c, 0x6c, 0x6f];
+
+        let mut field = StringField::default();
+        assert!(field.deserialize(&proto).is_err());
+
+        let options = DecodeOptions {
+            string_mode: StringMode::Utf8,
+            ..DecodeOptions::default()
+        };
+        assert_eq!(field.deserialize_with(&proto, &options).unwrap(), 8);
+        assert_eq!(field.0.data, "héllo");
+    }
+
+    #[test]
+    fn deserialize_fallback_type() {
+        let proto = [0x0a, 0x02, 0x01, 0x02];
+
+        let mut field: Box<dyn FieldTrait> = FieldType::Repeated.into();
+        assert_eq!(field.deserialize(&proto).unwrap(), 4);
+        assert_eq!(field.field_type(), FieldType::Repeated);
+        assert_eq!(field.number(), 1);
+
+        let mut field: Box<dyn FieldTrait> = Box::new(Field::default());
+        assert_eq!(field.deserialize(&proto).unwrap(), 4);
+        assert_eq!(field.field_type(), FieldType::Bytes);
+    }
+
+    #[test]
+    fn deserialize_enum() {
+        let proto = [0x08, 0x01];
+
+        let mut field: Box<dyn FieldTrait> = FieldType::Enum.into();
+        assert_eq!(field.deserialize(&proto).unwrap(), 2);
+        assert_eq!(field.field_type(), FieldType::Enum);
+        assert_eq!(field.example(), "1");
+        assert_eq!(field.serialize(), proto);
+
+        // Enums are varints, length-delimited fields are not enums
+        let mut field: Box<dyn FieldTrait> = FieldType::Enum.into();
+        let error = field.deserialize(&[0x0a, 0x01, 0x41]).unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::IncorrectType);
+    }
+
+    #[test]
+    fn deserialize_truncated() {
+        // field 1 declares 5 bytes, only 2 follow
+        let proto = [0x0a, 0x05, 0x61, 0x62];
+
+        let fields: Vec<Box<dyn FieldTrait>> = vec![
+            Box::new(StringField::default()),
+            Box::new(BytesField::default()),
+            Box::new(EmbeddedField::default()),
+        ];
+        for mut field in fields {
+            let error = field.deserialize(&proto).unwrap_err();
+            assert_eq!(error.error_type(), ErrorType::Truncated);
+            assert_eq!(
+                format!("{}", error),
+                "Truncated: { declared size 5 exceeds remaining 2 bytes }"
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize_field_number_zero() {
+        use crate::parser::parser::{FullParser, Parser};
+
+        // field 0 = 1 (varint), field 0 = "a"
+        let varint = [0x00, 0x01];
+        let buffer = [0x02, 0x01, 0x61];
+
+        let error = Int32Field::default().deserialize(&varint).unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::InvalidFieldNumber);
+        let error = StringField::default().deserialize(&buffer).unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::InvalidFieldNumber);
+
+        assert!(FullParser::new().deserialize(&varint).is_err());
+        assert!(FullParser::new().deserialize(&buffer).is_err());
+    }
+
+    #[test]
+    fn deserialize_field_number_range() {
+        let check = |number: u64| {
+            // Keys of invalid numbers can't be generated
+            let mut buffer = serialize_varint(number << 3);
+            buffer.push(0x01);
+            Int32Field::default().deserialize(&buffer)
+        };
+
+        assert!(check(18999).is_ok());
+        assert_eq!(
+            check(19000).unwrap_err().error_type(),
+            ErrorType::InvalidFieldNumber
+        );
+        assert!(check(19999).is_err());
+        assert!(check(20000).is_ok());
+        assert!(check(1 << 29).is_err());
+    }
+
+    #[test]
+    fn deserialize_negative_int32() {
+        let field = Int32Field::new("".to_string(), 1, -5);
+        let proto = field.serialize();
+        // key, then -5 sign-extended to a 10-byte varint
+        assert_eq!(proto.len(), 11);
+
+        let mut decoded = Int32Field::default();
+        assert_eq!(decoded.deserialize(&proto).unwrap(), 11);
+        assert_eq!(decoded.0.data, -5);
+        assert_eq!(decoded.serialize(), proto);
+
+        // 2^32 fits neither a positive nor a sign-extended int32
+        let mut proto = vec![0x08];
+        proto.extend(serialize_varint(1 << 32));
+        assert!(Int32Field::default().deserialize(&proto).is_err());
+    }
+
+    #[test]
+    fn deserialize_sfixed_signed() {
+        // field 1 = FF FF FF FF
+        let proto = [0x0d, 0xff, 0xff, 0xff, 0xff];
+        let mut field = SFixed32Field::default();
+        assert_eq!(field.deserialize(&proto).unwrap(), 5);
+        assert_eq!(field.0.data, -1);
+        assert_eq!(field.example(), "-1");
+        assert_eq!(
+            field.to_str("param0"),
+            "optional sfixed32 param0 = 1; // Example: -1"
+        );
+        assert_eq!(field.serialize(), proto);
+
+        // field 1 = FE FF FF FF FF FF FF FF
+        let proto = [0x09, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        let mut field = SFixed64Field::default();
+        assert_eq!(field.deserialize(&proto).unwrap(), 9);
+        assert_eq!(field.0.data, -2);
+        assert_eq!(field.example(), "-2");
+        assert_eq!(field.serialize(), proto);
+    }
+
+    #[test]
+    fn wire_type() {
+        let expected = [
+            (FieldType::Int32, VariantTypeRaw::Varint),
+            (FieldType::Int64, VariantTypeRaw::Varint),
+            (FieldType::UInt32, VariantTypeRaw::Varint),
+            (FieldType::UInt64, VariantTypeRaw::Varint),
+            (FieldType::SInt32, VariantTypeRaw::Varint),
+            (FieldType::SInt64, VariantTypeRaw::Varint),
+            (FieldType::Bool, VariantTypeRaw::Varint),
+            (FieldType::Enum, VariantTypeRaw::Varint),
+            (FieldType::Fixed64, VariantTypeRaw::Double),
+            (FieldType::SFixed64, VariantTypeRaw::Double),
+            (FieldType::Double, VariantTypeRaw::Double),
+            (FieldType::String, VariantTypeRaw::Buffer),
+            (FieldType::Bytes, VariantTypeRaw::Buffer),
+            (FieldType::Embedded, VariantTypeRaw::Buffer),
+            (FieldType::Repeated, VariantTypeRaw::Buffer),
+            (FieldType::StartGroup, VariantTypeRaw::StartGroup),
+            (FieldType::EndGroup, VariantTypeRaw::EndGroup),
+            (FieldType::Map, VariantTypeRaw::Buffer),
+            (FieldType::Fixed32, VariantTypeRaw::Float),
+            (FieldType::SFixed32, VariantTypeRaw::Float),
+            (FieldType::Float, VariantTypeRaw::Float),
+        ];
+
+        for (field_type, wire_type) in expected.iter() {
+            assert_eq!(field_type.wire_type(), *wire_type);
+            assert_eq!(
+                wire_type.is_length_delimited(),
+                *wire_type == VariantTypeRaw::Buffer
+            );
+
+            // Fields created for a type use the same wire type
+            let field: Box<dyn FieldTrait> = (*field_type).into();
+            assert_eq!(field.field_type().wire_type(), *wire_type, "{}", field_type);
+        }
+    }
+
+    #[test]
+    fn field_ext() {
+        fn check<T: FieldTrait + 'static>(
+            field_type: FieldType,
+            as_type: fn(&mut Box<dyn FieldTrait>) -> Option<&mut T>,
+        ) {
+            let mut field: Box<dyn FieldTrait> = field_type.into();
+            assert!(as_type(&mut field).is_some(), "{}", field_type);
+
+            let other = match field_type {
+                FieldType::Int32 => FieldType::Bytes,
+                _ => FieldType::Int32,
+            };
+            let mut field: Box<dyn FieldTrait> = other.into();
+            assert!(as_type(&mut field).is_none(), "{}", field_type);
+        }
+
+        check(FieldType::Int32, |x| x.as_int32());
+        check(FieldType::Int64, |x| x.as_int64());
+        check(FieldType::UInt32, |x| x.as_uint32());
+        check(FieldType::UInt64, |x| x.as_uint64());
+        check(FieldType::SInt32, |x| x.as_sint32());
+        check(FieldType::SInt64, |x| x.as_sint64());
+        check(FieldType::Bool, |x| x.as_bool());
+        check(FieldType::Fixed32, |x| x.as_fixed32());
+        check(FieldType::SFixed32, |x| x.as_sfixed32());
+        check(FieldType::Float, |x| x.as_float());
+        check(FieldType::Fixed64, |x| x.as_fixed64());
+        check(FieldType::SFixed64, |x| x.as_sfixed64());
+        check(FieldType::Double, |x| x.as_double());
+        check(FieldType::String, |x| x.as_string());
+        check(FieldType::Bytes, |x| x.as_bytes());
+        check(FieldType::StartGroup, |x| x.as_start_group());
+        check(FieldType::EndGroup, |x| x.as_end_group());
+        check(FieldType::Embedded, |x| x.as_embedded());
+        check(FieldType::Map, |x| x.as_map());
+    }
+
+    #[test]
+    fn deserialize_endianness() {
+        let big_endian = DecodeOptions {
+            endianness: Endianness::Big,
+            ..DecodeOptions::default()
+        };
+
+        // field 1 fixed32 = 00 00 00 01
+        let proto = [0x0d, 0x00, 0x00, 0x00, 0x01];
+        let mut field = Fixed32Field::default();
+        assert_eq!(field.deserialize(&proto).unwrap(), 5);
+        assert_eq!(field.0.data, 0x0100_0000);
+        assert_eq!(field.deserialize_with(&proto, &big_endian).unwrap(), 5);
+        assert_eq!(field.0.data, 1);
+
+        // field 1 float = 3F 80 00 00 (1.0 big-endian)
+        let proto = [0x0d, 0x3f, 0x80, 0x00, 0x00];
+        let mut field = FloatField::default();
+        field.deserialize(&proto).unwrap();
+        assert_ne!(field.0.data, 1.0);
+        field.deserialize_with(&proto, &big_endian).unwrap();
+        assert_eq!(field.0.data, 1.0);
+
+        // field 1 fixed64 = 00 .. 00 02
+        let proto = [0x09, 0, 0, 0, 0, 0, 0, 0, 0x02];
+        let mut field = Fixed64Field::default();
+        field.deserialize(&proto).unwrap();
+        assert_eq!(field.0.data, 0x0200_0000_0000_0000);
+        field.deserialize_with(&proto, &big_endian).unwrap();
+        assert_eq!(field.0.data, 2);
+    }
+
+    #[test]
+    fn deserialize_uint64_max() {
+        // field 1 = u64::MAX
+        let mut proto = vec![0x08];
+        proto.extend_from_slice(&serialize_varint(u64::MAX));
+
+        let mut field = UInt64Field::default();
+        assert_eq!(field.deserialize(&proto).unwrap(), 11);
+        assert_eq!(field.0.data, u64::MAX);
+        assert_eq!(field.example(), "18446744073709551615");
+        assert_eq!(
+            field.to_str("param0"),
+            "optional uint64 param0 = 1; // Example: 18446744073709551615 \
+             // int64: -1, uint64: 18446744073709551615, sint64: -9223372036854775808"
+        );
+        assert_eq!(field.serialize(), proto);
+    }
+
+    #[test]
+    fn encoded_len() {
+        fn check(mut field: Box<dyn FieldTrait>, proto: &[u8]) {
+            field.deserialize(proto).unwrap();
+            assert_eq!(field.encoded_len(), field.serialize().len(), "{:?}", proto);
+        }
+
+        check(FieldType::Int32.into(), &[0x08, 0x96, 0x01]);
+        check(
+            FieldType::Int32.into(),
+            &[0x08, 0xff, 0xff, 0xff, 0xff, 0x0f],
+        );
+        check(FieldType::Int64.into(), &[0x08, 0x96, 0x01]);
+        check(FieldType::UInt32.into(), &[0x08, 0x00]);
+        check(FieldType::UInt64.into(), &[0x80, 0x01, 0x96, 0x01]);
+        check(FieldType::SInt32.into(), &[0x08, 0x03]);
+        check(FieldType::SInt64.into(), &[0x08, 0x04]);
+        check(FieldType::Bool.into(), &[0x08, 0x01]);
+        check(FieldType::Fixed32.into(), &[0x0d, 0x01, 0x02, 0x03, 0x04]);
+        check(FieldType::SFixed32.into(), &[0x0d, 0x01, 0x02, 0x03, 0x04]);
+        check(FieldType::Float.into(), &[0x0d, 0x00, 0x00, 0x80, 0x3f]);
+        check(FieldType::Fixed64.into(), &[0x09, 1, 2, 3, 4, 5, 6, 7, 8]);
+        check(FieldType::SFixed64.into(), &[0x09, 1, 2, 3, 4, 5, 6, 7, 8]);
+        check(FieldType::Double.into(), &[0x09, 1, 2, 3, 4, 5, 6, 7, 8]);
+        check(FieldType::String.into(), &[0x0a, 0x03, 0x61, 0x62, 0x63]);
+        check(FieldType::Bytes.into(), &[0x0a, 0x02, 0x01, 0xff]);
+        check(FieldType::StartGroup.into(), &[0x0b]);
+        check(FieldType::EndGroup.into(), &[0x0c]);
+
+        // field 1 = {1: 150, 2: {1: 1}}
+        let proto = [0x0a, 0x07, 0x08, 0x96, 0x01, 0x12, 0x02, 0x08, 0x01];
+        let (mut embedded, _) = crate::parser::parser::FullParser::new()
+            .deserialize_field(&proto)
+            .unwrap();
+        assert_eq!(embedded.as_embedded().unwrap().field.data.fields.len(), 2);
+        assert_eq!(embedded.encoded_len(), proto.len());
+        assert_eq!(embedded.encoded_len(), embedded.serialize().len());
+    }
+
+    #[test]
+    fn bytes_as_string_comment() {
+        let text = BytesField::new("a".to_string(), 1, b"hello");
+        assert!(text.repr().ends_with(" // as string: \"hello\""));
+        assert!(text
+            .to_str("a")
+            .ends_with("// Example: 68 65 6C 6C 6F // as string: \"hello\""));
+
+        let binary = BytesField::new("b".to_string(), 2, &[0x01, 0xff]);
+        assert!(!binary.repr().contains("as string"));
+        assert!(!binary.to_str("b").contains("as string"));
+    }
+
+    #[test]
+    fn deserialize_fixed32_multibyte_key() {
+        // field 2000 = 00 00 80 3F, the key takes 2 bytes, followed by field 1 = 1
+        let proto = [0x85, 0x7d, 0x00, 0x00, 0x80, 0x3f, 0x08, 0x01];
+
+        let mut float = FloatField::default();
+        assert_eq!(float.deserialize(&proto).unwrap(), 6);
+        assert_eq!(float.0.number, 2000);
+        assert_eq!(float.0.data, 1.0);
+        assert_eq!(float.serialize(), proto[..6]);
+
+        let mut fixed = Fixed32Field::default();
+        assert_eq!(fixed.deserialize(&proto).unwrap(), 6);
+        assert_eq!(fixed.0.number, 2000);
+        assert_eq!(fixed.0.data, 0x3f800000);
+        assert_eq!(fixed.serialize(), proto[..6]);
+
+        let mut sfixed = SFixed32Field::default();
+        assert_eq!(sfixed.deserialize(&proto).unwrap(), 6);
+        assert_eq!(sfixed.0.number, 2000);
+        assert_eq!(sfixed.0.data, 0x3f800000);
+        assert_eq!(sfixed.serialize(), proto[..6]);
+
+        // Only 3 of 4 value bytes after the 2-byte key
+        let truncated = &proto[..5];
+        assert!(FloatField::default().deserialize(truncated).is_err());
+        assert!(Fixed32Field::default().deserialize(truncated).is_err());
+        assert!(SFixed32Field::default().deserialize(truncated).is_err());
+    }
+
+    #[test]
+    fn embedded_length_comment() {
+        // field 2 = {1: 150, 2: "ab"}
+        let proto = [0x12, 0x07, 0x08, 0x96, 0x01, 0x12, 0x02, 0x61, 0x62];
+        let mut field = EmbeddedField::default();
+        field.deserialize(&proto).unwrap();
+
+        let raw_len = field.raw.as_ref().unwrap().len();
+        assert_eq!(raw_len, 7);
+        assert!(field
+            .to_str("param0")
+            .ends_with(&format!("Param0 param0 = 2; // {} bytes", raw_len)));
+    }
+
+    #[test]
+    fn deserialize_into() {
+        fn check<T: DeserializeInto>(field: T) {
+            let proto = field.serialize();
+            let (decoded, readed) = T::deserialize_into(&proto).unwrap();
+            assert_eq!(readed, proto.len() as u64);
+            assert_eq!(decoded.number(), field.number());
+            assert_eq!(decoded.field_type(), field.field_type());
+            assert_eq!(decoded.serialize(), proto);
+        }
+
+        check(Int32Field::new("".to_string(), 1, -150));
+        check(Int64Field::new("".to_string(), 2, -150));
+        check(UInt32Field::new("".to_string(), 3, 150));
+        check(UInt64Field::new("".to_string(), 4, 150));
+        check(SInt32Field::new("".to_string(), 5, -150));
+        check(SInt64Field::new("".to_string(), 6, -150));
+        check(BoolField::new("".to_string(), 7, true));
+        check(Fixed32Field::new("".to_string(), 8, 150));
+        check(SFixed32Field::new("".to_string(), 9, -150));
+        check(FloatField::new("".to_string(), 10, 1.5));
+        check(Fixed64Field::new("".to_string(), 11, 150));
+        check(SFixed64Field::new("".to_string(), 12, -150));
+        check(DoubleField::new("".to_string(), 13, 1.5));
+        check(StringField::new("".to_string(), 14, "text".to_string()));
+        check(BytesField::new("".to_string(), 15, &[0x01, 0xff]));
+
+        let (field, readed) = Int32Field::deserialize_into(&[0x08, 0x96, 0x01, 0x10]).unwrap();
+        assert_eq!((field.0.data, readed), (150, 3));
+        assert!(Int32Field::deserialize_into(&[0x0a, 0x00]).is_err());
+    }
+
+    #[test]
+    fn deserialize_bytes_without_length() {
+        // field 1 key of a length-delimited field and nothing else
+        let error = BytesField::default().deserialize(&[0x0a]).unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::UnexpectedEof);
+        // field 2000 key, two bytes long
+        let error = BytesField::default()
+            .deserialize(&[0x82, 0x7d])
+            .unwrap_err();
+        assert_eq!(error.error_type(), ErrorType::UnexpectedEof);
+    }
+
+    #[test]
+    fn naming_strategy() {
+        let fields: Vec<Box<dyn FieldTrait>> = vec![
+            Box::new(Int32Field::new("".to_string(), 1, 1)),
+            Box::new(BoolField::new("".to_string(), 2, true)),
+            Box::new(StringField::new("".to_string(), 3, "abc".to_string())),
+        ];
+
+        let lines = fields_to_str(&fields);
+        assert!(lines[2].contains(" param2 = 3;"));
+
+        let semantic = RenderOptions {
+            naming: Arc::new(semantic_name),
+            ..RenderOptions::default()
+        };
+        let lines = fields_to_str_with(&fields, &semantic);
+        assert!(lines[0].contains(" num_0 = 1;"));
+        assert!(lines[1].contains(" flag_1 = 2;"));
+        assert!(lines[2].contains(" str_field_2 = 3;"));
+
+        let custom = RenderOptions {
+            naming: Arc::new(|_, index| format!("f{}", index)),
+            ..RenderOptions::default()
+        };
+        assert!(fields_to_str_with(&fields, &custom)[2].contains(" f2 = 3;"));
+
+        // Strategies may capture state, e.g. names from a known schema
+        let prefix = "field".to_string();
+        let capturing = RenderOptions {
+            naming: Arc::new(move |_, index| format!("{}_{}", prefix, index)),
+            ..RenderOptions::default()
+        };
+        assert!(fields_to_str_with(&fields, &capturing)[2].contains(" field_2 = 3;"));
+    }
+
+    #[test]
+    fn packed_annotation() {
+        use crate::parser::parser::{FullParser, Parser};
+
+        let options = RenderOptions {
+            detect_packed: true,
+            ..RenderOptions::default()
+        };
+
+        // repeated int32 field 1 = [1, 2, 300] packed
+        let packed = [0x0a, 0x04, 0x01, 0x02, 0xac, 0x02];
+        let message = FullParser::new().deserialize(&packed).unwrap();
+        assert_eq!(
+            fields_to_str_with(&message.fields, &options),
+            ["repeated int32 param0 = 1; // Example: [1, 2, 300] // packed"]
+        );
+        assert!(!fields_to_str(&message.fields)[0].contains("packed"));
+
+        // repeated int32 field 1 = [1, 2, 300] unpacked
+        let unpacked = [0x08, 0x01, 0x08, 0x02, 0x08, 0xac, 0x02];
+        let message = FullParser::new().deserialize(&unpacked).unwrap();
+        assert_eq!(
+            fields_to_str_with(&message.fields, &options),
+            ["repeated int32 param0 = 1; // Example: [1, 2, 300] // unpacked"]
+        );
+
+        // Overlong varints and a single value are not taken for packed fields
+        let bytes = BytesField::new("".to_string(), 1, &[0x81, 0x00, 0x02]);
+        assert!(packed_to_str(&bytes, "param0").is_none());
+        let bytes = BytesField::new("".to_string(), 1, &[0x01]);
+        assert!(packed_to_str(&bytes, "param0").is_none());
+    }
+
+    #[test]
+    fn embedded_as_message() {
+        let inner = FieldsVector {
+            fields: vec![
+                Box::new(Int32Field::new("".to_string(), 1, 150)),
+                Box::new(StringField::new("".to_string(), 2, "abc".to_string())),
+            ],
+        };
+        let embedded = EmbeddedField::new("nested_msg".to_string(), 3, inner);
+
+        let message = embedded.as_message().unwrap();
+        assert_eq!(message.name, "NestedMsg");
+        let numbers: Vec<u64> = message.fields.iter().map(|x| x.number()).collect();
+        assert_eq!(numbers, [1, 2]);
+        assert_eq!(message.fields[0].example(), "150");
+        assert_eq!(message.serialize(), embedded.raw.unwrap());
+
+        assert!(EmbeddedField::default().as_message().is_none());
+    }
+
+    #[test]
+    fn sint_zigzag_roundtrip() {
+        for var in [0, -1, 1, i32::MAX, i32::MIN].iter() {
+            let bytes = SInt32Field::new("".to_string(), 1, *var).serialize();
+            let mut field = SInt32Field::default();
+            assert_eq!(field.deserialize(&bytes).unwrap(), bytes.len() as u64);
+            assert_eq!(field.0.data, *var);
+        }
+        for var in [0, -1, 1, i64::MAX, i64::MIN].iter() {
+            let bytes = SInt64Field::new("".to_string(), 1, *var).serialize();
+            let mut field = SInt64Field::default();
+            assert_eq!(field.deserialize(&bytes).unwrap(), bytes.len() as u64);
+            assert_eq!(field.0.data, *var);
+        }
+    }
+
+    #[test]
+    fn field_declarations_order() {
+        let fields: Vec<Box<dyn FieldTrait>> = vec![
+            Box::new(Int32Field::new("".to_string(), 3, 1)),
+            Box::new(StringField::new("".to_string(), 1, "a".to_string())),
+            Box::new(Int32Field::new("".to_string(), 3, 2)),
+            Box::new(Int32Field::new("".to_string(), 2, 3)),
+        ];
+
+        let declarations = field_declarations(&fields, &RenderOptions::default());
+        let indices: Vec<usize> = declarations.iter().map(|x| x.original_index).collect();
+        assert_eq!(indices, [0, 1, 3]);
+        let numbers: Vec<u64> = declarations.iter().map(|x| x.number).collect();
+        assert_eq!(numbers, [3, 1, 2]);
+
+        let options = RenderOptions {
+            sorted: true,
+            ..RenderOptions::default()
+        };
+        let sorted = field_declarations(&fields, &options);
+        let indices: Vec<usize> = sorted.iter().map(|x| x.original_index).collect();
+        assert_eq!(indices, [1, 3, 0]);
+        assert_eq!(
+            fields_to_str_with(&fields, &options),
+            [
+                "optional string param1 = 1; // Example: \"a\"",
+                "optional int32 param2 = 2; // Example: 3 // int32: 3, uint32: 3, sint32: -2",
+                "repeated int32 param0 = 3; // Example: [1, 2] // unpacked",
+            ]
+        );
+    }
+
+    #[test]
+    fn field_declarations_repeated_embedded() {
+        // field 1 = {1: 1}, field 1 = {1: 2}, field 1 = {2: 3}
+        let entry = |number, data| {
+            let nested: Vec<Box<dyn FieldTrait>> =
+                vec![Box::new(Int32Field::new("".to_string(), number, data))];
+            Box::new(EmbeddedField::new(
+                "".to_string(),
+                1,
+                FieldsVector { fields: nested },
+            )) as Box<dyn FieldTrait>
+        };
+        let fields = vec![entry(1, 1), entry(1, 2), entry(2, 3)];
+
+        let declarations = field_declarations(&fields, &RenderOptions::default());
+        assert_eq!(declarations.len(), 1);
+        assert_eq!(
+            declarations[0].line,
+            "message Param0 {\n\
+             \toptional int32 param0 = 1; // Example: 1 // int32: 1, uint32: 1, sint32: -1\n\
+             \toptional int32 param1 = 2; // Example: 3 // int32: 3, uint32: 3, sint32: -2\n\
+             }\n\
+             repeated Param0 param0 = 1;"
+        );
+    }
+
+    #[test]
+    fn varint_interpretations() {
+        // field 1 = 0xFFFFFFFF as 32-bit varint
+        let bytes = [0x08, 0xff, 0xff, 0xff, 0xff, 0x0f];
+        let mut field = Int32Field::default();
+        assert_eq!(field.deserialize(&bytes).unwrap(), 6);
+        assert_eq!(
+            field.to_str("param0"),
+            "optional int32 param0 = 1; // Example: -1 // int32: -1, uint32: 4294967295, sint32: -2147483648"
+        );
+
+        let field = Int64Field::new("".to_string(), 1, -3);
+        assert!(field
+            .to_str("param0")
+            .ends_with("// int64: -3, uint64: 18446744073709551613, sint64: -9223372036854775807"));
+
+        assert_eq!(
+            Int32Field::new("".to_string(), 1, 150).to_str("param0"),
+            "optional int32 param0 = 1; // Example: 150 // int32: 150, uint32: 150, sint32: 75"
+        );
+    }
+
+    #[test]
+    fn field_name_override() {
+        let mut fields: Vec<Box<dyn FieldTrait>> = vec![
+            Box::new(Int32Field::new("".to_string(), 1, 150)),
+            Box::new(StringField::new("".to_string(), 2, "ab".to_string())),
+        ];
+        assert_eq!(fields[0].name(), "");
+        fields[0].set_name("user_id");
+        assert_eq!(fields[0].name(), "user_id");
+
+        assert_eq!(
+            fields_to_str(&fields),
+            [
+                "optional int32 user_id = 1; // Example: 150 // int32: 150, uint32: 150, sint32: 75",
+                "optional string param1 = 2; // Example: \"ab\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn to_str_stored_name() {
+        let field = Int32Field::new("user_id".to_string(), 1, 150);
+        assert_eq!(
+            field.to_str("param0"),
+            "optional int32 user_id = 1; // Example: 150 // int32: 150, uint32: 150, sint32: 75"
+        );
+        let mut field = BytesField::new("".to_string(), 2, &[0x01]);
+        assert!(field.to_str("param1").contains(" param1 = 2;"));
+        field.set_name("payload");
+        assert!(field.to_str("param1").contains(" payload = 2;"));
+
+        let inner = FieldsVector {
+            fields: vec![Box::new(Int32Field::new("".to_string(), 1, 1))],
+        };
+        let embedded = EmbeddedField::new("user".to_string(), 3, inner);
+        assert!(embedded
+            .to_str("param2")
+            .ends_with("optional User user = 3; // 2 bytes"));
+    }
+
+    #[test]
+    fn duplicate_names_unique() {
+        let inner = FieldsVector {
+            fields: vec![Box::new(Int32Field::new("".to_string(), 1, 1))],
+        };
+        let fields: Vec<Box<dyn FieldTrait>> = vec![
+            Box::new(Int32Field::new("".to_string(), 1, 1)),
+            Box::new(StringField::new("param0".to_string(), 2, "a".to_string())),
+            Box::new(EmbeddedField::new("user".to_string(), 3, inner.clone())),
+            Box::new(EmbeddedField::new("user".to_string(), 4, inner)),
+        ];
+
+        let lines = fields_to_str(&fields);
+        assert!(lines[0].contains(" param0 = 1;"));
+        assert!(lines[1].contains(" param0_2 = 2;"));
+        assert!(lines[2].starts_with("message User {"));
+        assert!(lines[2].ends_with("optional User user = 3; // 2 bytes"));
+        assert!(lines[3].starts_with("message User2 {"));
+        assert!(lines[3].ends_with("optional User2 user_2 = 4; // 2 bytes"));
+        // Stored names are left as they are
+        assert_eq!(fields[3].name(), "user");
+    }
+
+    #[test]
+    fn fixed64_halves() {
+        // 1.5f32 in the low half, 7 in the high half
+        let field = Fixed64Field::new("".to_string(), 1, 0x0000_0007_3fc0_0000);
+        assert_eq!(
+            field.serialize()[1..],
+            [0x00, 0x00, 0xc0, 0x3f, 0x07, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(
+            field.to_str("param0"),
+            "optional fixed64 param0 = 1; // Example: 31134318592 \
+             // halves: low 0x3fc00000 (float 1.5), high 0x00000007 (float 1e-44)"
+        );
+    }
+
+    #[test]
+    fn radix_consistent() {
+        let field = Int32Field::new("".to_string(), 1, 123);
+        let hex = RenderOptions {
+            radix: Some(Radix::Hex),
+            ..RenderOptions::default()
+        };
+        let dec = RenderOptions {
+            radix: Some(Radix::Dec),
+            ..RenderOptions::default()
+        };
+
+        let readings = " // int32: 123, uint32: 123, sint32: -62";
+        assert!(field
+            .repr_with(&hex)
+            .ends_with(&format!("= 0x7b{}", readings)));
+        assert!(field
+            .to_str_with("a", &hex)
+            .ends_with(&format!("// Example: 0x7b{}", readings)));
+        assert!(field
+            .repr_with(&dec)
+            .ends_with(&format!("= 123{}", readings)));
+        assert!(field
+            .to_str_with("a", &dec)
+            .ends_with(&format!("// Example: 123{}", readings)));
+
+        // Without a radix repr stays hex and examples stay decimal
+        let options = RenderOptions::default();
+        assert_eq!(field.repr_with(&options), field.repr());
+        assert_eq!(field.to_str_with("a", &options), field.to_str("a"));
+        assert!(field.repr().ends_with(&format!("= 0x7b{}", readings)));
+        assert!(field
+            .to_str("a")
+            .ends_with(&format!("// Example: 123{}", readings)));
+    }
+
+    #[test]
+    fn non_canonical_varint() {
+        // field 1 = 22 written as [0x96, 0x00] instead of [0x16]
+        let mut overlong = UInt32Field::default();
+        assert_eq!(overlong.deserialize(&[0x08, 0x96, 0x00]).unwrap(), 3);
+        assert_eq!(overlong.0.data, 22);
+        assert!(overlong.0.non_canonical);
+        assert!(overlong
+            .to_str("a")
+            .ends_with("// non-canonical varint // int32: 22, uint32: 22, sint32: 11"));
+        assert!(overlong
+            .repr()
+            .ends_with("// non-canonical varint // int32: 22, uint32: 22, sint32: 11"));
+        assert_ne!(overlong.serialize(), [0x08, 0x96, 0x00]);
+
+        let mut canonical = UInt32Field::default();
+        canonical.deserialize(&[0x08, 0x16]).unwrap();
+        assert!(!canonical.0.non_canonical);
+        assert!(!canonical.to_str("a").contains("non-canonical"));
+    }
+
+    #[test]
+    fn largest_field_number() {
+        let field = Int32Field::new("".to_string(), MAX_FIELD_NUMBER, 1);
+        let encoded = field.serialize();
+        assert_eq!(encoded, [0xf8, 0xff, 0xff, 0xff, 0x0f, 0x01]);
+
+        let mut decoded = Int32Field::default();
+        assert_eq!(decoded.deserialize(&encoded).unwrap(), 6);
+        assert_eq!(decoded, field);
+
+        // Tag 2^29 is one past the largest valid number
+        let mut encoded = Vec::new();
+        write_varint((MAX_FIELD_NUMBER + 1) << 3, &mut encoded);
+        encoded.push(0x01);
+        assert!(Int32Field::default().deserialize(&encoded).is_err());
+    }
 }
@@ -1,32 +1,122 @@
 // use core::fmt;
-use crate::proto::field::FieldTrait;
+
+use crate::proto::error::{Error, ErrorType, Result};
+use crate::proto::field::*;
+use crate::proto::utils::check_field_number;
 
 /// Protobuf syntax
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
 pub enum Syntax {
-    /// Protobuf syntax 2(default)
+    /// Protobuf syntax 2
     Proto2,
-    /// Protobuf syntax 3
+    /// Protobuf syntax 3(default)
+    #[default]
     Proto3,
 }
 
+impl Syntax {
+    /// Name used in the `syntax = "...";` statement
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Syntax::Proto2 => "proto2",
+            Syntax::Proto3 => "proto3",
+        }
+    }
+}
+
+/// Drop `optional`/`required` labels, proto3 has no `required` and `optional`
+/// is implicit
+fn strip_labels(proto: &str) -> String {
+    proto
+        .split('\n')
+        .map(|line| {
+            let declaration = line.trim_start();
+            let indent = &line[..line.len() - declaration.len()];
+            match declaration
+                .strip_prefix("optional ")
+                .or_else(|| declaration.strip_prefix("required "))
+            {
+                Some(rest) => format!("{}{}", indent, rest),
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Collect strings of `fields` and their embedded messages into `strings`
+fn collect_strings(
+    fields: &[Box<dyn FieldTrait>],
+    min_len: usize,
+    strings: &mut Vec<(u64, String)>,
+) {
+    for field in fields.iter() {
+        let any = field.as_any_ref();
+        let text = if let Some(x) = any.downcast_ref::<StringField>() {
+            Some(x.0.data.as_str())
+        } else if let Some(x) = any.downcast_ref::<BytesField>() {
+            printable_str(&x.0.data)
+        } else {
+            if let Some(x) = any.downcast_ref::<EmbeddedField>() {
+                collect_strings(&x.field.data.fields, min_len, strings);
+            }
+            None
+        };
+        if let Some(text) = text.filter(|x| x.chars().count() >= min_len) {
+            strings.push((field.number(), text.to_string()));
+        }
+    }
+}
+
+/// Nesting depth of `fields`, 1 without embedded messages
+///
+/// Messages held as map values are nested like embedded fields.
+fn fields_depth(fields: &[Box<dyn FieldTrait>]) -> usize {
+    let nested = fields
+        .iter()
+        .flat_map(|x| match x.as_any_ref().downcast_ref::<MapField>() {
+            Some(map) => map.field.data.iter().map(|(_, value)| value).collect(),
+            None => vec![x],
+        })
+        .filter_map(|x| x.as_any_ref().downcast_ref::<EmbeddedField>())
+        .map(|x| fields_depth(&x.field.data.fields))
+        .max()
+        .unwrap_or(0);
+    1 + nested
+}
+
 /// Protobuf message
-//#[derive(Debug, Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Message {
     /// Message name
     pub name: String,
     /// List of fields
     pub fields: Vec<Box<dyn FieldTrait>>,
+    /// Syntax used to render the message
+    pub syntax: Syntax,
 }
 
 impl Message {
     pub fn new(name: String, fields: Option<Vec<Box<dyn FieldTrait>>>) -> Self {
         Message {
-            name: name,
-            fields: match fields {
-                Some(x) => x,
-                None => Vec::new(),
-            },
+            name,
+            fields: fields.unwrap_or_default(),
+            syntax: Syntax::Proto3,
+        }
+    }
+
+    /// Set syntax used to render the message
+    pub fn with_syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Builder holding the fields of the message, to edit and serialize it again
+    pub fn into_builder(self) -> MessageBuilder {
+        MessageBuilder {
+            name: self.name,
+            fields: self.fields,
+            syntax: self.syntax,
         }
     }
 
@@ -39,16 +129,889 @@ impl Message {
         self.serialize_into(&mut gen);
         gen
     }
+
+    /// Length of `serialize` output, computed without building it
+    pub fn encoded_len(&self) -> usize {
+        self.fields.iter().map(|x| x.encoded_len()).sum()
+    }
+
+    /// Serialization with fields ordered by tag number
+    ///
+    /// Protobuf allows any order, but canonical form is ascending. The sort is
+    /// stable, so values of a repeated tag keep their relative order.
+    pub fn serialize_sorted_into(&self, into: &mut Vec<u8>) {
+        let mut fields: Vec<&Box<dyn FieldTrait>> = self.fields.iter().collect();
+        fields.sort_by_key(|x| x.number());
+        fields.iter().for_each(|x| x.serialize_into(into));
+    }
+
+    pub fn serialize_sorted(&self) -> Vec<u8> {
+        let mut gen = Vec::new();
+        self.serialize_sorted_into(&mut gen);
+        gen
+    }
+
+    /// Mark runs of consecutive fields sharing tag number and type as repeated
+    ///
+    /// A tag seen several times in a row is a repeated field, the generated
+    /// schema then declares it `repeated` (embedded messages included).
+    pub fn collapse_repeated(&mut self) {
+        let mut start = 0;
+        while start < self.fields.len() {
+            let number = self.fields[start].number();
+            let field_type = self.fields[start].field_type();
+            let end = (start..self.fields.len())
+                .find(|&x| {
+                    self.fields[x].number() != number || self.fields[x].field_type() != field_type
+                })
+                .unwrap_or(self.fields.len());
+
+            if end - start > 1 {
+                self.fields[start..end]
+                    .iter_mut()
+                    .for_each(|x| x.set_rule(FieldLabel::Repeated));
+            }
+            start = end;
+        }
+    }
+
+    /// Decode every field with tag `number` again as `new_type`
+    ///
+    /// Fields are re-decoded from their encoded bytes using `options`, e.g. a
+    /// `fixed32` as a `float`, and keep their name and rule. Nothing is changed
+    /// unless all of them decode. Embedded messages and maps need a parser, they
+    /// can't be the new type.
+    pub fn retype_field(
+        &mut self,
+        number: u64,
+        new_type: FieldType,
+        options: &DecodeOptions,
+    ) -> Result<()> {
+        if matches!(new_type, FieldType::Embedded | FieldType::Map) {
+            return Err(Error::new(
+                &format!("can't retype field {} as {}", number, new_type.proto_type()),
+                Some(ErrorType::IncorrectType),
+            ));
+        }
+
+        let mut retyped = Vec::new();
+        for (i, field) in self.fields.iter().enumerate() {
+            if field.number() != number {
+                continue;
+            }
+            let mut new_field: Box<dyn FieldTrait> = new_type.into();
+            new_field.deserialize_with(&field.serialize(), options)?;
+            new_field.set_name(field.name());
+            new_field.set_rule(field.rule());
+            retyped.push((i, new_field));
+        }
+        if retyped.is_empty() {
+            return Err(Error::new(
+                &format!("no field with number {}", number),
+                Some(ErrorType::IncorrectData),
+            ));
+        }
+
+        for (i, field) in retyped {
+            self.fields[i] = field;
+        }
+        Ok(())
+    }
+
+    /// Append fields of `other` unless the same field is already decoded
+    ///
+    /// Fields are the same when tag number, type and encoded value match. Tags
+    /// seen several times in either message are repeated fields, equal values of
+    /// those are legitimate so they are always appended.
+    pub fn merge(&mut self, other: Message) {
+        fn is_repeated(fields: &[Box<dyn FieldTrait>], number: u64) -> bool {
+            fields.iter().filter(|x| x.number() == number).count() > 1
+        }
+
+        let repeated: Vec<bool> = other
+            .fields
+            .iter()
+            .map(|x| {
+                is_repeated(&other.fields, x.number()) || is_repeated(&self.fields, x.number())
+            })
+            .collect();
+        let known = self.fields.len();
+        for (field, repeated) in other.fields.into_iter().zip(repeated) {
+            let duplicate = !repeated
+                && self.fields[..known].iter().any(|x| {
+                    x.number() == field.number()
+                        && x.field_type() == field.field_type()
+                        && x.serialize() == field.serialize()
+                });
+            if !duplicate {
+                self.fields.push(field);
+            }
+        }
+    }
+
+    /// Check that the message serializes back to exactly `original`
+    ///
+    /// Parsing is heuristic, so a mismatch (e.g. non-canonical varints or fields
+    /// of a wrong guessed type) means the decoded structure is not faithful.
+    pub fn reencode_matches(&self, original: &[u8]) -> bool {
+        self.serialize() == original
+    }
+
+    /// Readable strings of the message and its embedded messages with their
+    /// tag numbers
+    ///
+    /// Besides string fields, bytes fields with printable payloads are
+    /// included. Strings shorter than `min_len` characters are skipped.
+    pub fn find_strings(&self, min_len: usize) -> Vec<(u64, String)> {
+        let mut strings = Vec::new();
+        collect_strings(&self.fields, min_len, &mut strings);
+        strings
+    }
+
+    /// Deepest nesting of embedded messages, counting the message itself
+    ///
+    /// A message without embedded messages has depth 1.
+    pub fn depth(&self) -> usize {
+        fields_depth(&self.fields)
+    }
+
+    /// Render message in protoscope text syntax, one field per line
+    pub fn to_protoscope(&self) -> String {
+        self.fields
+            .iter()
+            .map(|x| x.to_protoscope())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Render message as `.proto` definition
+    ///
+    /// Labels other than `repeated` are only kept for proto2.
+    pub fn to_proto(&self) -> String {
+        self.to_proto_with(&RenderOptions::default())
+    }
+
+    /// Render message as `.proto` definition using `RenderOptions`
+    pub fn to_proto_with(&self, options: &RenderOptions) -> String {
+        let fields = options.indent_lines(&fields_to_str_with(&self.fields, options));
+        let proto = format!("message {} {{{}\n}}", self.name, fields);
+        match self.syntax {
+            Syntax::Proto2 => proto,
+            Syntax::Proto3 => strip_labels(&proto),
+        }
+    }
+
+    /// Render message as `.proto` file starting with the syntax statement
+    pub fn to_proto_file(&self) -> String {
+        self.to_proto_file_with(&RenderOptions::default())
+    }
+
+    /// Render message as `.proto` file using `RenderOptions`
+    pub fn to_proto_file_with(&self, options: &RenderOptions) -> String {
+        format!(
+            "syntax = \"{}\";\n\n{}",
+            self.syntax.name(),
+            self.to_proto_with(options)
+        )
+    }
+
+    /// `Debug` output with field values written using `RenderOptions`
+    pub fn repr_with(&self, options: &RenderOptions) -> String {
+        self.fields.iter().fold("Message\n".to_string(), |repr, x| {
+            repr + &x.repr_with(options) + "\n"
+        })
+    }
 }
 
 impl core::fmt::Debug for Message {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "Message")?;
+        write!(f, "{}", self.repr_with(&RenderOptions::default()))
+    }
+}
+
+/// Builder of messages to serialize
+///
+/// Fields are serialized in the order they are added.
+#[derive(Default)]
+pub struct MessageBuilder {
+    name: String,
+    fields: Vec<Box<dyn FieldTrait>>,
+    syntax: Syntax,
+}
+
+impl MessageBuilder {
+    pub fn new(name: &str) -> Self {
+        MessageBuilder {
+            name: name.to_string(),
+            fields: Vec::new(),
+            syntax: Syntax::Proto3,
+        }
+    }
+
+    /// Set syntax of the built message
+    pub fn with_syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Put `field` in place of the first field with its number and drop the
+    /// others, the field is added at the end if there is none
+    pub fn replace_field(mut self, field: Box<dyn FieldTrait>) -> Self {
+        let number = field.number();
+        match self.fields.iter().position(|x| x.number() == number) {
+            Some(i) => {
+                self.fields[i] = field;
+                let rest = self.fields.split_off(i + 1);
+                self.fields
+                    .extend(rest.into_iter().filter(|x| x.number() != number));
+            }
+            None => self.fields.push(field),
+        }
+        self
+    }
 
+    /// Drop every field with tag `number`
+    pub fn remove_field(mut self, number: u64) -> Self {
+        self.fields.retain(|x| x.number() != number);
+        self
+    }
+
+    pub fn add_field(mut self, field: Box<dyn FieldTrait>) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn add_int32(self, number: u64, value: i32) -> Self {
+        self.add_field(Box::new(Int32Field::new("".to_string(), number, value)))
+    }
+
+    pub fn add_int64(self, number: u64, value: i64) -> Self {
+        self.add_field(Box::new(Int64Field::new("".to_string(), number, value)))
+    }
+
+    pub fn add_uint32(self, number: u64, value: u32) -> Self {
+        self.add_field(Box::new(UInt32Field::new("".to_string(), number, value)))
+    }
+
+    pub fn add_uint64(self, number: u64, value: u64) -> Self {
+        self.add_field(Box::new(UInt64Field::new("".to_string(), number, value)))
+    }
+
+    pub fn add_sint32(self, number: u64, value: i32) -> Self {
+        self.add_field(Box::new(SInt32Field::new("".to_string(), number, value)))
+    }
+
+    pub fn add_sint64(self, number: u64, value: i64) -> Self {
+        self.add_field(Box::new(SInt64Field::new("".to_string(), number, value)))
+    }
+
+    pub fn add_bool(self, number: u64, value: bool) -> Self {
+        self.add_field(Box::new(BoolField::new("".to_string(), number, value)))
+    }
+
+    pub fn add_fixed32(self, number: u64, value: i32) -> Self {
+        self.add_field(Box::new(Fixed32Field::new("".to_string(), number, value)))
+    }
+
+    pub fn add_sfixed32(self, number: u64, value: i32) -> Self {
+        self.add_field(Box::new(SFixed32Field::new("".to_string(), number, value)))
+    }
+
+    pub fn add_float(self, number: u64, value: f32) -> Self {
+        self.add_field(Box::new(FloatField::new("".to_string(), number, value)))
+    }
+
+    pub fn add_fixed64(self, number: u64, value: i64) -> Self {
+        self.add_field(Box::new(Fixed64Field::new("".to_string(), number, value)))
+    }
+
+    pub fn add_sfixed64(self, number: u64, value: i64) -> Self {
+        self.add_field(Box::new(SFixed64Field::new("".to_string(), number, value)))
+    }
+
+    pub fn add_double(self, number: u64, value: f64) -> Self {
+        self.add_field(Box::new(DoubleField::new("".to_string(), number, value)))
+    }
+
+    pub fn add_string(self, number: u64, value: &str) -> Self {
+        self.add_field(Box::new(StringField::new(
+            "".to_string(),
+            number,
+            value.to_string(),
+        )))
+    }
+
+    pub fn add_bytes(self, number: u64, value: &[u8]) -> Self {
+        self.add_field(Box::new(BytesField::new("".to_string(), number, value)))
+    }
+
+    pub fn add_embedded(self, number: u64, value: Message) -> Self {
+        let fields = FieldsVector {
+            fields: value.fields,
+        };
+        self.add_field(Box::new(EmbeddedField::new(
+            value.name.to_lowercase(),
+            number,
+            fields,
+        )))
+    }
+
+    /// Build the message, failing on a field number `check_field_number` rejects
+    pub fn build(self) -> Result<Message> {
         for field in self.fields.iter() {
-            writeln!(f, "{}", field.repr())?;
+            check_field_number(field.number())?;
         }
+        Ok(Message::new(self.name, Some(self.fields)).with_syntax(self.syntax))
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod test {
+    use crate::parser::parser::{FullParser, Parser};
+    use crate::proto::field::FieldExt;
+
+    #[test]
+    fn test_to_proto_repeated() {
+        // field 1 = 1, 2, 3 (unpacked)
+        let buffer = [0x08, 0x01, 0x08, 0x02, 0x08, 0x03];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+
+        assert_eq!(
+            message.to_proto(),
+            "message Generated {\n\trepeated int32 param0 = 1; // Example: [1, 2, 3] // unpacked\n}"
+        );
+    }
+
+    #[test]
+    fn test_reencode_matches() {
+        // field 1 = 150, field 2 = "abc"
+        let buffer = [0x08, 0x96, 0x01, 0x12, 0x03, 0x61, 0x62, 0x63];
+        let mut message = FullParser::new().deserialize(&buffer).unwrap();
+        assert!(message.reencode_matches(&buffer));
+
+        message.fields.swap(0, 1);
+        assert!(!message.reencode_matches(&buffer));
+    }
+
+    #[test]
+    fn test_serialize_sorted() {
+        // field 3 = 3, field 1 = 10, field 2 = 2, field 1 = 11
+        let buffer = [0x18, 0x03, 0x08, 0x0a, 0x10, 0x02, 0x08, 0x0b];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+
+        assert_eq!(message.serialize(), buffer);
+        assert_eq!(
+            message.serialize_sorted(),
+            [0x08, 0x0a, 0x08, 0x0b, 0x10, 0x02, 0x18, 0x03]
+        );
+    }
+
+    #[test]
+    fn test_parsers_share_field_module() {
+        use crate::parser::parser::{PartialParser, SimpleParser};
+        use crate::proto::field::{FieldType, Int32Field, StringField};
+
+        // field 1 = 150, field 2 = "abc"
+        let buffer = [0x08, 0x96, 0x01, 0x12, 0x03, 0x61, 0x62, 0x63];
+        let simple = SimpleParser::new().deserialize(&buffer).unwrap();
+        let mut full = FullParser::new().deserialize(&buffer).unwrap();
+        let (_, _, partial) = PartialParser::new().best_parse(&buffer).unwrap();
+
+        for message in [&simple, &full, &partial].iter() {
+            assert_eq!(message.to_proto(), full.to_proto());
+            assert!(message.reencode_matches(&buffer));
+        }
+        assert_eq!(full.fields[1].field_type(), FieldType::String);
+        assert!(full.fields[0]
+            .as_any()
+            .downcast_mut::<Int32Field>()
+            .is_some());
+        assert!(full.fields[1]
+            .as_any()
+            .downcast_mut::<StringField>()
+            .is_some());
+    }
+
+    #[test]
+    fn test_to_protoscope() {
+        // field 1 = 150
+        let message = FullParser::new().deserialize(&[0x08, 0x96, 0x01]).unwrap();
+        assert_eq!(message.to_protoscope(), "1: 150");
+
+        // field 2 = "text", field 3 = {1: 1}, field 4 = 01 FF
+        let buffer = [
+            0x12, 0x04, 0x74, 0x65, 0x78, 0x74, 0x1a, 0x02, 0x08, 0x01, 0x22, 0x02, 0x01, 0xff,
+        ];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+        assert_eq!(
+            message.to_protoscope(),
+            "2: {\"text\"}\n3: {\n  1: 1\n}\n4: {`01ff`}"
+        );
+    }
+
+    #[test]
+    fn test_syntax() {
+        use crate::proto::message::Syntax;
+
+        // field 1 = 150, field 2 = {1: 1}
+        let buffer = [0x08, 0x96, 0x01, 0x12, 0x02, 0x08, 0x01];
+        let proto2 = FullParser::new()
+            .with_syntax(Syntax::Proto2)
+            .deserialize(&buffer)
+            .unwrap();
+        let proto3 = FullParser::new()
+            .with_syntax(Syntax::Proto3)
+            .deserialize(&buffer)
+            .unwrap();
+
+        assert_eq!(
+            proto2.to_proto_file(),
+            "syntax = \"proto2\";\n\nmessage Generated {\n\toptional int32 param0 = 1; // Example: 150 // int32: 150, uint32: 150, sint32: 75\n\tmessage Param1 {\n\t\toptional int32 param0 = 1; // Example: 1 // int32: 1, uint32: 1, sint32: -1\n\t}\n\toptional Param1 param1 = 2; // 2 bytes\n}"
+        );
+        assert_eq!(
+            proto3.to_proto_file(),
+            "syntax = \"proto3\";\n\nmessage Generated {\n\tint32 param0 = 1; // Example: 150 // int32: 150, uint32: 150, sint32: 75\n\tmessage Param1 {\n\t\tint32 param0 = 1; // Example: 1 // int32: 1, uint32: 1, sint32: -1\n\t}\n\tParam1 param1 = 2; // 2 bytes\n}"
+        );
+    }
+
+    #[test]
+    fn test_repr_with() {
+        use crate::proto::field::{Radix, RenderOptions};
+
+        // field 1 = 150
+        let message = FullParser::new().deserialize(&[0x08, 0x96, 0x01]).unwrap();
+        assert_eq!(
+            format!("{:?}", message),
+            message.repr_with(&RenderOptions::default())
+        );
+
+        let options = RenderOptions {
+            radix: Some(Radix::Dec),
+            ..RenderOptions::default()
+        };
+        let repr = message.repr_with(&options);
+        assert!(repr.starts_with("Message\n"));
+        assert!(repr.contains("> = 150 //"));
+    }
+
+    #[test]
+    fn test_to_proto_group() {
+        use crate::proto::message::Syntax;
+
+        // field 1 = group {2: 1}
+        let message = FullParser::new()
+            .deserialize(&[0x0b, 0x10, 0x01, 0x0c])
+            .unwrap();
+        assert_eq!(
+            message.to_proto(),
+            "message Generated {\n\tgroup Param0 = 1 {\n\t\tint32 param0 = 2; // Example: 1 // int32: 1, uint32: 1, sint32: -1\n\t}\n}"
+        );
+
+        // Groups only exist in proto2, which requires the label
+        let message = message.with_syntax(Syntax::Proto2);
+        assert_eq!(
+            message.to_proto(),
+            "message Generated {\n\toptional group Param0 = 1 {\n\t\toptional int32 param0 = 2; // Example: 1 // int32: 1, uint32: 1, sint32: -1\n\t}\n}"
+        );
+    }
+
+    #[test]
+    fn test_to_proto_indent() {
+        use crate::proto::field::RenderOptions;
+
+        // field 1 = {1: {1: 1}}
+        let buffer = [0x0a, 0x04, 0x0a, 0x02, 0x08, 0x01];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+        let options = RenderOptions {
+            indent: "  ".to_string(),
+            ..RenderOptions::default()
+        };
+
+        let proto = message.to_proto_with(&options);
+        let lines: Vec<&str> = proto.split('\n').collect();
+        assert_eq!(lines[1], format!("{}message Param0 {{", options.indent));
+        assert_eq!(
+            lines[2],
+            format!("{}message Param0 {{", options.indent.repeat(2))
+        );
+        assert_eq!(
+            lines[3],
+            format!(
+                "{}int32 param0 = 1; // Example: 1 // int32: 1, uint32: 1, sint32: -1",
+                options.indent.repeat(3)
+            )
+        );
+        assert_eq!(
+            lines[5],
+            format!("{}Param0 param0 = 1; // 2 bytes", options.indent.repeat(2))
+        );
+        assert_eq!(
+            lines[7],
+            format!("{}Param0 param0 = 1; // 4 bytes", options.indent)
+        );
+        assert!(!proto.contains('\t'));
+    }
+
+    #[test]
+    fn test_retype_field() {
+        use crate::proto::field::{DecodeOptions, FieldLabel, FieldType, StringMode};
+        use crate::proto::message::MessageBuilder;
+
+        let options = DecodeOptions::default();
+        let mut message = MessageBuilder::new("Generated")
+            .add_fixed32(1, 1.5f32.to_bits() as i32)
+            .add_string(2, "abc")
+            .add_bytes(3, "é".as_bytes())
+            .build()
+            .unwrap();
+        let before = message.to_proto();
+        assert!(before.contains("fixed32 param0 = 1; // Example: 1069547520"));
+
+        message.fields[0].set_name("ratio");
+        message.fields[0].set_rule(FieldLabel::Required);
+        message.retype_field(1, FieldType::Float, &options).unwrap();
+        assert_eq!(message.fields[0].field_type(), FieldType::Float);
+        assert_eq!(message.fields[0].name(), "ratio");
+        assert_eq!(message.fields[0].rule(), FieldLabel::Required);
+        assert!(message
+            .to_proto()
+            .contains("float ratio = 1; // Example: 1.5"));
+
+        // Wire type mismatch, unknown field and embedded messages fail unchanged
+        assert!(message
+            .retype_field(2, FieldType::Double, &options)
+            .is_err());
+        assert!(message.retype_field(4, FieldType::Float, &options).is_err());
+        assert!(message
+            .retype_field(2, FieldType::Embedded, &options)
+            .is_err());
+        assert_eq!(message.fields[1].field_type(), FieldType::String);
+
+        // Decode options apply, UTF-8 is not a string by default
+        assert!(message
+            .retype_field(3, FieldType::String, &options)
+            .is_err());
+        let utf8 = DecodeOptions {
+            string_mode: StringMode::Utf8,
+            ..options
+        };
+        message.retype_field(3, FieldType::String, &utf8).unwrap();
+        assert_eq!(message.fields[2].field_type(), FieldType::String);
+    }
+
+    #[test]
+    fn test_find_strings() {
+        use crate::proto::message::MessageBuilder;
+
+        let nested = MessageBuilder::new("Nested")
+            .add_string(3, "world")
+            .add_bytes(4, b"ab")
+            .add_bytes(5, &[0x00, 0x01, 0x02])
+            .build()
+            .unwrap();
+        let message = MessageBuilder::new("Generated")
+            .add_string(1, "hello")
+            .add_embedded(2, nested)
+            .add_bytes(6, b"printable")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            message.find_strings(3),
+            vec![
+                (1, "hello".to_string()),
+                (3, "world".to_string()),
+                (6, "printable".to_string())
+            ]
+        );
+        assert_eq!(message.find_strings(0).len(), 4);
+    }
+
+    #[test]
+    fn test_collapse_repeated() {
+        use crate::proto::field::{FieldExt, FieldLabel};
+
+        // field 1 = 1, 2, 3 (unpacked), field 2 = 4
+        let buffer = [0x08, 0x01, 0x08, 0x02, 0x08, 0x03, 0x10, 0x04];
+        let mut message = FullParser::new().deserialize(&buffer).unwrap();
+        message.collapse_repeated();
+
+        for field in message.fields[..3].iter_mut() {
+            assert_eq!(field.as_int32().unwrap().0.rule, FieldLabel::Repeated);
+        }
+        assert_eq!(
+            message.fields[3].as_int32().unwrap().0.rule,
+            FieldLabel::Optional
+        );
+        assert_eq!(
+            message.to_proto(),
+            "message Generated {\n\trepeated int32 param0 = 1; // Example: [1, 2, 3] // unpacked\n\tint32 param1 = 2; // Example: 4 // int32: 4, uint32: 4, sint32: 2\n}"
+        );
+
+        // field 1 = {1: 1}, field 1 = {1: 2}
+        let buffer = [0x0a, 0x02, 0x08, 0x01, 0x0a, 0x02, 0x08, 0x02];
+        let mut message = FullParser::new().deserialize(&buffer).unwrap();
+        message.collapse_repeated();
+        assert_eq!(
+            message.to_proto(),
+            "message Generated {\n\tmessage Param0 {\n\t\tint32 param0 = 1; // Example: 1 // int32: 1, uint32: 1, sint32: -1\n\t}\n\trepeated Param0 param0 = 1;\n}"
+        );
+    }
+
+    #[test]
+    fn test_to_proto_examples() {
+        use crate::proto::field::{FieldType, RenderOptions};
+
+        // field 1 = 00 01 .. 63 (100 bytes)
+        let mut buffer = vec![0x0a, 100];
+        buffer.extend(0..100u8);
+        let message = FullParser::new()
+            .with_order(&[FieldType::Bytes])
+            .deserialize(&buffer)
+            .unwrap();
+
+        let first_32: Vec<String> = (0..32u8).map(|x| format!("{:02X}", x)).collect();
+        assert_eq!(
+            message.to_proto(),
+            format!(
+                "message Generated {{\n\tbytes param0 = 1; // Example: {} ...\n}}",
+                first_32.join(" ")
+            )
+        );
+        let options = RenderOptions {
+            max_example_bytes: 2,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            message.to_proto_with(&options),
+            "message Generated {\n\tbytes param0 = 1; // Example: 00 01 ...\n}"
+        );
+
+        // field 1 = 01, field 1 = 02 03 04
+        let buffer = [0x0a, 0x01, 0x01, 0x0a, 0x03, 0x02, 0x03, 0x04];
+        let message = FullParser::new()
+            .with_order(&[FieldType::Bytes])
+            .deserialize(&buffer)
+            .unwrap();
+        assert_eq!(
+            message.to_proto_with(&options),
+            "message Generated {\n\trepeated bytes param0 = 1; // Example: [01, 02 03 ...]\n}"
+        );
+    }
+
+    #[test]
+    fn test_encoded_len() {
+        // field 3 = {1: "a", 2: 1}, field 3 = {1: "b", 2: 2}, field 4 = {1: 150}
+        let buffer = [
+            0x1a, 0x05, 0x0a, 0x01, 0x61, 0x10, 0x01, 0x1a, 0x05, 0x0a, 0x01, 0x62, 0x10, 0x02,
+            0x22, 0x03, 0x08, 0x96, 0x01,
+        ];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+
+        assert_eq!(message.encoded_len(), buffer.len());
+        assert_eq!(message.encoded_len(), message.serialize().len());
+    }
+
+    #[test]
+    fn test_message_builder() {
+        use crate::proto::message::MessageBuilder;
+
+        let nested = MessageBuilder::new("Nested")
+            .add_int32(1, 1)
+            .build()
+            .unwrap();
+        let message = MessageBuilder::new("Generated")
+            .add_int32(1, 150)
+            .add_string(2, "abc")
+            .add_embedded(3, nested)
+            .add_double(4, 2.5)
+            .add_bytes(5, &[0x01, 0xff])
+            .build()
+            .unwrap();
+        let buffer = message.serialize();
+
+        assert_eq!(
+            buffer[..8],
+            [0x08, 0x96, 0x01, 0x12, 0x03, 0x61, 0x62, 0x63]
+        );
+        assert_eq!(message.encoded_len(), buffer.len());
+
+        let mut decoded = FullParser::new().deserialize(&buffer).unwrap();
+        // Builder names embedded fields after their message
+        decoded.fields[2].set_name("nested");
+        assert_eq!(decoded.to_proto(), message.to_proto());
+        assert_eq!(decoded.fields[0].as_int32().unwrap().0.data, 150);
+        assert_eq!(decoded.fields[1].as_string().unwrap().0.data, "abc");
+        let embedded = decoded.fields[2].as_embedded().unwrap();
+        assert_eq!(embedded.field.data.fields[0].number(), 1);
+        assert_eq!(decoded.fields[3].as_double().unwrap().0.data, 2.5);
+        assert_eq!(decoded.fields[4].as_bytes().unwrap().0.data, [0x01, 0xff]);
+        assert!(decoded.reencode_matches(&buffer));
+    }
+
+    #[test]
+    fn test_message_builder_field_number() {
+        use crate::proto::error::ErrorType;
+        use crate::proto::message::MessageBuilder;
+
+        for number in [0, 19000, 1 << 29, 1 << 61].iter() {
+            let error = MessageBuilder::new("Generated")
+                .add_int32(1, 1)
+                .add_int32(*number, 1)
+                .build()
+                .unwrap_err();
+            assert_eq!(
+                error.error_type(),
+                ErrorType::InvalidFieldNumber,
+                "{}",
+                number
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        use crate::proto::message::MessageBuilder;
+
+        let mut message = MessageBuilder::new("Generated")
+            .add_int32(1, 150)
+            .add_string(2, "abc")
+            .build()
+            .unwrap();
+        let other = MessageBuilder::new("Generated")
+            .add_int32(1, 150)
+            .add_int32(3, 1)
+            .build()
+            .unwrap();
+        message.merge(other);
+
+        assert_eq!(message.fields.len(), 3);
+        let numbers: Vec<u64> = message.fields.iter().map(|x| x.number()).collect();
+        assert_eq!(numbers, [1, 2, 3]);
+
+        // Same value of a different type is another field
+        message.merge(
+            MessageBuilder::new("Generated")
+                .add_sint32(1, 150)
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(message.fields.len(), 4);
+
+        // Values of a repeated tag are kept
+        let mut repeated = MessageBuilder::new("Generated")
+            .add_int32(4, 1)
+            .add_int32(4, 1)
+            .build()
+            .unwrap();
+        repeated.merge(
+            MessageBuilder::new("Generated")
+                .add_int32(4, 1)
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(repeated.fields.len(), 3);
+    }
+
+    #[test]
+    fn test_clone_eq() {
+        // field 1 = 150, field 2 = {1: 1, 2: "ab"}
+        let buffer = [
+            0x08, 0x96, 0x01, 0x12, 0x06, 0x08, 0x01, 0x12, 0x02, 0x61, 0x62,
+        ];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+
+        let mut copy = message.clone();
+        assert_eq!(copy, message);
+        assert_eq!(copy.serialize(), buffer);
+
+        copy.fields[1]
+            .as_embedded()
+            .unwrap()
+            .field
+            .data
+            .fields
+            .pop();
+        assert_ne!(copy, message);
+        assert_eq!(message.serialize(), buffer);
+    }
+
+    #[test]
+    fn test_into_builder() {
+        use crate::proto::field::StringField;
+        use crate::proto::message::Syntax;
+
+        // field 1 = 150, field 2 = "abc", field 3 = 1
+        let buffer = [0x08, 0x96, 0x01, 0x12, 0x03, 0x61, 0x62, 0x63, 0x18, 0x01];
+        let message = FullParser::new()
+            .with_syntax(Syntax::Proto2)
+            .deserialize(&buffer)
+            .unwrap();
+
+        let edited = message
+            .into_builder()
+            .replace_field(Box::new(StringField::new(
+                "".to_string(),
+                2,
+                "edited".to_string(),
+            )))
+            .remove_field(3)
+            .build()
+            .unwrap();
+        assert_eq!(edited.syntax, Syntax::Proto2);
+
+        let mut decoded = FullParser::new().deserialize(&edited.serialize()).unwrap();
+        assert_eq!(decoded.fields.len(), 2);
+        assert_eq!(decoded.fields[0].as_int32().unwrap().0.data, 150);
+        assert_eq!(decoded.fields[1].as_string().unwrap().0.data, "edited");
+    }
+
+    #[test]
+    fn test_depth() {
+        use crate::parser::parser::ParseStats;
+        use crate::proto::field::{
+            EmbeddedField, FieldType, FieldsVector, Int32Field, MapEntry, MapField,
+        };
+        use crate::proto::message::MessageBuilder;
+
+        let flat = MessageBuilder::new("Flat")
+            .add_int32(1, 150)
+            .add_string(2, "abc")
+            .build()
+            .unwrap();
+        assert_eq!(flat.depth(), 1);
+
+        let inner = MessageBuilder::new("Inner")
+            .add_int32(1, 1)
+            .build()
+            .unwrap();
+        let middle = MessageBuilder::new("Middle")
+            .add_embedded(1, inner)
+            .build()
+            .unwrap();
+        let outer = MessageBuilder::new("Outer")
+            .add_int32(1, 150)
+            .add_embedded(2, middle)
+            .add_embedded(3, flat)
+            .build()
+            .unwrap();
+        assert_eq!(outer.depth(), 3);
+        assert_eq!(MessageBuilder::new("Empty").build().unwrap().depth(), 1);
+
+        // map<int32, Value> with Value = {1: 1}
+        let value = FieldsVector {
+            fields: vec![Box::new(Int32Field::new("".to_string(), 1, 1))],
+        };
+        let entry: MapEntry = (
+            Box::new(Int32Field::new("".to_string(), 1, 7)),
+            Box::new(EmbeddedField::new("".to_string(), 2, value)),
+        );
+        let map = MapField::new(1, FieldType::Int32, FieldType::Embedded, vec![entry]);
+        let message = MessageBuilder::new("Map")
+            .add_field(Box::new(map))
+            .build()
+            .unwrap();
+        assert_eq!(message.depth(), 2);
+        assert_eq!(ParseStats::of(&message).max_depth, 1);
     }
 }
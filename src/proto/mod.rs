@@ -1,4 +1,9 @@
+pub mod codec;
 pub mod error;
 pub mod field;
 pub mod message;
+pub mod protoscope;
+pub mod schema;
+#[cfg(feature = "serde")]
+mod serde_impl;
 pub mod utils;
@@ -0,0 +1,242 @@
+use crate::proto::error::{Error, ErrorType, Result};
+use crate::proto::message::{Message, MessageBuilder};
+use crate::proto::utils::check_field_number;
+
+/// Cursor over protoscope text
+struct Reader<'a> {
+    text: &'a str,
+    index: usize,
+}
+
+fn parse_error(message: &str) -> Error {
+    Error::new(message, Some(ErrorType::ParserError))
+}
+
+impl<'a> Reader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.index..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.index += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.rest().chars().next()
+    }
+
+    fn expect(&mut self, token: char) -> Result<()> {
+        match self.peek() {
+            Some(x) if x == token => {
+                self.index += x.len_utf8();
+                Ok(())
+            }
+            _ => Err(parse_error(&format!(
+                "expected `{}` at offset {}",
+                token, self.index
+            ))),
+        }
+    }
+
+    /// Read a run of characters other than whitespace and brackets
+    fn word(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len = rest
+            .find(|x: char| x.is_whitespace() || "{}:".contains(x))
+            .unwrap_or(rest.len());
+        self.index += len;
+        &rest[..len]
+    }
+
+    /// Read fields until the end of text or a closing bracket
+    fn fields(&mut self, name: &str) -> Result<Message> {
+        let mut builder = MessageBuilder::new(name);
+        while !matches!(self.peek(), None | Some('}')) {
+            let word = self.word();
+            let number = word
+                .parse::<u64>()
+                .map_err(|_| parse_error(&format!("invalid field number `{}`", word)))?;
+            check_field_number(number)?;
+            self.expect(':')?;
+            builder = match self.peek() {
+                Some('{') => self.delimited(builder, number)?,
+                _ => {
+                    let word = self.word();
+                    scalar(builder, number, word)?
+                }
+            };
+        }
+        builder.build()
+    }
+
+    /// Read `{...}` value: string, bytes or embedded message
+    fn delimited(&mut self, builder: MessageBuilder, number: u64) -> Result<MessageBuilder> {
+        self.expect('{')?;
+        let builder = match self.peek() {
+            Some('"') => builder.add_string(number, &self.quoted('"')?),
+            Some('`') => builder.add_bytes(number, &decode_hex(&self.quoted('`')?)?),
+            _ => builder.add_embedded(number, self.fields("Embedded")?),
+        };
+        self.expect('}')?;
+        Ok(builder)
+    }
+
+    /// Read text between `quote`s, `\` escapes the next character
+    fn quoted(&mut self, quote: char) -> Result<String> {
+        self.expect(quote)?;
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, x)) = chars.next() {
+            match x {
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, x)) => value.push(x),
+                    None => break,
+                },
+                x if x == quote => {
+                    self.index += i + x.len_utf8();
+                    return Ok(value);
+                }
+                x => value.push(x),
+            }
+        }
+        Err(parse_error(&format!(
+            "unterminated {}...{} value",
+            quote, quote
+        )))
+    }
+}
+
+fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    let digits: Vec<char> = text.chars().filter(|x| !x.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(parse_error(&format!(
+            "odd number of hex digits in `{}`",
+            text
+        )));
+    }
+    digits
+        .chunks(2)
+        .map(|x| {
+            let byte: String = x.iter().collect();
+            u8::from_str_radix(&byte, 16)
+                .map_err(|_| parse_error(&format!("invalid hex byte `{}`", byte)))
+        })
+        .collect()
+}
+
+/// Integer literal as the bits of a two's complement value, `-1` and
+/// `18446744073709551615` are the same 64 bits
+fn parse_bits(word: &str) -> Option<u64> {
+    word.parse::<u64>()
+        .ok()
+        .or_else(|| word.parse::<i64>().ok().map(|x| x as u64))
+}
+
+fn scalar(builder: MessageBuilder, number: u64, word: &str) -> Result<MessageBuilder> {
+    let invalid = || parse_error(&format!("invalid value `{}` of field {}", word, number));
+    let is_float = |x: &str| x.contains('.') || x.contains("inf") || x.contains("nan");
+
+    let builder = if let Some(value) = word.strip_suffix("i32") {
+        match is_float(value) {
+            true => builder.add_float(number, value.parse().map_err(|_| invalid())?),
+            false => {
+                let bits = parse_bits(value).ok_or_else(invalid)?;
+                if bits > u32::MAX as u64 && !(i32::MIN as i64..0).contains(&(bits as i64)) {
+                    return Err(invalid());
+                }
+                builder.add_fixed32(number, bits as i32)
+            }
+        }
+    } else if let Some(value) = word.strip_suffix("i64") {
+        match is_float(value) {
+            true => builder.add_double(number, value.parse().map_err(|_| invalid())?),
+            false => builder.add_fixed64(number, parse_bits(value).ok_or_else(invalid)? as i64),
+        }
+    } else if let Some(value) = word.strip_suffix('z') {
+        builder.add_sint64(number, value.parse().map_err(|_| invalid())?)
+    } else if is_float(word) {
+        builder.add_double(number, word.parse().map_err(|_| invalid())?)
+    } else {
+        match word {
+            "true" => builder.add_bool(number, true),
+            "false" => builder.add_bool(number, false),
+            _ => builder.add_uint64(number, parse_bits(word).ok_or_else(invalid)?),
+        }
+    };
+    Ok(builder)
+}
+
+/// Build a message from protoscope text
+///
+/// Every field is `number: value`, where value is one of:
+/// - `150`, `-1` - varint (`uint64`, negative numbers as `int64`)
+/// - `-3z` - zigzag varint (`sint64`)
+/// - `true`, `false` - varint (`bool`)
+/// - `5i32`, `1.5i32` - 32-bit fixed (`fixed32`, `float`)
+/// - `5i64`, `1.5i64`, `1.5` - 64-bit fixed (`fixed64`, `double`)
+/// - `{"text"}` - string
+/// - ``{`01ff`}`` - bytes
+/// - `{1: 150 2: {"a"}}` - embedded message
+///
+/// This is the subset produced by `Message::to_protoscope`, groups are not
+/// supported.
+pub fn parse(text: &str) -> Result<Message> {
+    let mut reader = Reader { text, index: 0 };
+    let message = reader.fields("Generated")?;
+    match reader.peek() {
+        None => Ok(message),
+        Some(_) => Err(parse_error(&format!(
+            "unexpected `}}` at offset {}",
+            reader.index
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parser::{FullParser, Parser};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("1: 150").unwrap().serialize(), [0x08, 0x96, 0x01]);
+        assert_eq!(
+            parse("1: -1").unwrap().serialize(),
+            [0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]
+        );
+        assert_eq!(parse("1: -1z").unwrap().serialize(), [0x08, 0x01]);
+        assert_eq!(
+            parse("1: 1i32 2: 1.0i32").unwrap().serialize(),
+            [0x0d, 0x01, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x80, 0x3f]
+        );
+        assert_eq!(
+            parse("2: {\"a\\\"\"}\n3: {`01FF`}").unwrap().serialize(),
+            [0x12, 0x02, 0x61, 0x22, 0x1a, 0x02, 0x01, 0xff]
+        );
+
+        assert!(parse("1: abc").is_err());
+        assert!(parse("1: {1: 1").is_err());
+        assert!(parse("1: 1}").is_err());
+        assert!(parse("1: 5000000000i32").is_err());
+        assert!(parse("536870911: 1").is_ok());
+        assert!(parse("536870912: 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        // field 2 = "text", field 3 = {1: 1, 2: 2.5}, field 4 = 01 FF
+        let buffer = [
+            0x12, 0x04, 0x74, 0x65, 0x78, 0x74, 0x1a, 0x0b, 0x08, 0x01, 0x11, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x04, 0x40, 0x22, 0x02, 0x01, 0xff,
+        ];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+
+        let text = message.to_protoscope();
+        assert_eq!(parse(&text).unwrap().serialize(), buffer);
+    }
+}
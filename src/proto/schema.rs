@@ -0,0 +1,234 @@
+use crate::proto::error::{Error, ErrorType, Result};
+use crate::proto::field::{DecodeOptions, FieldTrait, FieldType, StringMode};
+use crate::proto::message::Message;
+
+/// Field declared in a schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaField {
+    /// Field name
+    pub name: String,
+    /// Declared type, unknown type names are treated as embedded messages
+    pub type_: FieldType,
+    /// Tag number
+    pub number: u64,
+}
+
+/// Minimal `.proto` schema: name and fields of the first top-level message
+///
+/// Only `[label] type name = number;` declarations are understood, options,
+/// nested messages, enums and other statements are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    /// Message name
+    pub name: String,
+    /// List of declared fields
+    pub fields: Vec<SchemaField>,
+}
+
+fn type_from_proto(name: &str) -> FieldType {
+    name.parse().unwrap_or(FieldType::Embedded)
+}
+
+/// Split schema text into identifiers and `{`, `}`, `=`, `;` tokens
+fn tokenize(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|x| x.split("//").next().unwrap_or_default())
+        .flat_map(|x| {
+            x.replace('{', " { ")
+                .replace('}', " } ")
+                .replace('=', " = ")
+                .replace(';', " ; ")
+                .split_whitespace()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>()
+        })
+        .collect()
+}
+
+impl Schema {
+    /// Parse schema from `.proto` text
+    pub fn parse(text: &str) -> Result<Schema> {
+        let tokens = tokenize(text);
+        let start = tokens
+            .iter()
+            .position(|x| x == "message")
+            .ok_or_else(|| Error::new("no message in schema", Some(ErrorType::ParserError)))?;
+        let name = match (tokens.get(start + 1), tokens.get(start + 2)) {
+            (Some(name), Some(open)) if open == "{" => name.clone(),
+            _ => {
+                return Err(Error::new(
+                    "expected `message <name> {` in schema",
+                    Some(ErrorType::ParserError),
+                ))
+            }
+        };
+
+        let mut fields = Vec::new();
+        let mut statement: Vec<&str> = Vec::new();
+        let mut depth = 0;
+        for token in tokens[start + 3..].iter() {
+            match token.as_str() {
+                "{" => depth += 1,
+                "}" if depth == 0 => return Ok(Schema { name, fields }),
+                "}" => {
+                    depth -= 1;
+                    statement.clear();
+                }
+                ";" if depth == 0 => {
+                    if let Some(field) = Schema::parse_field(&statement)? {
+                        fields.push(field);
+                    }
+                    statement.clear();
+                }
+                _ if depth == 0 => statement.push(token),
+                _ => {}
+            }
+        }
+
+        Err(Error::new(
+            &format!("message `{}` is not closed in schema", name),
+            Some(ErrorType::ParserError),
+        ))
+    }
+
+    /// Parse `[label] type name = number` statement, others are skipped
+    fn parse_field(statement: &[&str]) -> Result<Option<SchemaField>> {
+        let statement = match statement.first() {
+            Some(&"optional") | Some(&"required") | Some(&"repeated") => &statement[1..],
+            _ => statement,
+        };
+        match statement {
+            [type_, name, "=", number, ..] if !["option", "reserved"].contains(type_) => {
+                let number = number.parse::<u64>().map_err(|_| {
+                    Error::new(
+                        &format!("invalid number `{}` of field `{}` in schema", number, name),
+                        Some(ErrorType::ParserError),
+                    )
+                })?;
+                Ok(Some(SchemaField {
+                    name: name.to_string(),
+                    type_: type_from_proto(type_),
+                    number,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Declared field with tag `number`
+    pub fn field(&self, number: u64) -> Option<&SchemaField> {
+        self.fields.iter().find(|x| x.number == number)
+    }
+
+    /// Rename and re-type fields of a heuristically decoded message
+    ///
+    /// A field decoded as another type is decoded again as the declared one.
+    /// When that fails the decoded field is kept and a warning is returned.
+    pub fn apply(&self, message: &mut Message) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let options = DecodeOptions {
+            string_mode: StringMode::Utf8,
+            ..DecodeOptions::default()
+        };
+        message.name = self.name.clone();
+
+        for field in message.fields.iter_mut() {
+            let declared = match self.field(field.number()) {
+                Some(declared) => declared,
+                None => continue,
+            };
+            if field.field_type() != declared.type_ {
+                if declared.type_ == FieldType::Embedded {
+                    warnings.push(format!(
+                        "field {}: schema says message but decoded as {}",
+                        declared.number,
+                        field.field_type().proto_type()
+                    ));
+                    continue;
+                }
+
+                let mut retyped: Box<dyn FieldTrait> = declared.type_.into();
+                match retyped.deserialize_with(&field.serialize(), &options) {
+                    Ok(_) => *field = retyped,
+                    Err(e) => {
+                        warnings.push(format!(
+                            "field {}: schema says {} but decoded as {} ({})",
+                            declared.number,
+                            declared.type_.proto_type(),
+                            field.field_type().proto_type(),
+                            e
+                        ));
+                        continue;
+                    }
+                }
+            }
+            field.set_name(&declared.name);
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parser::{FullParser, Parser};
+    use crate::proto::field::{Int32Field, SInt32Field};
+
+    const SCHEMA: &str = "syntax = \"proto3\";
+
+message User {
+    int32 user_id = 1; // primary key
+    optional string name = 2;
+    sint32 delta = 3 [deprecated = true];
+    message Nested {
+        int32 x = 1;
+    }
+}";
+
+    #[test]
+    fn test_parse() {
+        let schema = Schema::parse(SCHEMA).unwrap();
+
+        assert_eq!(schema.name, "User");
+        assert_eq!(
+            schema.field(1),
+            Some(&SchemaField {
+                name: "user_id".to_string(),
+                type_: FieldType::Int32,
+                number: 1,
+            })
+        );
+        assert_eq!(schema.fields.len(), 3);
+        assert!(Schema::parse("message User { int32 a = 1;").is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        // field 1 = 150, field 2 = FF FE, field 3 = 3
+        let buffer = [0x08, 0x96, 0x01, 0x12, 0x02, 0xff, 0xfe, 0x18, 0x03];
+        let mut message = FullParser::new().deserialize(&buffer).unwrap();
+        let schema = Schema::parse(SCHEMA).unwrap();
+
+        let warnings = schema.apply(&mut message);
+
+        assert_eq!(message.name, "User");
+        let user_id = message.fields[0]
+            .as_any()
+            .downcast_mut::<Int32Field>()
+            .unwrap();
+        assert_eq!(user_id.0.name, "user_id");
+        assert_eq!(user_id.0.data, 150);
+
+        assert_eq!(message.fields[1].field_type(), FieldType::Bytes);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("field 2: schema says string but decoded as bytes"));
+
+        let delta = message.fields[2]
+            .as_any()
+            .downcast_mut::<SInt32Field>()
+            .unwrap();
+        assert_eq!(delta.0.name, "delta");
+        assert_eq!(delta.0.data, -2);
+    }
+}
@@ -0,0 +1,149 @@
+use serde::ser::{Serialize, SerializeSeq, SerializeStruct, Serializer};
+
+use crate::proto::field::*;
+use crate::proto::message::Message;
+
+/// Decoded value of a field: numbers, booleans and strings as themselves,
+/// bytes as a byte sequence, embedded messages as their fields and maps as a
+/// sequence of key/value entries
+struct Value<'a>(&'a dyn FieldTrait);
+
+/// Sequence of fields
+struct Fields<'a>(&'a [Box<dyn FieldTrait>]);
+
+/// Entries of a map field
+struct Entries<'a>(&'a [MapEntry]);
+
+fn downcast<T: 'static>(field: &dyn FieldTrait) -> Option<&T> {
+    field.as_any_ref().downcast_ref::<T>()
+}
+
+impl<'a> Serialize for Value<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let field = self.0;
+        if let Some(x) = downcast::<Int32Field>(field) {
+            return serializer.serialize_i32(x.0.data);
+        }
+        if let Some(x) = downcast::<Int64Field>(field) {
+            return serializer.serialize_i64(x.0.data);
+        }
+        if let Some(x) = downcast::<UInt32Field>(field) {
+            return serializer.serialize_u32(x.0.data);
+        }
+        if let Some(x) = downcast::<UInt64Field>(field) {
+            return serializer.serialize_u64(x.0.data);
+        }
+        if let Some(x) = downcast::<SInt32Field>(field) {
+            return serializer.serialize_i32(x.0.data);
+        }
+        if let Some(x) = downcast::<SInt64Field>(field) {
+            return serializer.serialize_i64(x.0.data);
+        }
+        if let Some(x) = downcast::<BoolField>(field) {
+            return serializer.serialize_bool(x.0.data);
+        }
+        if let Some(x) = downcast::<Fixed32Field>(field) {
+            return serializer.serialize_i32(x.0.data);
+        }
+        if let Some(x) = downcast::<SFixed32Field>(field) {
+            return serializer.serialize_i32(x.0.data);
+        }
+        if let Some(x) = downcast::<FloatField>(field) {
+            return serializer.serialize_f32(x.0.data);
+        }
+        if let Some(x) = downcast::<Fixed64Field>(field) {
+            return serializer.serialize_i64(x.0.data);
+        }
+        if let Some(x) = downcast::<SFixed64Field>(field) {
+            return serializer.serialize_i64(x.0.data);
+        }
+        if let Some(x) = downcast::<DoubleField>(field) {
+            return serializer.serialize_f64(x.0.data);
+        }
+        if let Some(x) = downcast::<StringField>(field) {
+            return serializer.serialize_str(&x.0.data);
+        }
+        if let Some(x) = downcast::<BytesField>(field) {
+            return serializer.serialize_bytes(&x.0.data);
+        }
+        if let Some(x) = downcast::<EmbeddedField>(field) {
+            return Fields(&x.field.data.fields).serialize(serializer);
+        }
+        if let Some(x) = downcast::<MapField>(field) {
+            return Entries(&x.field.data).serialize(serializer);
+        }
+        serializer.serialize_str(&field.example())
+    }
+}
+
+impl<'a> Serialize for Fields<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for field in self.0.iter() {
+            seq.serialize_element(field.as_ref())?;
+        }
+        seq.end()
+    }
+}
+
+impl<'a> Serialize for Entries<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for (key, value) in self.0.iter() {
+            seq.serialize_element(&(Value(key.as_ref()), Value(value.as_ref())))?;
+        }
+        seq.end()
+    }
+}
+
+/// Field as `{"number": .., "type": .., "value": ..}`, the type is its `.proto`
+/// name
+impl Serialize for dyn FieldTrait {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Field", 3)?;
+        state.serialize_field("number", &self.number())?;
+        state.serialize_field("type", self.field_type().proto_type())?;
+        state.serialize_field("value", &Value(self))?;
+        state.end()
+    }
+}
+
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Message", 3)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("syntax", self.syntax.name())?;
+        state.serialize_field("fields", &Fields(&self.fields))?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::parser::{FullParser, Parser};
+
+    #[test]
+    fn test_serialize_json() {
+        // field 1 = 150, field 2 = {1: "ab"}, field 3 = 01 FF
+        let buffer = [
+            0x08, 0x96, 0x01, 0x12, 0x04, 0x0a, 0x02, 0x61, 0x62, 0x1a, 0x02, 0x01, 0xff,
+        ];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "name": "Generated",
+                "syntax": "proto3",
+                "fields": [
+                    {"number": 1, "type": "int32", "value": 150},
+                    {"number": 2, "type": "message", "value": [
+                        {"number": 1, "type": "string", "value": "ab"}
+                    ]},
+                    {"number": 3, "type": "bytes", "value": [1, 255]}
+                ]
+            })
+        );
+    }
+}
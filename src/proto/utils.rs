@@ -1,57 +1,23 @@
-use crate::proto::error::Result;
+use crate::proto::codec::{read_varint, write_varint};
+use crate::proto::error::{Error, ErrorType, Result};
+use crate::proto::field::VariantTypeRaw;
+
+pub use crate::proto::codec::{varint_len, MAX_VARINT_BYTES};
 
 /// Serialization using Varints method
 pub fn serialize_varint(var: u64) -> Vec<u8> {
     let mut gen = Vec::new();
-    serialize_varint_into(var, &mut gen);
+    write_varint(var, &mut gen);
 
     gen
 }
 
-/// Serialization using Varints method into Vec
-pub fn serialize_varint_into(var: u64, gen: &mut Vec<u8>) {
-    let mut x = var;
-    match x {
-        0 => gen.push(0),
-        _ => {
-            while x != 0 {
-                gen.push((((x & 0x7F) as u8) | (((x >> 7) != 0) as u8) << 0x7) as u8);
-                x = x >> 7;
-            }
-        }
-    }
-}
-
-/// Deserialization using Varints method
-///
-/// Returns (result, bytes readed)
-use std::ops::Add;
-pub fn deserialize_varint(gen: &[u8]) -> Result<(u64, u64)> {
-    let mut result: u64 = 0;
-    let mut readed: u64 = 0;
-    for (i, x) in gen.iter().enumerate() {
-        result |= ((x & 0x7F) as u64) << (i * 7);
-        if x >> 7 == 0 {
-            readed = (i + 1) as u64;
-            break;
-        }
-    }
-    log::trace!(
-        "VarInt: bytes {} -> <result {}[{}], {}[{}]>",
-        &gen[0..readed as usize]
-            .iter()
-            .fold(String::new(), |s, x| s.add(&format!("{:02x} ", x))),
-        result,
-        result,
-        readed,
-        readed,
-    );
-    Ok((result, readed))
-}
-
 /// Generate key using next alg: (field_number << 3) | wire_type
+///
+/// Field numbers are not checked, high bits of numbers above 2^61 are lost. Use
+/// `check_field_number` on numbers that don't come from parsed data.
 pub fn generate_key(field_number: u64, wire_type: u8) -> u64 {
-    ((field_number & 0x1FFFFFFFFFFFFFFF) << 3) | (wire_type as u64)
+    (field_number << 3) | (wire_type as u64)
 }
 
 /// Parse key using next alg: (field_number << 3) | wire_type
@@ -59,36 +25,150 @@ pub fn parse_key(key: u64) -> (u64, u8) {
     (key >> 3, (key & 0x7) as u8)
 }
 
-/// ZigZag Encoding for sint32
-pub fn encode_zigzag_s32(var: i32) -> u64 {
-    match (var as u32) >> 31 {
-        0 => (((var as u32) << 1) ^ ((var as u32) >> 31)) as u64,
-        _ => ((((var as u32) ^ 0x7FFFFFFF) << 1) ^ ((var as u32) >> 31)) as u64,
+/// Largest valid field number, 2^29 - 1
+pub const MAX_FIELD_NUMBER: u64 = (1 << 29) - 1;
+
+/// Field numbers reserved for the protobuf implementation
+pub const RESERVED_FIELD_NUMBERS: std::ops::RangeInclusive<u64> = 19000..=19999;
+
+/// Check that a parsed field number is a valid protobuf tag
+///
+/// Tags start at 1 and end at `MAX_FIELD_NUMBER`, tags in
+/// `RESERVED_FIELD_NUMBERS` can't appear in a message. Such numbers usually mean
+/// the data is misaligned.
+pub fn check_field_number(number: u64) -> Result<()> {
+    if number == 0 {
+        return Err(Error::new(
+            "field number 0 is not a valid tag",
+            Some(ErrorType::InvalidFieldNumber),
+        ));
     }
+    if RESERVED_FIELD_NUMBERS.contains(&number) {
+        return Err(Error::new(
+            &format!("field number {} is reserved", number),
+            Some(ErrorType::InvalidFieldNumber),
+        ));
+    }
+    if number > MAX_FIELD_NUMBER {
+        return Err(Error::new(
+            &format!(
+                "field number {} exceeds maximum {}",
+                number, MAX_FIELD_NUMBER
+            ),
+            Some(ErrorType::InvalidFieldNumber),
+        ));
+    }
+    Ok(())
 }
 
-/// ZigZag Decoding for sint32
-pub fn decode_zigzag_s32(var: u64) -> i32 {
-    match var & 0x1 {
-        0 => ((var << 31) ^ (var >> 1)) as i32,
-        _ => ((var << 31) ^ (var >> 1) ^ 0x7FFFFFFF) as i32,
+/// Skip one field starting at `into` whatever its type
+///
+/// The key is read and the payload is skipped according to the wire type, a
+/// group is skipped up to and including its matching end group.
+///
+/// Returns bytes skipped
+pub fn skip_field(into: &[u8]) -> Result<u64> {
+    let mut index: u64 = 0;
+    let mut groups: Vec<u64> = Vec::new();
+    loop {
+        let (key, key_len) = read_varint(&into[index as usize..])?;
+        let (number, wire_type) = parse_key(key);
+        check_field_number(number)?;
+        index += key_len;
+
+        let data = &into[index as usize..];
+        let len = match VariantTypeRaw::from(wire_type) {
+            VariantTypeRaw::Varint => read_varint(data)?.1,
+            VariantTypeRaw::Double => 8,
+            VariantTypeRaw::Float => 4,
+            VariantTypeRaw::Buffer => {
+                let (size, size_len) = read_varint(data)?;
+                let remaining = data.len() as u64 - size_len;
+                if size > remaining {
+                    return Err(Error::truncated(size, remaining));
+                }
+                size_len + size
+            }
+            VariantTypeRaw::StartGroup => {
+                groups.push(number);
+                0
+            }
+            VariantTypeRaw::EndGroup => match groups.pop() {
+                Some(x) if x == number => 0,
+                _ => {
+                    return Err(Error::new(
+                        &format!("unexpected end of group {}", number),
+                        Some(ErrorType::IncorrectData),
+                    ))
+                }
+            },
+            VariantTypeRaw::Undefined => {
+                return Err(Error::new(
+                    &format!("unknown wire type {}", wire_type),
+                    Some(ErrorType::IncorrectType),
+                ))
+            }
+        };
+        if len > data.len() as u64 {
+            return Err(Error::truncated(len, data.len() as u64));
+        }
+        index += len;
+
+        if groups.is_empty() {
+            return Ok(index);
+        }
     }
 }
 
-/// ZigZag Encoding for sint64
-pub fn encode_zigzag_s64(var: i64) -> u64 {
-    match (var as u64) >> 63 {
-        0 => ((var as u64) << 1) ^ ((var as u64) >> 63),
-        _ => (((var as u64) ^ 0x7FFFFFFFFFFFFFFF) << 1) ^ ((var as u64) >> 63),
+/// Parse hex bytes as pasted from debuggers, e.g. `089601`, `08 96 01` or
+/// `0x08,0x96,0x01`
+///
+/// Whitespace and commas separate groups of digits, each group may start with
+/// `0x`. A lone group is read two digits per byte. Separated groups are read
+/// each on its own, a group of odd length is padded with a leading zero, so
+/// `0x8,0x96,0x1` is `08 96 01`.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>> {
+    let groups: Vec<&str> = text
+        .split(|x: char| x.is_whitespace() || x == ',')
+        .filter(|x| !x.is_empty())
+        .map(|x| x.trim_start_matches("0x").trim_start_matches("0X"))
+        .collect();
+    if let Some(x) = groups
+        .iter()
+        .flat_map(|x| x.chars())
+        .find(|x| !x.is_ascii_hexdigit())
+    {
+        return Err(Error::new(
+            &format!("invalid hex digit {:?}", x),
+            Some(ErrorType::IncorrectData),
+        ));
+    }
+    if let [group] = groups.as_slice() {
+        if !group.len().is_multiple_of(2) {
+            return Err(Error::new(
+                "odd number of hex digits",
+                Some(ErrorType::IncorrectData),
+            ));
+        }
     }
-}
 
-/// ZigZag Decoding for sint64
-pub fn decode_zigzag_s64(var: u64) -> i64 {
-    match var & 0x1 {
-        0 => ((var << 63) ^ (var >> 1)) as i64,
-        _ => ((var << 63) ^ (var >> 1) ^ 0x7FFFFFFFFFFFFFFF) as i64,
+    let mut bytes = Vec::new();
+    for group in groups {
+        let digits = match group.len().is_multiple_of(2) {
+            true => group.to_string(),
+            false => format!("0{}", group),
+        };
+        for i in (0..digits.len()).step_by(2) {
+            let byte = u8::from_str_radix(&digits[i..i + 2], 16).map_err(|e| {
+                Error::new(
+                    &format!("invalid hex byte {:?}: {}", &digits[i..i + 2], e),
+                    Some(ErrorType::IncorrectData),
+                )
+            })?;
+            bytes.push(byte);
+        }
     }
+    Ok(bytes)
 }
 
 #[cfg(test)]
@@ -107,7 +187,80 @@ mod test {
     fn test_generate_key() {
         assert_eq!(generate_key(0, 0), 0);
         assert_eq!(generate_key(1234, 0), 9872);
-        assert_eq!(generate_key(0xFFFFFFFF, 3), 34359738363);
-        assert_eq!(generate_key(0xFFFFFFFFFF, 4), 8796093022204);
+        assert_eq!(generate_key(MAX_FIELD_NUMBER, 3), 0xFFFFFFFB);
+        assert_eq!(
+            parse_key(generate_key(MAX_FIELD_NUMBER, 4)),
+            (MAX_FIELD_NUMBER, 4)
+        );
+    }
+
+    #[test]
+    fn test_varint_len() {
+        for var in [0, 1, 127, 128, 150, 16383, 16384, u32::MAX as u64, u64::MAX].iter() {
+            assert_eq!(varint_len(*var), serialize_varint(*var).len(), "{}", var);
+        }
+    }
+
+    #[test]
+    fn test_check_field_number() {
+        assert!(check_field_number(0).is_err());
+        assert!(check_field_number(1).is_ok());
+        assert!(check_field_number(18999).is_ok());
+        assert!(check_field_number(19000).is_err());
+        assert!(check_field_number(19999).is_err());
+        assert!(check_field_number(20000).is_ok());
+        assert!(check_field_number(MAX_FIELD_NUMBER).is_ok());
+        assert!(check_field_number(1 << 29).is_err());
+    }
+
+    #[test]
+    fn test_skip_field() {
+        // field 1 = 150
+        assert_eq!(skip_field(&[0x08, 0x96, 0x01, 0x08]).unwrap(), 3);
+        // field 1 = 1i64
+        assert_eq!(skip_field(&[0x09, 1, 0, 0, 0, 0, 0, 0, 0]).unwrap(), 9);
+        // field 2 = "abc"
+        assert_eq!(skip_field(&[0x12, 0x03, 0x61, 0x62, 0x63]).unwrap(), 5);
+        // field 1 = 1i32
+        assert_eq!(skip_field(&[0x0d, 1, 0, 0, 0, 0x08]).unwrap(), 5);
+        // field 1 = group {2: 1, 3 = group {}}
+        assert_eq!(
+            skip_field(&[0x0b, 0x10, 0x01, 0x1b, 0x1c, 0x0c, 0x08, 0x01]).unwrap(),
+            6
+        );
+
+        assert!(skip_field(&[0x09, 1, 0, 0]).is_err());
+        assert!(skip_field(&[0x12, 0x05, 0x61]).is_err());
+        assert!(skip_field(&[0x0b, 0x10, 0x01]).is_err());
+        assert!(skip_field(&[0x0c]).is_err());
+        assert!(skip_field(&[0x0e, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        let expected = vec![0x08, 0x96, 0x01];
+        for text in [
+            "089601",
+            "08 96 01",
+            "08\t96\n01 ",
+            "08,96,01",
+            "08, 96, 01",
+            "0x08,0x96,0x01",
+            "0x08 0X96 0x01",
+            "0x089601",
+        ] {
+            assert_eq!(parse_hex(text).unwrap(), expected, "{:?}", text);
+        }
+        assert_eq!(parse_hex("").unwrap(), Vec::<u8>::new());
+        assert!(parse_hex("089").is_err());
+        assert!(parse_hex("08 zz").is_err());
+        assert!(parse_hex("+f").is_err());
+
+        // Separated groups are bytes of their own, odd ones padded
+        for text in ["0x8,0x96,0x1", "8 96 1", "0x8 0x9601"] {
+            assert_eq!(parse_hex(text).unwrap(), expected, "{:?}", text);
+        }
+        assert_eq!(parse_hex("08 9 6").unwrap(), [0x08, 0x09, 0x06]);
+        assert_eq!(parse_hex("0x896 01").unwrap(), [0x08, 0x96, 0x01]);
     }
 }
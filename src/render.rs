@@ -0,0 +1,361 @@
+use colored::Colorize;
+use std::io::IsTerminal;
+
+use crate::args::ColorMode;
+use protodec_rs::parser::analysis::field_hint;
+use protodec_rs::parser::parser::Span;
+use protodec_rs::proto::field::{EmbeddedField, FieldTrait, RenderOptions};
+
+/// Whether output should be colored, `Auto` colors a terminal unless `NO_COLOR`
+/// is set
+pub fn color_enabled(mode: ColorMode) -> bool {
+    match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+        }
+    }
+}
+
+/// Color one line of `repr` output: `<number> <rule> <type> = <value>`
+fn colorize_line(line: &str) -> String {
+    let indent = line.len() - line.trim_start_matches('\t').len();
+    let (prefix, line) = line.split_at(indent);
+    let (head, value) = match line.find(" = ") {
+        Some(i) => (&line[..i], &line[i + 3..]),
+        None => return format!("{}{}", prefix, line),
+    };
+    let (number_rule, type_) = match head.find('<') {
+        Some(i) => (&head[..i], &head[i..]),
+        None => (head, ""),
+    };
+    let (number, rule) = number_rule.split_at(number_rule.find(' ').unwrap_or(0));
+
+    format!(
+        "{}{}{}{} = {}",
+        prefix,
+        number.yellow(),
+        rule,
+        type_.cyan(),
+        value.green()
+    )
+}
+
+/// Render `repr` output of a field, colored when `color` is set
+pub fn render_repr(repr: &str, color: bool) -> String {
+    match color {
+        true => repr
+            .split('\n')
+            .map(colorize_line)
+            .collect::<Vec<String>>()
+            .join("\n"),
+        false => repr.to_string(),
+    }
+}
+
+/// Comments appended to the rendered line of each field
+#[derive(Debug, Default)]
+pub struct Notes<'a> {
+    /// Describe what values may mean, see `field_hint`
+    pub hints: bool,
+    /// Decoded input and spans of the rendered fields, to show the source bytes
+    /// of each field
+    pub raw: Option<(&'a [u8], &'a [Span])>,
+}
+
+impl<'a> Notes<'a> {
+    /// Spans of the top-level fields, empty unless raw bytes are shown
+    fn spans(&self) -> &'a [Span] {
+        self.raw.map_or(&[], |(_, spans)| spans)
+    }
+
+    /// Comment for the line of `field` decoded from `span`, if any
+    ///
+    /// Bytes of an embedded message are shown up to its first field, the
+    /// fields show their own bytes.
+    fn of(&self, field: &dyn FieldTrait, span: Option<&Span>) -> Option<String> {
+        let mut notes = Vec::new();
+        if let (Some((into, _)), Some(span)) = (self.raw, span) {
+            let end = match field.as_any_ref().downcast_ref::<EmbeddedField>() {
+                Some(_) => span.fields.first().map_or(span.end, |x| x.start),
+                None => span.end,
+            };
+            let hex: Vec<String> = into[span.start..end]
+                .iter()
+                .map(|x| format!("{:02X}", x))
+                .collect();
+            notes.push(hex.join(" "));
+        }
+        if self.hints {
+            notes.extend(field_hint(field));
+        }
+        match notes.is_empty() {
+            true => None,
+            false => Some(notes.join("; ")),
+        }
+    }
+}
+
+/// Summary of `count` fields left out by `render_fields`
+fn omitted_line(count: usize) -> String {
+    let noun = match count {
+        1 => "field",
+        _ => "fields",
+    };
+    format!("// ... ({} more {} omitted)", count, noun)
+}
+
+/// Render `repr_with` output of `field` with its note on its first line
+///
+/// Fields of embedded messages are rendered the same way, so each gets its own
+/// note. Every rendered field takes one from `budget`, fields of an embedded
+/// message past the budget are summarized. `budget` must not be 0.
+fn render_field(
+    field: &dyn FieldTrait,
+    span: Option<&Span>,
+    options: &RenderOptions,
+    color: bool,
+    notes: &Notes,
+    budget: &mut usize,
+) -> String {
+    *budget -= 1;
+    let repr = field.repr_with(options);
+    let (head, rest) = match repr.find('\n') {
+        Some(i) => repr.split_at(i),
+        None => (repr.as_str(), ""),
+    };
+    let mut rendered = render_repr(head, color);
+    if let Some(note) = notes.of(field, span) {
+        rendered.push_str(&format!(" // {}", note));
+    }
+    match field.as_any_ref().downcast_ref::<EmbeddedField>() {
+        Some(embedded) => {
+            let fields = &embedded.field.data.fields;
+            let spans = span.map_or(&[][..], |x| &x.fields);
+            for (i, x) in fields.iter().enumerate() {
+                rendered.push_str("\n\t");
+                if *budget == 0 {
+                    rendered.push_str(&omitted_line(count_fields(&fields[i..])));
+                    break;
+                }
+                rendered.push_str(&render_field(
+                    x.as_ref(),
+                    spans.get(i),
+                    options,
+                    color,
+                    notes,
+                    budget,
+                ));
+            }
+        }
+        None => rendered.push_str(&render_repr(rest, color)),
+    }
+    rendered
+}
+
+/// Number of fields including the fields of embedded messages
+fn count_fields(fields: &[Box<dyn FieldTrait>]) -> usize {
+    fields
+        .iter()
+        .map(|x| match x.as_any_ref().downcast_ref::<EmbeddedField>() {
+            Some(embedded) => 1 + count_fields(&embedded.field.data.fields),
+            None => 1,
+        })
+        .sum()
+}
+
+/// Render `repr` output of `fields`, stopping once `max_fields` fields,
+/// counting fields of embedded messages, are rendered
+///
+/// Skipped fields of each message are summarized in a trailing
+/// `// ... (N more fields omitted)`.
+pub fn render_fields(
+    fields: &[Box<dyn FieldTrait>],
+    options: &RenderOptions,
+    color: bool,
+    notes: &Notes,
+    max_fields: Option<usize>,
+) -> Vec<String> {
+    let mut budget = max_fields.unwrap_or(usize::MAX);
+    let mut lines = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        if budget == 0 {
+            lines.push(omitted_line(count_fields(&fields[i..])));
+            break;
+        }
+        lines.push(render_field(
+            field.as_ref(),
+            notes.spans().get(i),
+            options,
+            color,
+            notes,
+            &mut budget,
+        ));
+    }
+    lines
+}
+
+/// Whether `render_fields` leaves out some of `fields` with `max_fields`
+pub fn is_truncated(fields: &[Box<dyn FieldTrait>], max_fields: Option<usize>) -> bool {
+    max_fields.is_some_and(|x| count_fields(fields) > x)
+}
+
+/// Keep the first `max_fields` of `lines` rendering one field each, e.g.
+/// `annotate` output, and summarize the rest
+pub fn limit_lines(mut lines: Vec<String>, max_fields: Option<usize>) -> Vec<String> {
+    if let Some(max_fields) = max_fields.filter(|&x| x < lines.len()) {
+        let omitted = lines.len() - max_fields;
+        lines.truncate(max_fields);
+        lines.push(omitted_line(omitted));
+    }
+    lines
+}
+
+/// Render `fields` in protoscope syntax, stopping at the first top-level field
+/// whose nested fields don't fit in what is left of `max_fields`
+pub fn render_protoscope(fields: &[Box<dyn FieldTrait>], max_fields: Option<usize>) -> Vec<String> {
+    let mut budget = max_fields.unwrap_or(usize::MAX);
+    let mut lines = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let count = count_fields(&fields[i..=i]);
+        if count > budget {
+            lines.push(omitted_line(count_fields(&fields[i..])));
+            break;
+        }
+        budget -= count;
+        lines.push(field.to_protoscope());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use protodec_rs::parser::parser::{FullParser, Parser};
+    use protodec_rs::proto::utils::serialize_varint;
+
+    #[test]
+    fn test_color_never() {
+        // field 1 = 150, field 2 = {1: 1}
+        let buffer = [0x08, 0x96, 0x01, 0x12, 0x02, 0x08, 0x01];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+
+        for field in message.fields.iter() {
+            let rendered = render_repr(&field.repr(), color_enabled(ColorMode::Never));
+            assert_eq!(rendered, field.repr());
+            assert!(!rendered.contains('\x1b'));
+        }
+    }
+
+    #[test]
+    fn test_color_always() {
+        colored::control::set_override(true);
+        let line = "\t0x1 Optional <FieldType::Int32[0] == VariantTypeRaw::Varint[0]> = 0x96";
+
+        let rendered = render_repr(line, color_enabled(ColorMode::Always));
+        assert!(rendered.starts_with("\t\x1b["));
+        assert!(rendered.contains("0x96"));
+    }
+
+    #[test]
+    fn test_render_fields_truncated() {
+        // field 1 = 150, field 2 = {1: 1, 2: 2}, field 3 = 3
+        let buffer = [
+            0x08, 0x96, 0x01, 0x12, 0x04, 0x08, 0x01, 0x10, 0x02, 0x18, 0x03,
+        ];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+        let options = RenderOptions::default();
+
+        let lines = render_fields(&message.fields, &options, false, &Notes::default(), None);
+        assert_eq!(lines.len(), 3);
+
+        let lines = render_fields(&message.fields, &options, false, &Notes::default(), Some(1));
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], message.fields[0].repr());
+        assert_eq!(lines[1], "// ... (4 more fields omitted)");
+
+        // The embedded message is cut with the remaining budget
+        let lines = render_fields(&message.fields, &options, false, &Notes::default(), Some(3));
+        assert_eq!(lines.len(), 3);
+        let nested: Vec<&str> = lines[1].split('\n').collect();
+        assert_eq!(nested.len(), 3);
+        assert_eq!(nested[2], "\t// ... (1 more field omitted)");
+        assert_eq!(lines[2], "// ... (1 more field omitted)");
+
+        let lines = render_fields(&message.fields, &options, false, &Notes::default(), Some(4));
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], message.fields[1].repr());
+        assert_eq!(lines[2], "// ... (1 more field omitted)");
+    }
+
+    #[test]
+    fn test_render_protoscope_truncated() {
+        // field 1 = 150, field 2 = {1: 1, 2: 2}, field 3 = 3
+        let buffer = [
+            0x08, 0x96, 0x01, 0x12, 0x04, 0x08, 0x01, 0x10, 0x02, 0x18, 0x03,
+        ];
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+
+        let lines = render_protoscope(&message.fields, None);
+        assert_eq!(lines.join("\n"), message.to_protoscope());
+        assert!(!is_truncated(&message.fields, Some(5)));
+
+        // The embedded message doesn't fit, nothing after it is rendered
+        assert!(is_truncated(&message.fields, Some(3)));
+        let lines = render_protoscope(&message.fields, Some(3));
+        assert_eq!(lines, ["1: 150", "// ... (4 more fields omitted)"]);
+
+        let lines = limit_lines(vec!["a".to_string(), "b".to_string()], Some(1));
+        assert_eq!(lines, ["a", "// ... (1 more field omitted)"]);
+    }
+
+    #[test]
+    fn test_render_hints() {
+        // field 1 = 150, field 2 = {1: 1, 2: 1622505600}
+        let mut buffer = vec![0x08, 0x96, 0x01, 0x12, 0x08, 0x08, 0x01, 0x10];
+        buffer.extend(serialize_varint(1622505600));
+        let message = FullParser::new().deserialize(&buffer).unwrap();
+        let options = RenderOptions::default();
+
+        let plain = render_fields(&message.fields, &options, false, &Notes::default(), None);
+        let expected: Vec<String> = message.fields.iter().map(|x| x.repr()).collect();
+        assert_eq!(plain, expected);
+
+        let notes = Notes {
+            hints: true,
+            ..Notes::default()
+        };
+        let lines = render_fields(&message.fields, &options, false, &notes, None);
+        assert_eq!(lines[0], message.fields[0].repr());
+        let nested: Vec<&str> = lines[1].split('\n').collect();
+        assert_eq!(nested.len(), 3);
+        assert!(!nested[0].contains("maybe"));
+        assert!(!nested[1].contains("maybe"));
+        assert!(nested[2].ends_with(" // maybe timestamp: 2021-06-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn test_render_raw() {
+        // field 1 = 150, field 2 = {1: 1, 2: "ab"}
+        let buffer = [
+            0x08, 0x96, 0x01, 0x12, 0x06, 0x08, 0x01, 0x12, 0x02, 0x61, 0x62,
+        ];
+        let (message, spans) = FullParser::new().deserialize_with_spans(&buffer).unwrap();
+        let options = RenderOptions::default();
+        let notes = Notes {
+            raw: Some((&buffer, &spans)),
+            ..Notes::default()
+        };
+
+        let lines = render_fields(&message.fields, &options, false, &notes, None);
+        assert_eq!(
+            lines[0],
+            format!("{} // 08 96 01", message.fields[0].repr())
+        );
+        let nested: Vec<&str> = lines[1].split('\n').collect();
+        assert!(nested[0].ends_with(" // 12 06"));
+        assert!(nested[1].ends_with(" // 08 01"));
+        assert!(nested[2].ends_with(" // 12 02 61 62"));
+    }
+}
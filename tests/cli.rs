@@ -0,0 +1,131 @@
+#![cfg(feature = "cli")]
+
+use std::process::Command;
+
+/// Run the command line tool on `data` written to a file named `name`
+fn run(name: &str, data: &[u8], args: &[&str]) -> std::process::Output {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, data).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_protodec-rs"))
+        .arg("--file")
+        .arg(&path)
+        .arg("--quiet")
+        .args(args)
+        .output()
+        .expect("Failed to run protodec-rs")
+}
+
+#[test]
+fn empty_input() {
+    let output = run("empty.bin", &[], &[]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "// empty message\n"
+    );
+}
+
+#[test]
+fn parser_variants() {
+    // field 1 = 150, field 2 = "ab", then bytes which are not a field
+    let data = [0x08, 0x96, 0x01, 0x12, 0x02, 0x61, 0x62, 0xff, 0xff];
+
+    let output = run("trailing.bin", &data, &["--parser", "full"]);
+    assert!(!output.status.success());
+
+    let output = run("trailing.bin", &data, &["--parser", "partial"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("int32 param0 = 1; // Example: 150"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("string param1 = 2; // Example: \"ab\""),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn scan_ranges() {
+    // Garbage, then field 1 = 150 and field 2 = {1: "ab"}, then garbage
+    let data = [
+        0xff, 0xff, 0xff, 0x08, 0x96, 0x01, 0x12, 0x04, 0x0a, 0x02, 0x61, 0x62, 0xff,
+    ];
+    let output = run("scan.bin", &data, &["--scan", "--min-fields", "2"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().next(),
+        Some("[0x3..0xc] 2 fields: int32, message")
+    );
+}
+
+#[test]
+fn hex_data() {
+    // field 1 = 150
+    let from_file = run("hex.bin", &[0x08, 0x96, 0x01], &[]);
+    assert!(from_file.status.success());
+
+    for hex in [
+        "089601",
+        "08 96 01",
+        "08,96,01",
+        "0x08,0x96,0x01",
+        "0x8,0x96,0x1",
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_protodec-rs"))
+            .args(["--data", hex, "--quiet"])
+            .output()
+            .expect("Failed to run protodec-rs");
+        assert!(output.status.success(), "{}", hex);
+        assert_eq!(output.stdout, from_file.stdout, "{}", hex);
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_protodec-rs"))
+        .args(["--data", "089", "--quiet"])
+        .output()
+        .expect("Failed to run protodec-rs");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn max_fields() {
+    // field 1 = 1, 2, 3, 4
+    let data = [0x08, 0x01, 0x08, 0x02, 0x08, 0x03, 0x08, 0x04];
+
+    for format in ["text", "annotated", "protoscope"] {
+        let output = run(
+            "max_fields.bin",
+            &data,
+            &["--max-fields", "2", "--format", format, "--color", "never"],
+        );
+        assert!(output.status.success(), "{}", format);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut sections = stdout.split("data[");
+
+        // Decoded fields are cut and the .proto definition is left out
+        let decoded = sections.next().unwrap();
+        assert!(!decoded.contains("Example"), "{}: {}", format, decoded);
+        assert!(!decoded.contains("= 0x4"), "{}: {}", format, decoded);
+        assert!(!decoded.contains("1: 4"), "{}: {}", format, decoded);
+        assert!(
+            decoded.contains("// ... (2 more fields omitted)"),
+            "{}: {}",
+            format,
+            decoded
+        );
+
+        // So is every partial parse
+        for section in sections {
+            let fields = section.lines().filter(|x| x.starts_with("0x")).count();
+            assert!(fields <= 2, "{}: {}", format, section);
+        }
+    }
+
+    let output = run("max_fields.bin", &data, &["--max-fields", "4"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("repeated int32 param0 = 1;"), "{}", stdout);
+}
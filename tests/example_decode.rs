@@ -0,0 +1,20 @@
+#![cfg(feature = "serde")]
+
+use protodec_rs::parser::parser::{FullParser, Parser};
+use protodec_rs::proto::utils::parse_hex;
+
+/// Steps of `examples/decode.rs`, run through the library instead of a nested
+/// `cargo run`
+#[test]
+fn example_decodes_hex_arguments() {
+    let data = parse_hex("08 96 01 12 02 61 62").unwrap();
+    let message = FullParser::new().deserialize(&data).unwrap();
+    assert_eq!(
+        serde_json::to_string(&message).unwrap(),
+        concat!(
+            r#"{"name":"Generated","syntax":"proto3","fields":["#,
+            r#"{"number":1,"type":"int32","value":150},"#,
+            r#"{"number":2,"type":"string","value":"ab"}]}"#
+        )
+    );
+}
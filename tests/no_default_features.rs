@@ -0,0 +1,32 @@
+use std::process::Command;
+
+fn cargo(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env(
+            "CARGO_TARGET_DIR",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/target/no-default-features"),
+        )
+        .output()
+        .expect("Failed to run cargo")
+}
+
+/// Builds the crate again in its own target directory, run it with
+/// `cargo test --test no_default_features -- --ignored`
+#[test]
+#[ignore = "spawns a nested cargo build"]
+fn library_builds_without_default_features() {
+    let build = cargo(&["build", "--lib", "--no-default-features"]);
+    assert!(
+        build.status.success(),
+        "{}",
+        String::from_utf8_lossy(&build.stderr)
+    );
+
+    let tree = cargo(&["tree", "--no-default-features", "--edges", "normal"]);
+    assert!(tree.status.success());
+    let tree = String::from_utf8_lossy(&tree.stdout);
+    assert!(!tree.contains("log4rs"), "{}", tree);
+    assert!(!tree.contains("clap"), "{}", tree);
+}
@@ -0,0 +1,100 @@
+//! Messages encoded by prost decode to the same field numbers and wire types
+
+use prost::Message as _;
+use protodec_rs::parser::parser::{FullParser, Parser};
+use protodec_rs::proto::field::{
+    fields_to_str_with, FieldExt, FieldTrait, RenderOptions, VariantTypeRaw,
+};
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Inner {
+    #[prost(int32, tag = "1")]
+    id: i32,
+    #[prost(string, tag = "2")]
+    label: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Outer {
+    #[prost(uint64, tag = "1")]
+    count: u64,
+    #[prost(string, tag = "2")]
+    name: String,
+    #[prost(message, optional, tag = "3")]
+    inner: Option<Inner>,
+    #[prost(int32, repeated, packed = "true", tag = "4")]
+    values: Vec<i32>,
+    #[prost(sint64, tag = "5")]
+    delta: i64,
+    #[prost(fixed32, tag = "6")]
+    flags: u32,
+}
+
+fn layout(fields: &[Box<dyn FieldTrait>]) -> Vec<(u64, VariantTypeRaw)> {
+    fields
+        .iter()
+        .map(|x| (x.number(), x.field_type().wire_type()))
+        .collect()
+}
+
+#[test]
+fn decodes_prost_encoding() {
+    let outer = Outer {
+        count: 150,
+        name: "protodec".to_string(),
+        inner: Some(Inner {
+            id: 7,
+            label: "nested".to_string(),
+        }),
+        values: vec![1, 2, 300],
+        delta: -2,
+        flags: 0x0102_0304,
+    };
+    let encoded = outer.encode_to_vec();
+
+    let mut message = FullParser::new().deserialize(&encoded).unwrap();
+    assert_eq!(
+        layout(&message.fields),
+        [
+            (1, VariantTypeRaw::Varint),
+            (2, VariantTypeRaw::Buffer),
+            (3, VariantTypeRaw::Buffer),
+            (4, VariantTypeRaw::Buffer),
+            (5, VariantTypeRaw::Varint),
+            (6, VariantTypeRaw::Float),
+        ]
+    );
+    assert_eq!(message.serialize(), encoded);
+
+    assert_eq!(message.fields[1].as_string().unwrap().0.data, "protodec");
+    let inner = message.fields[2].as_embedded().unwrap();
+    assert_eq!(
+        layout(&inner.field.data.fields),
+        [(1, VariantTypeRaw::Varint), (2, VariantTypeRaw::Buffer)]
+    );
+    assert_eq!(inner.field.data.fields[0].example(), "7");
+
+    let options = RenderOptions {
+        detect_packed: true,
+        ..RenderOptions::default()
+    };
+    let lines = fields_to_str_with(&message.fields, &options);
+    assert!(lines[3].ends_with("= 4; // Example: [1, 2, 300] // packed"));
+}
+
+#[test]
+fn decodes_prost_unpacked_repeated() {
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Unpacked {
+        #[prost(int32, repeated, packed = "false", tag = "1")]
+        values: Vec<i32>,
+    }
+    let encoded = Unpacked {
+        values: vec![1, 2, 3],
+    }
+    .encode_to_vec();
+
+    let message = FullParser::new().deserialize(&encoded).unwrap();
+    assert_eq!(layout(&message.fields), [(1, VariantTypeRaw::Varint); 3]);
+    assert_eq!(message.serialize(), encoded);
+}